@@ -1,10 +1,22 @@
 mod bases;
 mod entity;
+mod guild;
+mod login;
+mod party;
+mod reload;
+mod sanctions;
 mod storage;
+#[cfg(test)]
+mod tests;
 mod world;
 
 pub use bases::*;
 pub use entity::*;
+pub use guild::*;
+pub use login::*;
+pub use party::*;
+pub use reload::*;
+pub use sanctions::*;
 pub use storage::*;
 pub use world::*;
 
@@ -1,9 +1,17 @@
-use crate::{containers::IndexMap, gametypes::*, items::*, maps::*, npcs::*};
+use crate::{
+    containers::IndexMap, gametypes::*, items::*, maps::*, npcs::*, progression::*, quests::*,
+    skills::*,
+};
+
+#[derive(Clone)]
 pub struct Bases {
     pub maps: IndexMap<MapPosition, Map>,
     pub npcs: Vec<NpcData>,
     pub items: Vec<ItemData>,
     pub shops: Vec<ShopData>,
+    pub quests: Vec<QuestData>,
+    pub skills: Vec<SkillData>,
+    pub progression: Progression,
 }
 
 impl Bases {
@@ -13,6 +21,30 @@ impl Bases {
             npcs: vec![NpcData::default(); MAX_NPCS],
             items: vec![ItemData::default(); MAX_ITEMS],
             shops: vec![ShopData::default(); MAX_SHOPS],
+            quests: vec![QuestData::default(); MAX_QUESTS],
+            skills: vec![SkillData::default(); MAX_SKILLS],
+            progression: Progression::default(),
         })
     }
+
+    /// The item with this id if one was loaded. Padding entries have no name.
+    pub fn item(&self, num: usize) -> Option<&ItemData> {
+        self.items.get(num).filter(|item| !item.name.is_empty())
+    }
+
+    /// The npc with this id if one was loaded. Padding entries have no name.
+    pub fn npc(&self, index: usize) -> Option<&NpcData> {
+        self.npcs.get(index).filter(|npc| !npc.name.is_empty())
+    }
+}
+
+/// Pads loaded data out to its max so lookups by index stay valid.
+pub fn fill_base<T: Default + Clone>(data: Vec<T>, max: usize) -> Vec<T> {
+    let mut base = vec![T::default(); max];
+
+    for (index, entry) in data.into_iter().enumerate().take(max) {
+        base[index] = entry;
+    }
+
+    base
 }
@@ -1,5 +1,8 @@
 use super::{CombatData, MovementData, Sprite};
-use crate::gametypes::Position;
+use crate::{
+    containers::{GlobalKey, HashMap},
+    gametypes::Position,
+};
 use educe::Educe;
 use mmap_bytey::{MByteBufferRead, MByteBufferWrite};
 use serde::{Deserialize, Serialize};
@@ -26,6 +29,8 @@ pub struct NpcEntity {
     // Combat
     pub combat: CombatData,
     pub hit_by: NpcHitBy,
+    pub skill_cooldowns: NpcSkillCooldowns,
+    pub support: NpcSupportTarget,
 
     // Timer
     pub timer: NpcTimer,
@@ -65,6 +70,14 @@ pub struct NpcHitBy(#[educe(Default = Vec::new())] pub Vec<(u32, u64, u64)>);
 #[educe(Default)]
 pub struct NpcMoves(#[educe(Default = VecDeque::new())] pub VecDeque<(Position, u8)>);
 
+//when each of the npc's skills can be cast again.
+#[derive(Default, Debug, Clone)]
+pub struct NpcSkillCooldowns(pub HashMap<u16, Instant>);
+
+//the hurt ally a healer wants to cast its heals on.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NpcSupportTarget(pub Option<GlobalKey>);
+
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
 pub struct NpcSpawnedZone(pub Option<usize>);
 
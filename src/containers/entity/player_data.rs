@@ -1,9 +1,10 @@
 use super::{CombatData, MovementData};
 use crate::{
-    containers::{GlobalKey, HashSet},
+    containers::{GlobalKey, HashMap, HashSet, IndexMap, PartyKey},
     gametypes::*,
     items::Item,
 };
+use chrono::{DateTime, Duration, Utc};
 use educe::Educe;
 use mmap_bytey::{MByteBufferRead, MByteBufferWrite};
 use serde::{Deserialize, Serialize};
@@ -35,6 +36,7 @@ pub struct PlayerEntity {
 
     // Combat
     pub combat: CombatData,
+    pub attributes: Attributes,
 
     // Items
     pub inventory: Inventory,
@@ -46,16 +48,26 @@ pub struct PlayerEntity {
     pub trade_status: TradeStatus,
     pub trade_request_entity: TradeRequestEntity,
 
+    pub party: PlayerParty,
+    pub guild: PlayerGuild,
+    pub quests: PlayerQuests,
+    pub skills: PlayerSkills,
+
     // Timer
     pub item_timer: PlayerItemTimer,
     pub map_timer: PlayerMapTimer,
+    pub mute_timer: PlayerMuteTimer,
+    pub pvp_timer: PlayerPvpTimer,
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct Account {
+    /// Name of the active character, which is what other players see.
     pub username: String,
     pub passresetcode: Option<String>,
     pub id: Uuid,
+    /// The active character. Per character data is saved under this id.
+    pub character: Uuid,
 }
 
 #[derive(Copy, Clone, Debug, Educe)]
@@ -110,6 +122,43 @@ pub struct PlayerMapTimer {
     pub mapitemtimer: Instant,
 }
 
+#[derive(Copy, Clone, Debug, Educe)]
+#[educe(Default)]
+pub struct PlayerPvpTimer {
+    /// Pvp can not be turned on or off again until this time.
+    #[educe(Default = Instant::recent())]
+    pub toggle_timer: Instant,
+    /// Pk status wears off at this time.
+    #[educe(Default = Instant::recent())]
+    pub pk_timer: Instant,
+}
+
+/// Chat is blocked until this time. None means the player is not muted
+/// unless the mute is permanent.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PlayerMuteTimer {
+    pub muted_until: Option<Instant>,
+    pub permanent: bool,
+}
+
+impl PlayerMuteTimer {
+    pub fn is_muted(&self, tick: Instant) -> bool {
+        self.permanent || self.muted_until.is_some_and(|until| until > tick)
+    }
+
+    /// Sets the mute from a database expiry where None is permanent.
+    pub fn set(&mut self, expires_on: Option<DateTime<Utc>>, tick: Instant) {
+        self.permanent = expires_on.is_none();
+        self.muted_until =
+            expires_on.map(|expires_on| tick + (expires_on - Utc::now()).max(Duration::zero()));
+    }
+
+    pub fn clear(&mut self) {
+        self.muted_until = None;
+        self.permanent = false;
+    }
+}
+
 #[derive(
     PartialEq, Eq, Clone, Debug, Educe, Deserialize, Serialize, MByteBufferRead, MByteBufferWrite,
 )]
@@ -161,6 +210,88 @@ pub struct TradeRequestEntity {
     pub requesttimer: Instant,
 }
 
+#[derive(Copy, Clone, Debug, Educe)]
+#[educe(Default)]
+pub struct PlayerParty {
+    #[educe(Default = None)]
+    pub party: Option<PartyKey>,
+    /// The player who last invited us and when that invite runs out.
+    #[educe(Default = None)]
+    pub invite: Option<GlobalKey>,
+    #[educe(Default = Instant::recent())]
+    pub invite_timer: Instant,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GuildMembership {
+    pub id: Uuid,
+    pub tag: String,
+    pub rank: i16,
+}
+
+#[derive(Clone, Debug, Educe)]
+#[educe(Default)]
+pub struct PlayerGuild {
+    #[educe(Default = None)]
+    pub membership: Option<GuildMembership>,
+    /// The guild we were last invited to and when that invite runs out.
+    #[educe(Default = None)]
+    pub invite: Option<Uuid>,
+    #[educe(Default = Instant::recent())]
+    pub invite_timer: Instant,
+}
+
+impl PlayerGuild {
+    pub fn guild_id(&self) -> Option<Uuid> {
+        self.membership.as_ref().map(|membership| membership.id)
+    }
+
+    pub fn tag(&self) -> Option<String> {
+        self.membership
+            .as_ref()
+            .map(|membership| membership.tag.clone())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PlayerQuests {
+    /// Progress of each objective for the quests being worked on, keyed by quest id.
+    pub active: IndexMap<u16, Vec<u16>>,
+    /// How many times each quest has been completed.
+    pub completed: HashMap<u16, u32>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Attributes {
+    /// Points spent on each attribute, indexed by AttributeType.
+    pub values: [i32; ATTRIBUTES_MAX],
+    /// Points earned from levelling that have not been spent yet.
+    pub points: i32,
+}
+
+impl Attributes {
+    pub fn get(&self, attribute: AttributeType) -> i32 {
+        self.values
+            .get(attribute as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PlayerSkills {
+    /// Skill ids placed in each hotbar slot.
+    pub hotbar: [Option<u16>; MAX_HOTBAR],
+    /// When each skill can next be used, keyed by skill id.
+    pub cooldowns: HashMap<u16, Instant>,
+}
+
+impl PlayerQuests {
+    pub fn times_completed(&self, quest: u16) -> u32 {
+        self.completed.get(&quest).copied().unwrap_or(0)
+    }
+}
+
 #[derive(
     PartialEq, Eq, Clone, Debug, Educe, Deserialize, Serialize, MByteBufferRead, MByteBufferWrite,
 )]
@@ -275,6 +406,8 @@ impl IsUsingType {
     Default,
     MByteBufferRead,
     MByteBufferWrite,
+    PartialOrd,
+    Ord,
     sqlx::Type,
 )]
 #[sqlx(type_name = "user_access")]
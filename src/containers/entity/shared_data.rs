@@ -1,6 +1,6 @@
 use crate::{
     containers::GlobalKey,
-    gametypes::{MapPosition, Position, VITALS_MAX},
+    gametypes::{MapPosition, Position, StatusKind, VITALS_MAX},
 };
 use educe::Educe;
 use mmap_bytey::{MByteBufferRead, MByteBufferWrite};
@@ -37,6 +37,7 @@ pub struct CombatData {
     pub death_timer: DeathTimer,
     pub combat_timer: CombatTimer,
     pub physical: Physical,
+    pub effects: StatusEffects,
 }
 
 #[derive(Educe, Debug, Copy, Clone, PartialEq, Eq, MByteBufferWrite, MByteBufferRead)]
@@ -52,6 +53,65 @@ pub struct Vitals {
     pub regens: [u32; VITALS_MAX],
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub power: i32,
+    pub stacks: u8,
+    pub expires: Instant,
+    pub next_tick: Instant,
+    //who applied it, so poison kills are credited to them.
+    pub source: Option<GlobalKey>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatusEffects(pub Vec<StatusEffect>);
+
+impl StatusEffects {
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.0.iter().any(|effect| effect.kind == kind)
+    }
+
+    /// Combined power of every stack of the effect or 0 if it is not active.
+    pub fn power(&self, kind: StatusKind) -> i32 {
+        self.0
+            .iter()
+            .find(|effect| effect.kind == kind)
+            .map(|effect| effect.power.saturating_mul(effect.stacks as i32))
+            .unwrap_or_default()
+    }
+
+    /// Reapplying an active effect adds a stack up to the kind's limit, keeps the
+    /// strongest power and refreshes the duration if the new one lasts longer.
+    pub fn apply(&mut self, effect: StatusEffect) {
+        if let Some(active) = self.0.iter_mut().find(|active| active.kind == effect.kind) {
+            active.stacks = active
+                .stacks
+                .saturating_add(1)
+                .min(effect.kind.max_stacks());
+            active.power = active.power.max(effect.power);
+
+            if effect.expires > active.expires {
+                active.expires = effect.expires;
+            }
+
+            if effect.source.is_some() {
+                active.source = effect.source;
+            }
+        } else {
+            self.0.push(effect);
+        }
+    }
+
+    /// Returns true if any effects ran out.
+    pub fn remove_expired(&mut self, tick: Instant) -> bool {
+        let len = self.0.len();
+
+        self.0.retain(|effect| effect.expires > tick);
+        self.0.len() != len
+    }
+}
+
 #[derive(Educe, Debug, Copy, Clone, PartialEq, Eq)]
 #[educe(Default)]
 pub struct AttackTimer(#[educe(Default = Instant::recent())] pub Instant);
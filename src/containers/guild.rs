@@ -0,0 +1,88 @@
+use uuid::Uuid;
+
+/// Rank given to whoever created or was handed the guild.
+pub const GUILD_LEADER_RANK: i16 = 0;
+
+/// Bit set of what a guild rank is allowed to do. Stored as an integer in the database.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub struct GuildPermissions(pub i32);
+
+impl GuildPermissions {
+    pub const NONE: GuildPermissions = GuildPermissions(0);
+    pub const INVITE: GuildPermissions = GuildPermissions(1);
+    pub const KICK: GuildPermissions = GuildPermissions(1 << 1);
+    pub const SET_RANK: GuildPermissions = GuildPermissions(1 << 2);
+    pub const SET_MOTD: GuildPermissions = GuildPermissions(1 << 3);
+    pub const ALL: GuildPermissions = GuildPermissions(0b1111);
+
+    pub fn contains(self, other: GuildPermissions) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn union(self, other: GuildPermissions) -> GuildPermissions {
+        GuildPermissions(self.0 | other.0)
+    }
+}
+
+/// Lower ranks are higher up in the guild. Rank 0 is the leader.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GuildRank {
+    pub rank: i16,
+    pub name: String,
+    pub permissions: GuildPermissions,
+}
+
+impl GuildRank {
+    pub fn new(rank: i16, name: &str, permissions: GuildPermissions) -> Self {
+        Self {
+            rank,
+            name: name.into(),
+            permissions,
+        }
+    }
+}
+
+/// Ranks every new guild starts with.
+pub fn default_guild_ranks() -> Vec<GuildRank> {
+    vec![
+        GuildRank::new(GUILD_LEADER_RANK, "Leader", GuildPermissions::ALL),
+        GuildRank::new(
+            1,
+            "Officer",
+            GuildPermissions::INVITE
+                .union(GuildPermissions::KICK)
+                .union(GuildPermissions::SET_MOTD),
+        ),
+        GuildRank::new(2, "Member", GuildPermissions::NONE),
+    ]
+}
+
+/// Guild data cached while any of its members are online.
+#[derive(Clone, Debug)]
+pub struct Guild {
+    pub id: Uuid,
+    pub name: String,
+    pub tag: String,
+    pub motd: String,
+    pub ranks: Vec<GuildRank>,
+}
+
+impl Guild {
+    pub fn get_rank(&self, rank: i16) -> Option<&GuildRank> {
+        self.ranks.iter().find(|data| data.rank == rank)
+    }
+
+    pub fn has_permission(&self, rank: i16, permission: GuildPermissions) -> bool {
+        self.get_rank(rank)
+            .is_some_and(|data| data.permissions.contains(permission))
+    }
+
+    /// The rank new members join at.
+    pub fn lowest_rank(&self) -> i16 {
+        self.ranks
+            .iter()
+            .map(|data| data.rank)
+            .max()
+            .unwrap_or(GUILD_LEADER_RANK)
+    }
+}
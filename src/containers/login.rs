@@ -0,0 +1,79 @@
+use crate::{
+    containers::{HashMap, socket_ip},
+    gametypes::*,
+    sql::get_time_left,
+};
+use chrono::Duration;
+use time::Instant;
+use uuid::Uuid;
+
+pub struct LoginAttempts {
+    pub failures: u32,
+    pub blocked_until: Instant,
+    pub last_attempt: Instant,
+}
+
+/// Failed logins per ip. Once an ip runs out of free attempts it has to wait between
+/// tries, with the wait doubling on every further failure. Client addresses are passed
+/// in with their port, which is dropped so reconnecting does not start over.
+#[derive(Default)]
+pub struct LoginThrottle(pub HashMap<String, LoginAttempts>);
+
+/// The ip of the address, or the whole address if it does not parse.
+fn throttle_key(addr: &str) -> String {
+    socket_ip(addr).map_or_else(|| addr.to_string(), |ip| ip.to_string())
+}
+
+impl LoginThrottle {
+    /// Milliseconds the address still has to wait before it may try again.
+    pub fn wait_left(&self, addr: &str, tick: Instant) -> i64 {
+        self.0
+            .get(&throttle_key(addr))
+            .map(|attempts| get_time_left(attempts.blocked_until, tick))
+            .unwrap_or_default()
+    }
+
+    /// Counts a failure and returns how long the address now has to wait, if at all.
+    pub fn failed(&mut self, addr: &str, tick: Instant) -> Option<i64> {
+        let attempts = self.0.entry(throttle_key(addr)).or_insert(LoginAttempts {
+            failures: 0,
+            blocked_until: tick,
+            last_attempt: tick,
+        });
+
+        attempts.failures += 1;
+        attempts.last_attempt = tick;
+
+        if attempts.failures < LOGIN_FREE_ATTEMPTS {
+            return None;
+        }
+
+        let shift = (attempts.failures - LOGIN_FREE_ATTEMPTS).min(16);
+        let wait = LOGIN_BACKOFF_MS
+            .saturating_mul(1 << shift)
+            .min(LOGIN_BACKOFF_MAX_MS);
+
+        attempts.blocked_until = tick + Duration::try_milliseconds(wait).unwrap_or_default();
+
+        Some(wait)
+    }
+
+    pub fn succeeded(&mut self, addr: &str) {
+        self.0.remove(&throttle_key(addr));
+    }
+
+    /// Forgets addresses that are no longer waiting and have not tried in a while.
+    pub fn prune(&mut self, tick: Instant) {
+        let window = Duration::try_milliseconds(LOGIN_ATTEMPT_WINDOW_MS).unwrap_or_default();
+
+        self.0.retain(|_, attempts| {
+            attempts.blocked_until > tick || attempts.last_attempt + window > tick
+        });
+    }
+}
+
+/// An account that has logged in and is still picking a character.
+pub struct CharacterSelect {
+    pub uid: Uuid,
+    pub reconnect_code: String,
+}
@@ -0,0 +1,36 @@
+use super::{GlobalKey, IndexMap, Vitals};
+use crate::gametypes::*;
+use slotmap::new_key_type;
+
+new_key_type! {
+    pub struct PartyKey;
+}
+
+/// A group of players sharing experience, party chat and each other's vitals.
+#[derive(Clone, Debug)]
+pub struct Party {
+    pub leader: GlobalKey,
+    /// Members in the order they joined along with the vitals last sent to the party.
+    pub members: IndexMap<GlobalKey, Option<Vitals>>,
+}
+
+impl Party {
+    pub fn new(leader: GlobalKey) -> Self {
+        let mut members = IndexMap::default();
+        members.insert(leader, None);
+
+        Self { leader, members }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.members.len() >= MAX_PARTY_SIZE
+    }
+
+    pub fn contains(&self, entity: GlobalKey) -> bool {
+        self.members.contains_key(&entity)
+    }
+
+    pub fn member_list(&self) -> Vec<GlobalKey> {
+        self.members.keys().copied().collect()
+    }
+}
@@ -0,0 +1,509 @@
+use super::{
+    Bases, DeathType, Entity, EntityKind, GlobalKey, HashSet, IndexMap, Storage, World, fill_base,
+};
+use crate::{
+    gametypes::*,
+    items::{get_item, get_shop},
+    maps::{Map, MapAttribute, MapData, get_maps},
+    npcs::get_npc,
+    players::player_warp,
+    progression::{Progression, get_progression},
+    quests::{QuestObjective, get_quest},
+    skills::{SkillArea, SkillEffect, get_skill},
+    socket::{send_attributes, send_level},
+    tasks::{DataTaskToken, vitals_packet},
+};
+use log::info;
+use std::cell::RefCell;
+
+/// Which Bases tables to reload from the data folder.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReloadKind {
+    Items,
+    Npcs,
+    Shops,
+    Quests,
+    Skills,
+    Progression,
+    Maps,
+    All,
+}
+
+impl ReloadKind {
+    pub fn parse(name: &str) -> Option<ReloadKind> {
+        match name.to_lowercase().as_str() {
+            "items" | "item" => Some(ReloadKind::Items),
+            "npcs" | "npc" => Some(ReloadKind::Npcs),
+            "shops" | "shop" => Some(ReloadKind::Shops),
+            "quests" | "quest" => Some(ReloadKind::Quests),
+            "skills" | "skill" => Some(ReloadKind::Skills),
+            "progression" | "exp" => Some(ReloadKind::Progression),
+            "maps" | "map" => Some(ReloadKind::Maps),
+            "all" => Some(ReloadKind::All),
+            _ => None,
+        }
+    }
+
+    pub fn includes(&self, kind: ReloadKind) -> bool {
+        *self == ReloadKind::All || *self == kind
+    }
+}
+
+fn invalid(file: &str, message: String) -> AscendingError {
+    AscendingError::InvalidData {
+        file: file.into(),
+        message,
+    }
+}
+
+/// Checks the data references each other correctly before we swap it in.
+/// bases holds the padded tables as they would be after the reload, maps aside.
+fn validate_bases(bases: &Bases, maps: &IndexMap<MapPosition, Map>) -> Result<()> {
+    let (shops, quests, skills) = (&bases.shops, &bases.quests, &bases.skills);
+    let item_exists = |item: u32| bases.item(item as usize).is_some();
+    let npc_exists = |npc: u64| bases.npc(npc as usize).is_some();
+
+    for (id, item) in bases.items.iter().enumerate() {
+        if item.stackable && item.stacklimit == 0 {
+            return Err(invalid(
+                &format!("./data/items/{id}.bin"),
+                "stackable item has a stack limit of 0".into(),
+            ));
+        }
+    }
+
+    for (id, npc) in bases.npcs.iter().enumerate() {
+        let file = format!("./data/npcs/{id}.bin");
+
+        if npc.mindamage > npc.maxdamage {
+            return Err(invalid(&file, "mindamage is greater than maxdamage".into()));
+        }
+
+        for drop in npc.drops.iter().flat_map(|drop| drop.items.iter()) {
+            if drop.item > 0 && !item_exists(drop.item) {
+                return Err(invalid(
+                    &file,
+                    format!("drops item {} which does not exist", drop.item),
+                ));
+            }
+        }
+
+        for skill in &npc.skills {
+            if *skill as usize >= skills.len() {
+                return Err(invalid(
+                    &file,
+                    format!("casts skill {skill} which does not exist"),
+                ));
+            }
+        }
+    }
+
+    for (id, shop) in shops.iter().enumerate() {
+        let file = format!("./data/shops/{id}.bin");
+
+        if shop.max_item as usize > MAX_SHOP_ITEM {
+            return Err(invalid(&file, format!("max_item is over {MAX_SHOP_ITEM}")));
+        }
+
+        for shop_item in shop.item.iter().take(shop.max_item as usize) {
+            if !item_exists(shop_item.index as u32) {
+                return Err(invalid(
+                    &file,
+                    format!("sells item {} which does not exist", shop_item.index),
+                ));
+            }
+        }
+    }
+
+    for (id, quest) in quests.iter().enumerate() {
+        let file = format!("./data/quests/{id}.bin");
+
+        if let Some(prerequisite) = quest.prerequisite
+            && prerequisite as usize >= quests.len()
+        {
+            return Err(invalid(
+                &file,
+                format!("prerequisite quest {prerequisite} does not exist"),
+            ));
+        }
+
+        for npc in quest.giver.iter().chain(quest.turn_in.iter()) {
+            if !npc_exists(*npc) {
+                return Err(invalid(&file, format!("npc {npc} does not exist")));
+            }
+        }
+
+        for objective in &quest.objectives {
+            let valid = match *objective {
+                QuestObjective::None => true,
+                QuestObjective::Kill { npc, .. } | QuestObjective::Talk { npc } => npc_exists(npc),
+                QuestObjective::Collect { item, .. } => item_exists(item),
+                QuestObjective::Deliver { item, npc, .. } => item_exists(item) && npc_exists(npc),
+            };
+
+            if !valid {
+                return Err(invalid(
+                    &file,
+                    format!("objective {objective:?} uses data which does not exist"),
+                ));
+            }
+        }
+
+        for reward in &quest.items {
+            if !item_exists(reward.item) {
+                return Err(invalid(
+                    &file,
+                    format!("rewards item {} which does not exist", reward.item),
+                ));
+            }
+        }
+    }
+
+    for (id, skill) in skills.iter().enumerate() {
+        let file = format!("./data/skills/{id}.bin");
+
+        if skill.cooldown < 0 || skill.range < 0 || skill.mp_cost < 0 || skill.sp_cost < 0 {
+            return Err(invalid(
+                &file,
+                "cooldown, range and costs can not be negative".into(),
+            ));
+        }
+
+        if let SkillArea::Radius(radius) = skill.area
+            && radius < 0
+        {
+            return Err(invalid(&file, "area radius can not be negative".into()));
+        }
+
+        if let SkillEffect::Damage { min, max } | SkillEffect::Heal { min, max } = skill.effect
+            && min > max
+        {
+            return Err(invalid(&file, "effect min is greater than max".into()));
+        }
+
+        if let SkillEffect::Status {
+            power, duration, ..
+        } = skill.effect
+            && (power < 0 || duration <= 0)
+        {
+            return Err(invalid(
+                &file,
+                "status power can not be negative and duration must be above 0".into(),
+            ));
+        }
+    }
+
+    for (position, map) in maps {
+        let file = format!("map {position:?}");
+
+        if map.attribute.len() != MAP_MAX_X * MAP_MAX_Y
+            || map.dir_block.len() != MAP_MAX_X * MAP_MAX_Y
+        {
+            return Err(invalid(&file, "map tile count does not match".into()));
+        }
+
+        for attribute in &map.attribute {
+            match attribute {
+                MapAttribute::Shop(shop) if *shop as usize >= shops.len() => {
+                    return Err(invalid(&file, format!("shop {shop} does not exist")));
+                }
+                MapAttribute::ItemSpawn(data) if !item_exists(data.index) => {
+                    return Err(invalid(
+                        &file,
+                        format!("item {} does not exist", data.index),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        for (_, zone_npcs) in map.zones.iter() {
+            for npc in zone_npcs.iter().flatten() {
+                if !npc_exists(*npc) {
+                    return Err(invalid(&file, format!("zone npc {npc} does not exist")));
+                }
+            }
+        }
+
+        for spawns in map.zonespawns.iter() {
+            for (x, y) in spawns {
+                if *x as usize >= MAP_MAX_X || *y as usize >= MAP_MAX_Y {
+                    return Err(invalid(&file, format!("zone spawn {x},{y} is off the map")));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn map_layout_changed(old: &Map, new: &Map) -> bool {
+    old.attribute != new.attribute || old.dir_block != new.dir_block
+}
+
+impl Storage {
+    /// Loads the requested data from disk, checks it and then swaps it into Bases.
+    /// This must only be called between ticks as it needs mutable access to Storage.
+    pub fn reload_bases(&mut self, world: &mut World, kind: ReloadKind) -> Result<()> {
+        let items = if kind.includes(ReloadKind::Items) {
+            Some(get_item()?)
+        } else {
+            None
+        };
+        let npcs = if kind.includes(ReloadKind::Npcs) {
+            Some(get_npc()?)
+        } else {
+            None
+        };
+        let shops = if kind.includes(ReloadKind::Shops) {
+            Some(get_shop()?)
+        } else {
+            None
+        };
+        let quests = if kind.includes(ReloadKind::Quests) {
+            Some(get_quest()?)
+        } else {
+            None
+        };
+        let skills = if kind.includes(ReloadKind::Skills) {
+            Some(get_skill()?)
+        } else {
+            None
+        };
+        let progression = if kind.includes(ReloadKind::Progression) {
+            Some(get_progression()?)
+        } else {
+            None
+        };
+        let maps = if kind.includes(ReloadKind::Maps) {
+            let mut maps = IndexMap::default();
+
+            for map in get_maps()? {
+                maps.insert(map.position, map);
+            }
+
+            // Removing a map while entities could be standing on it is not supported.
+            for position in self.bases.maps.keys() {
+                if !maps.contains_key(position) {
+                    return Err(invalid(
+                        &format!("map {position:?}"),
+                        "map is missing from the new data".into(),
+                    ));
+                }
+            }
+
+            Some(maps)
+        } else {
+            None
+        };
+
+        let counts = [
+            items.as_ref().map(Vec::len),
+            npcs.as_ref().map(Vec::len),
+            shops.as_ref().map(Vec::len),
+            quests.as_ref().map(Vec::len),
+            skills.as_ref().map(Vec::len),
+        ];
+
+        // Padded the same way as the live tables so Bases::item and Bases::npc only find
+        // what was really loaded.
+        let mut next = Bases {
+            maps: IndexMap::default(),
+            items: items.map_or_else(|| self.bases.items.clone(), |v| fill_base(v, MAX_ITEMS)),
+            npcs: npcs.map_or_else(|| self.bases.npcs.clone(), |v| fill_base(v, MAX_NPCS)),
+            shops: shops.map_or_else(|| self.bases.shops.clone(), |v| fill_base(v, MAX_SHOPS)),
+            quests: quests.map_or_else(|| self.bases.quests.clone(), |v| fill_base(v, MAX_QUESTS)),
+            skills: skills.map_or_else(|| self.bases.skills.clone(), |v| fill_base(v, MAX_SKILLS)),
+            progression: Progression::default(),
+        };
+
+        validate_bases(&next, maps.as_ref().unwrap_or(&self.bases.maps))?;
+
+        let mut affected_maps = HashSet::default();
+        let [items, npcs, shops, quests, skills] = counts;
+
+        if let Some(count) = items {
+            info!("Reloaded {count} items");
+            self.bases.items = std::mem::take(&mut next.items);
+        }
+
+        if let Some(count) = shops {
+            info!("Reloaded {count} shops");
+            self.bases.shops = std::mem::take(&mut next.shops);
+        }
+
+        if let Some(count) = quests {
+            info!("Reloaded {count} quests");
+            self.bases.quests = std::mem::take(&mut next.quests);
+        }
+
+        if let Some(count) = skills {
+            info!("Reloaded {count} skills");
+            self.bases.skills = std::mem::take(&mut next.skills);
+        }
+
+        if let Some(progression) = progression {
+            info!("Reloaded progression up to level {}", progression.max_level);
+            self.bases.progression = progression;
+            self.refresh_players(world)?;
+        }
+
+        if let Some(count) = npcs {
+            info!("Reloaded {count} npcs");
+            self.bases.npcs = std::mem::take(&mut next.npcs);
+            self.refresh_npcs(world, &mut affected_maps)?;
+        }
+
+        if let Some(maps) = maps {
+            info!("Reloaded {} maps", maps.len());
+            self.swap_maps(world, maps, &mut affected_maps)?;
+        }
+
+        self.resend_maps(world, &affected_maps)
+    }
+
+    // Keeps live npcs within their new max hp and gives them their new regen.
+    fn refresh_npcs(
+        &self,
+        world: &mut World,
+        affected_maps: &mut HashSet<MapPosition>,
+    ) -> Result<()> {
+        for id in self.npc_ids.borrow().iter() {
+            if let Some(Entity::Npc(n_data)) = world.get_opt_entity(*id) {
+                let mut n_data = n_data.try_lock()?;
+                let base = &self.bases.npcs[n_data.index as usize];
+                let maxhp = base.maxhp as i32;
+                let hp = VitalTypes::Hp as usize;
+
+                n_data.combat.vitals.regens = base.regens;
+                n_data.combat.vitals.vitalmax[hp] = maxhp;
+                n_data.combat.vitals.vital[hp] = n_data.combat.vitals.vital[hp].min(maxhp);
+
+                affected_maps.insert(n_data.movement.pos.map);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Recalculates online players' stats and sends them their new vitals and exp.
+    fn refresh_players(&self, world: &mut World) -> Result<()> {
+        let players: Vec<GlobalKey> = self.player_ids.borrow().iter().copied().collect();
+
+        for entity in players {
+            let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) else {
+                continue;
+            };
+
+            let (map, vitals) = {
+                let p_data = &mut *p_data.try_lock()?;
+
+                self.bases
+                    .progression
+                    .apply(&mut p_data.combat, &p_data.attributes);
+
+                (p_data.movement.pos.map, p_data.combat.vitals)
+            };
+
+            send_level(world, self, entity)?;
+            send_attributes(world, self, entity)?;
+            DataTaskToken::Vitals(map)
+                .add_task(self, vitals_packet(entity, vitals.vital, vitals.vitalmax)?)?;
+        }
+
+        Ok(())
+    }
+
+    fn swap_maps(
+        &mut self,
+        world: &mut World,
+        maps: IndexMap<MapPosition, Map>,
+        affected_maps: &mut HashSet<MapPosition>,
+    ) -> Result<()> {
+        for (position, map) in maps {
+            let changed = match self.bases.maps.get(&position) {
+                Some(old) => map_layout_changed(old, &map),
+                None => {
+                    self.maps
+                        .insert(position, RefCell::new(MapData::from_map(&map)));
+                    false
+                }
+            };
+
+            if changed && let Some(map_data) = self.maps.get(&position) {
+                let mut data = map_data.borrow_mut();
+                data.build_grid(&map);
+
+                let players: Vec<GlobalKey> = data.players.iter().copied().collect();
+                let npcs: Vec<GlobalKey> = data.npcs.iter().copied().collect();
+
+                for entity in players {
+                    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+                        data.add_entity_to_grid(p_data.try_lock()?.movement.pos);
+                    }
+                }
+
+                for entity in npcs {
+                    if let Some(Entity::Npc(n_data)) = world.get_opt_entity(entity) {
+                        let mut n_data = n_data.try_lock()?;
+
+                        if n_data.combat.death_type != DeathType::Alive {
+                            continue;
+                        }
+
+                        // Npcs left standing inside a wall get removed and will respawn normally.
+                        if data.is_blocked_tile(n_data.movement.pos, EntityKind::Npc) {
+                            n_data.combat.death_type = DeathType::Dead;
+                            self.unload_npc.borrow_mut().push(entity);
+                        } else {
+                            data.add_entity_to_grid(n_data.movement.pos);
+                        }
+                    }
+                }
+
+                affected_maps.insert(position);
+            }
+
+            self.bases.maps.insert(position, map);
+        }
+
+        Ok(())
+    }
+
+    // Respawns every player on an affected map so they get the current data sent again.
+    fn resend_maps(&self, world: &mut World, affected_maps: &HashSet<MapPosition>) -> Result<()> {
+        let mut players = Vec::new();
+
+        for position in affected_maps {
+            if let Some(map_data) = self.maps.get(position) {
+                players.extend(map_data.borrow().players.iter().copied());
+            }
+        }
+
+        for entity in players {
+            let (pos, spawn) = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+                let p_data = p_data.try_lock()?;
+
+                (p_data.movement.pos, p_data.movement.spawn.pos)
+            } else {
+                continue;
+            };
+
+            let blocked = match self.bases.maps.get(&pos.map) {
+                Some(map) => matches!(
+                    map.attribute[pos.as_tile()],
+                    MapAttribute::Blocked | MapAttribute::Storage | MapAttribute::Shop(_)
+                ),
+                None => true,
+            };
+
+            if blocked {
+                player_warp(world, self, entity, &spawn, true)?;
+            } else {
+                player_warp(world, self, entity, &pos, true)?;
+            }
+        }
+
+        Ok(())
+    }
+}
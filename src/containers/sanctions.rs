@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+};
+
+/// A single address or a CIDR range such as 10.0.0.0/8. Host bits are cleared on parse.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct IpRange {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(range), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(range) == u32::from(ip) & mask
+            }
+            (IpAddr::V6(range), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(range) == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+
+        let addr = addr
+            .parse::<IpAddr>()
+            .map_err(|_| format!("{addr} is not an ip address"))?
+            .to_canonical();
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(|| format!("{prefix} is not a valid prefix length"))?,
+            None => max,
+        };
+
+        let addr = match addr {
+            IpAddr::V4(ip) => {
+                let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+                IpAddr::from((u32::from(ip) & mask).to_be_bytes())
+            }
+            IpAddr::V6(ip) => {
+                let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+                IpAddr::from((u128::from(ip) & mask).to_be_bytes())
+            }
+        };
+
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl fmt::Display for IpRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// The ip part of a client's address, which is stored with its port.
+pub fn socket_ip(addr: &str) -> Option<IpAddr> {
+    addr.parse::<SocketAddr>()
+        .map(|addr| addr.ip().to_canonical())
+        .ok()
+}
+
+/// Active ip bans kept in memory so new connections can be refused without a query.
+/// None as the expiry means the ban is permanent.
+#[derive(Default)]
+pub struct IpBans(pub Vec<(IpRange, Option<DateTime<Utc>>)>);
+
+impl IpBans {
+    pub fn is_banned(&self, ip: IpAddr) -> bool {
+        let now = Utc::now();
+
+        self.0.iter().any(|(range, expires)| {
+            range.contains(ip) && expires.is_none_or(|expires| expires > now)
+        })
+    }
+
+    /// Replaces any ban on exactly this range. Used so a new ban applies before the
+    /// next refresh from the database.
+    pub fn ban(&mut self, range: IpRange, expires: Option<DateTime<Utc>>) {
+        self.unban(range);
+        self.0.push((range, expires));
+    }
+
+    /// Drops the ban on exactly this range, returning whether it was still active.
+    pub fn unban(&mut self, range: IpRange) -> bool {
+        let now = Utc::now();
+        let mut active = false;
+
+        self.0.retain(|(banned, expires)| {
+            if *banned != range {
+                return true;
+            }
+
+            active |= expires.is_none_or(|expires| expires > now);
+            false
+        });
+
+        active
+    }
+}
@@ -1,14 +1,16 @@
 use super::{
-    CombatData, Entity, EntityKind, GlobalKey, HashSet, LoginHandShake, MovementData, NpcEntity,
-    NpcMode, NpcTimer, PlayerConnectionTimer, PlayerEntity, ReloginCode, Socket, Spawn, Vitals,
-    World,
+    CharacterSelect, CombatData, Entity, EntityKind, GlobalKey, Guild, HashSet, IpBans,
+    LoginHandShake, LoginThrottle, MovementData, NpcEntity, NpcMode, NpcTimer, Party, PartyKey,
+    PlayerConnectionTimer, PlayerEntity, ReloginCode, Socket, Spawn, Vitals, World, fill_base,
 };
 use crate::{
     containers::{Bases, HashMap, IndexMap, IndexSet},
     gametypes::*,
+    mail::{LocalMailer, Mailer},
     maps::*,
     npcs::*,
     socket::*,
+    sql::{LoginWorker, LookupWorker, SaveWorker, process_ip_bans, refresh_ip_bans},
     tasks::{DataTaskToken, MapSwitchTasks},
 };
 use chrono::Duration;
@@ -20,7 +22,7 @@ use rustls::{
     pki_types::{CertificateDer, PrivateKeyDer},
 };
 use serde::{Deserialize, Serialize};
-use slotmap::SecondaryMap;
+use slotmap::{SecondaryMap, SlotMap};
 use sqlx::{
     ConnectOptions, PgPool,
     postgres::{PgConnectOptions, PgPoolOptions},
@@ -35,6 +37,7 @@ use std::{
 use time::Instant;
 use tokio::runtime::Runtime;
 use tokio::task;
+use uuid::Uuid;
 
 #[derive(Hash, PartialEq, Eq, Clone)]
 pub struct ClearCodeData {
@@ -64,6 +67,16 @@ pub struct Storage {
     pub server: RefCell<Server>,
     pub gettick: RefCell<Instant>,
     pub pgconn: PgPool,
+    pub saves: SaveWorker,
+    pub logins: LoginWorker,
+    pub lookups: LookupWorker,
+    //Failed logins per address and the sockets with a login still being checked.
+    pub login_throttle: RefCell<LoginThrottle>,
+    pub pending_logins: RefCell<HashSet<usize>>,
+    //Sockets that logged in and are on the character select screen.
+    pub character_select: RefCell<HashMap<usize, CharacterSelect>>,
+    pub mailer: Box<dyn Mailer>,
+    pub ip_bans: RefCell<IpBans>,
     pub time: RefCell<GameTime>,
     pub map_switch_tasks: RefCell<IndexMap<GlobalKey, Vec<MapSwitchTasks>>>, //Data Tasks For dealing with Player Warp and MapSwitch
     pub bases: Bases,
@@ -71,6 +84,8 @@ pub struct Storage {
     pub local: RefCell<task::LocalSet>,
     pub config: Config,
     pub unload_npc: RefCell<Vec<GlobalKey>>,
+    pub parties: RefCell<SlotMap<PartyKey, Party>>,
+    pub guilds: RefCell<HashMap<Uuid, Guild>>,
 }
 
 fn establish_connection(
@@ -85,6 +100,8 @@ fn establish_connection(
     connect_opts = connect_opts.password(&config.password);
     connect_opts = connect_opts.host(&config.host);
     connect_opts = connect_opts.port(config.port);
+    // Every query uses fixed text with bound values so each connection only prepares it once.
+    connect_opts = connect_opts.statement_cache_capacity(256);
 
     let pool = local.block_on(
         rt,
@@ -140,11 +157,30 @@ pub struct Config {
     pub port: u16,
     pub enable_backtrace: bool,
     pub level_filter: ServerLevelFilter,
+    /// Seconds players are given to finish up when the server is shut down.
+    #[serde(default = "default_shutdown_countdown")]
+    pub shutdown_countdown: u64,
+    /// Written into the logs table so multiple servers can share one database.
+    #[serde(default)]
+    pub server_id: i16,
+    /// File password reset mails are written to. Printed to stdout when not set.
+    #[serde(default)]
+    pub mail_file: Option<String>,
+    /// Per packet flood limits. Built in defaults are used for anything left out.
+    #[serde(default)]
+    pub rate_limits: RateLimits,
+}
+
+fn default_shutdown_countdown() -> u64 {
+    30
 }
 
 pub fn read_config(path: &str) -> Config {
     let data = fs::read_to_string(path).unwrap();
-    toml::from_str(&data).unwrap()
+    let config: Config = toml::from_str(&data).unwrap();
+
+    config.rate_limits.validate(path).unwrap();
+    config
 }
 
 fn load_certs(filename: &str) -> Vec<CertificateDer<'static>> {
@@ -197,6 +233,78 @@ fn build_tls_config(
 
 impl Storage {
     pub fn new(config: Config) -> Option<Self> {
+        let mut rt: Runtime = Runtime::new().unwrap();
+        let local = task::LocalSet::new();
+        let pgconn = establish_connection(&config, &mut rt, &local).unwrap();
+        crate::sql::initiate(&pgconn, &mut rt, &local).unwrap();
+
+        let mut storage = Self::with_pool(config, rt, local, pgconn)?;
+
+        let mut map_data_entry = crate::maps::get_maps().unwrap();
+        while let Some(map_data) = map_data_entry.pop() {
+            let position = map_data.position;
+
+            storage
+                .maps
+                .insert(position, RefCell::new(MapData::from_map(&map_data)));
+            storage.bases.maps.insert(position, map_data);
+        }
+
+        storage.bases.npcs = fill_base(crate::npcs::get_npc().unwrap(), MAX_NPCS);
+        storage.bases.items = fill_base(crate::items::get_item().unwrap(), MAX_ITEMS);
+        storage.bases.shops = fill_base(crate::items::get_shop().unwrap(), MAX_SHOPS);
+        storage.bases.quests = fill_base(crate::quests::get_quest().unwrap(), MAX_QUESTS);
+        storage.bases.skills = fill_base(crate::skills::get_skill().unwrap(), MAX_SKILLS);
+        storage.bases.progression = crate::progression::get_progression().unwrap();
+
+        // The first ip ban list has to be in before any connection is accepted.
+        refresh_ip_bans(&storage).unwrap();
+        storage.saves.flush().unwrap();
+        process_ip_bans(&storage);
+
+        Some(storage)
+    }
+
+    /// Storage with empty bases and a database pool that never connects unless used.
+    /// Lets tests run game logic against a World without a server setup.
+    #[cfg(test)]
+    pub fn new_for_tests() -> Self {
+        let config = Config {
+            listen: "127.0.0.1:0".into(),
+            tls_listen: "127.0.0.1:0".into(),
+            server_cert: "keys/server.crt".into(),
+            server_key: "keys/server-key.pem".into(),
+            ca_root: String::new(),
+            maxconnections: 1,
+            database: String::new(),
+            username: String::new(),
+            password: String::new(),
+            host: String::new(),
+            port: 5432,
+            enable_backtrace: false,
+            level_filter: ServerLevelFilter::Off,
+            shutdown_countdown: default_shutdown_countdown(),
+            server_id: 0,
+            mail_file: None,
+            rate_limits: RateLimits::default(),
+        };
+        let rt = Runtime::new().unwrap();
+        let local = task::LocalSet::new();
+        let pgconn = {
+            let _guard = rt.enter();
+
+            PgPoolOptions::new().connect_lazy_with(PgConnectOptions::new())
+        };
+
+        Self::with_pool(config, rt, local, pgconn).unwrap()
+    }
+
+    fn with_pool(
+        config: Config,
+        rt: Runtime,
+        local: task::LocalSet,
+        pgconn: PgPool,
+    ) -> Option<Self> {
         let mut poll = Poll::new().ok()?;
         let tls_config =
             build_tls_config(&config.server_cert, &config.server_key, &config.ca_root).unwrap();
@@ -209,12 +317,11 @@ impl Storage {
         )
         .ok()?;
 
-        let mut rt: Runtime = Runtime::new().unwrap();
-        let local = task::LocalSet::new();
-        let pgconn = establish_connection(&config, &mut rt, &local).unwrap();
-        crate::sql::initiate(&pgconn, &mut rt, &local).unwrap();
+        let saves = SaveWorker::spawn(&rt, pgconn.clone());
+        let logins = LoginWorker::spawn(&rt, pgconn.clone());
+        let lookups = LookupWorker::spawn(&rt, pgconn.clone());
 
-        let mut storage = Self {
+        Some(Self {
             player_ids: RefCell::new(IndexSet::default()),
             recv_ids: RefCell::new(IndexSet::default()),
             npc_ids: RefCell::new(IndexSet::default()),
@@ -232,6 +339,14 @@ impl Storage {
             server: RefCell::new(server),
             gettick: RefCell::new(Instant::recent()),
             pgconn,
+            saves,
+            logins,
+            lookups,
+            login_throttle: RefCell::new(LoginThrottle::default()),
+            pending_logins: RefCell::new(HashSet::default()),
+            character_select: RefCell::new(HashMap::default()),
+            mailer: Box::new(LocalMailer::new(config.mail_file.clone())),
+            ip_bans: RefCell::new(IpBans::default()),
             time: RefCell::new(GameTime::default()),
             map_switch_tasks: RefCell::new(IndexMap::default()),
             bases: Bases::new()?,
@@ -239,71 +354,9 @@ impl Storage {
             local: RefCell::new(local),
             config,
             unload_npc: RefCell::new(Vec::with_capacity(32)),
-        };
-
-        let mut map_data_entry = crate::maps::get_maps();
-        while let Some(map_data) = map_data_entry.pop() {
-            let position = MapPosition {
-                x: map_data.position.x,
-                y: map_data.position.y,
-                group: map_data.position.group,
-            };
-
-            let mut map = MapData {
-                position,
-                ..Default::default()
-            };
-
-            for id in 0..MAP_MAX_X * MAP_MAX_Y {
-                match map_data.attribute[id].clone() {
-                    MapAttribute::Blocked | MapAttribute::Storage | MapAttribute::Shop(_) => {
-                        map.move_grid[id].attr = GridAttribute::Blocked;
-                    }
-                    MapAttribute::NpcBlocked => {
-                        map.move_grid[id].attr = GridAttribute::NpcBlock;
-                    }
-                    MapAttribute::ItemSpawn(itemdata) => {
-                        map.add_spawnable_item(
-                            Position::new(id as i32 % 32, id as i32 / 32, map_data.position),
-                            itemdata.index,
-                            itemdata.amount,
-                            itemdata.timer,
-                        );
-                    }
-                    _ => {}
-                }
-                map.move_grid[id].dir_block = map_data.dir_block[id];
-            }
-
-            storage.maps.insert(position, RefCell::new(map));
-            storage.bases.maps.insert(position, map_data);
-        }
-
-        let npc_data_entry = crate::npcs::get_npc();
-        npc_data_entry
-            .iter()
-            .enumerate()
-            .for_each(|(index, npc_data)| {
-                storage.bases.npcs[index] = npc_data.clone();
-            });
-
-        let item_data_entry = crate::items::get_item();
-        item_data_entry
-            .iter()
-            .enumerate()
-            .for_each(|(index, item_data)| {
-                storage.bases.items[index] = item_data.clone();
-            });
-
-        let shop_data_entry = crate::items::get_shop();
-        shop_data_entry
-            .iter()
-            .enumerate()
-            .for_each(|(index, shopdata)| {
-                storage.bases.shops[index] = shopdata.clone();
-            });
-
-        Some(storage)
+            parties: RefCell::new(SlotMap::with_key()),
+            guilds: RefCell::new(HashMap::default()),
+        })
     }
 
     pub fn add_player_data(
@@ -338,7 +391,10 @@ impl Storage {
                 {
                     let p_data = p_data.try_lock()?;
 
-                    let _ = world.account_id.remove(&p_data.account.id);
+                    // A stale entity of the account may be unloaded after its new one joined.
+                    if world.get_account_id(&p_data.account.id) == Some(id) {
+                        let _ = world.account_id.remove(&p_data.account.id);
+                    }
 
                     // pos = Some((p_data.movement.pos, p_data.movement.map_instance));
 
@@ -346,8 +402,8 @@ impl Storage {
                         .borrow_mut()
                         .remove(&p_data.account.username);
 
-                    info!("Players Disconnected : {}", &p_data.account.username);
-                    trace!("Players Disconnected IP: {} ", &p_data.socket.addr);
+                    info!("Players Disconnected : {}", p_data.account.username);
+                    trace!("Players Disconnected IP: {} ", p_data.socket.addr);
                 }
 
                 Some(p_data)
@@ -369,6 +425,9 @@ impl Storage {
 
             vitals.vital[VitalTypes::Hp as usize] = npcdata.maxhp as i32;
             vitals.vitalmax[VitalTypes::Hp as usize] = npcdata.maxhp as i32;
+            vitals.vital[VitalTypes::Mp as usize] = npcdata.maxmp as i32;
+            vitals.vitalmax[VitalTypes::Mp as usize] = npcdata.maxmp as i32;
+            vitals.regens = npcdata.regens;
 
             world.entities.insert(
                 entity,
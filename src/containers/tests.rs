@@ -0,0 +1,33 @@
+//! Checks for the in memory login bookkeeping. These need no database.
+
+use super::*;
+use crate::gametypes::*;
+use time::Instant;
+
+#[test]
+fn login_throttle_shares_ip_between_sockets() {
+    let mut throttle = LoginThrottle::default();
+    let tick = Instant::recent();
+
+    // Every attempt comes from a new connection and so a new port.
+    for attempt in 1..LOGIN_FREE_ATTEMPTS {
+        let addr = format!("127.0.0.1:{}", 50000 + attempt);
+
+        assert_eq!(throttle.failed(&addr, tick), None);
+    }
+
+    assert_eq!(
+        throttle.failed("127.0.0.1:60000", tick),
+        Some(LOGIN_BACKOFF_MS)
+    );
+    assert_eq!(throttle.0.len(), 1);
+    assert_eq!(
+        throttle.wait_left("127.0.0.1:60001", tick),
+        LOGIN_BACKOFF_MS
+    );
+    assert_eq!(throttle.wait_left("127.0.0.2:60000", tick), 0);
+
+    throttle.succeeded("127.0.0.1:60002");
+
+    assert_eq!(throttle.wait_left("127.0.0.1:60000", tick), 0);
+}
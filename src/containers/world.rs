@@ -26,6 +26,7 @@ pub enum Entity {
 pub struct World {
     pub kinds: SlotMap<GlobalKey, EntityKind>,
     pub entities: SecondaryMap<GlobalKey, Entity>,
+    /// Online accounts and the entity of the character they are playing.
     pub account_id: HashMap<Uuid, GlobalKey>,
 }
 
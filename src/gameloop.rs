@@ -1,5 +1,9 @@
+mod console;
 mod handledata;
 mod mainloop;
+mod shutdown;
 
-pub use handledata::{SocketID, handle_data};
+pub use console::{ConsoleCommand, process_console, spawn_console};
+pub use handledata::{SocketID, handle_data, process_load_results, process_login_results};
 pub use mainloop::game_loop;
+pub use shutdown::{ShutdownCountdown, request_shutdown, shutdown_server, spawn_signal_handler};
@@ -0,0 +1,259 @@
+use crate::{
+    containers::{ReloadKind, Storage, World},
+    gameloop::{ShutdownCountdown, request_shutdown},
+    gametypes::*,
+    maps::spawn_npc,
+    players::{kick_player, player_warp},
+    tasks::{DataTaskToken, message_packet},
+};
+use log::{error, info, warn};
+use std::{
+    sync::mpsc::{Receiver, Sender, TryRecvError},
+    thread::{self, JoinHandle},
+};
+
+const CONSOLE_HELP: &str = "Commands:
+    kick <name>
+    broadcast <message>
+    warp <name> <x> <y> <map x> <map y> <map group>
+    spawn <npc id> <x> <y> <map x> <map y> <map group>
+    settime <hour> <min>
+    reload [items|npcs|shops|quests|skills|progression|maps|all]
+    shutdown [seconds]
+    help";
+
+/// Commands typed into the server console. These are parsed on the console thread
+/// and handed to the game loop so World and Storage are only ever touched from the loop.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConsoleCommand {
+    Kick(String),
+    Broadcast(String),
+    Warp(String, Position),
+    SpawnNpc(u64, Position),
+    SetTime(u32, u32),
+    Reload(ReloadKind),
+    Shutdown(Option<u64>),
+    Help,
+}
+
+impl ConsoleCommand {
+    pub fn parse(line: &str) -> std::result::Result<ConsoleCommand, String> {
+        let mut args = line.split_whitespace();
+        let command = match args.next() {
+            Some(command) => command.to_lowercase(),
+            None => return Err(String::new()),
+        };
+
+        match command.as_str() {
+            "kick" => Ok(ConsoleCommand::Kick(parse_name(&mut args)?)),
+            "broadcast" | "say" => {
+                let msg = line
+                    .trim_start()
+                    .split_once(char::is_whitespace)
+                    .map(|(_, msg)| msg.trim())
+                    .unwrap_or_default();
+
+                if msg.is_empty() {
+                    return Err("broadcast requires a message".into());
+                }
+
+                Ok(ConsoleCommand::Broadcast(msg.to_string()))
+            }
+            "warp" => {
+                let name = parse_name(&mut args)?;
+                Ok(ConsoleCommand::Warp(name, parse_position(&mut args)?))
+            }
+            "spawn" => {
+                let index = parse_arg::<u64>(args.next(), "npc id")?;
+                Ok(ConsoleCommand::SpawnNpc(index, parse_position(&mut args)?))
+            }
+            "settime" => {
+                let hour = parse_arg::<u32>(args.next(), "hour")?;
+                let min = parse_arg::<u32>(args.next(), "min")?;
+
+                if hour >= 24 || min >= 60 {
+                    return Err("time must be within 0-23 hours and 0-59 minutes".into());
+                }
+
+                Ok(ConsoleCommand::SetTime(hour, min))
+            }
+            "reload" => match args.next() {
+                Some(name) => match ReloadKind::parse(name) {
+                    Some(kind) => Ok(ConsoleCommand::Reload(kind)),
+                    None => Err(format!("Unknown data type to reload: {name}")),
+                },
+                None => Ok(ConsoleCommand::Reload(ReloadKind::All)),
+            },
+            "shutdown" | "exit" | "quit" => {
+                let seconds = match args.next() {
+                    Some(seconds) => Some(parse_arg::<u64>(Some(seconds), "seconds")?),
+                    None => None,
+                };
+
+                Ok(ConsoleCommand::Shutdown(seconds))
+            }
+            "help" | "?" => Ok(ConsoleCommand::Help),
+            _ => Err(format!("Unknown command: {command}")),
+        }
+    }
+}
+
+fn parse_name<'a>(args: &mut impl Iterator<Item = &'a str>) -> std::result::Result<String, String> {
+    args.next()
+        .map(|name| name.to_string())
+        .ok_or_else(|| "missing player name".to_string())
+}
+
+pub(crate) fn parse_arg<T: std::str::FromStr>(
+    arg: Option<&str>,
+    name: &str,
+) -> std::result::Result<T, String> {
+    match arg {
+        Some(value) => value
+            .parse::<T>()
+            .map_err(|_| format!("{name} is not a valid number: {value}")),
+        None => Err(format!("missing {name}")),
+    }
+}
+
+pub(crate) fn parse_position<'a>(
+    args: &mut impl Iterator<Item = &'a str>,
+) -> std::result::Result<Position, String> {
+    let x = parse_arg::<i32>(args.next(), "x")?;
+    let y = parse_arg::<i32>(args.next(), "y")?;
+    let map_x = parse_arg::<i32>(args.next(), "map x")?;
+    let map_y = parse_arg::<i32>(args.next(), "map y")?;
+    let group = parse_arg::<i32>(args.next(), "map group")?;
+
+    if !(0..MAP_MAX_X as i32).contains(&x) || !(0..MAP_MAX_Y as i32).contains(&y) {
+        return Err("x and y must be within the map".into());
+    }
+
+    Ok(Position::new(x, y, MapPosition::new(map_x, map_y, group)))
+}
+
+// Reads a line from stdin. Returns None once stdin is closed so the console thread can exit.
+fn read_line() -> Option<String> {
+    let mut rv = String::new();
+
+    match std::io::stdin().read_line(&mut rv) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(rv.replace("\r\n", "").replace('\n', "")),
+    }
+}
+
+/// Spawns the console thread which reads stdin and sends parsed commands to the game loop.
+pub fn spawn_console(sender: Sender<ConsoleCommand>) -> std::io::Result<JoinHandle<()>> {
+    thread::Builder::new()
+        .name("console".into())
+        .spawn(move || {
+            while let Some(line) = read_line() {
+                match ConsoleCommand::parse(&line) {
+                    Ok(command) => {
+                        // The loop has ended so there is nothing left to control.
+                        if sender.send(command).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) if e.is_empty() => {}
+                    Err(e) => println!("{e}. Type help for a list of commands."),
+                }
+            }
+        })
+}
+
+/// Runs every command waiting from the console.
+/// Reloads need mutable access to Storage so they are handed back to run after the tick.
+pub fn process_console(
+    world: &mut World,
+    storage: &Storage,
+    console: &Receiver<ConsoleCommand>,
+    shutdown: &mut Option<ShutdownCountdown>,
+    reload: &mut Option<ReloadKind>,
+) -> Result<()> {
+    loop {
+        let command = match console.try_recv() {
+            Ok(command) => command,
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => return Ok(()),
+        };
+
+        match command {
+            ConsoleCommand::Kick(name) => console_kick(world, storage, &name)?,
+            ConsoleCommand::Broadcast(msg) => {
+                info!("Broadcast: {msg}");
+                DataTaskToken::GlobalChat.add_task(
+                    storage,
+                    message_packet(MessageChannel::Global, "[Server]".into(), msg, None)?,
+                )?;
+            }
+            ConsoleCommand::Warp(name, pos) => {
+                if !storage.bases.maps.contains_key(&pos.map) {
+                    warn!("Map {:?} does not exist", pos.map);
+                    continue;
+                }
+
+                let entity = storage.player_names.borrow().get(&name).copied();
+
+                match entity {
+                    Some(entity) => {
+                        player_warp(world, storage, entity, &pos, false)?;
+                        info!("Warped {name} to {pos:?}");
+                    }
+                    None => warn!("Player {name} is not online"),
+                }
+            }
+            ConsoleCommand::SpawnNpc(index, pos) => {
+                if storage.bases.npc(index as usize).is_none() {
+                    warn!("Npc {index} does not exist");
+                    continue;
+                }
+
+                if let Some(mapdata) = storage.maps.get(&pos.map) {
+                    let mut data = mapdata.borrow_mut();
+                    if let Some(id) = storage.add_npc(world, index)? {
+                        data.add_npc(id);
+                        spawn_npc(world, pos, None, id)?;
+                        info!("Spawned npc {index} at {pos:?}");
+                    }
+                } else {
+                    warn!("Map {:?} does not exist", pos.map);
+                }
+            }
+            ConsoleCommand::SetTime(hour, min) => {
+                let mut time = storage.time.borrow_mut();
+                time.hour = hour;
+                time.min = min;
+                time.sec = 0;
+                info!("Game time set to {hour:02}:{min:02}");
+            }
+            ConsoleCommand::Reload(kind) => {
+                // A full reload already covers anything else that was asked for.
+                *reload = match *reload {
+                    Some(current) if current != kind => Some(ReloadKind::All),
+                    _ => Some(kind),
+                };
+            }
+            ConsoleCommand::Shutdown(seconds) => {
+                request_shutdown(world, storage, shutdown, seconds)?
+            }
+            ConsoleCommand::Help => println!("{CONSOLE_HELP}"),
+        }
+    }
+}
+
+fn console_kick(world: &mut World, storage: &Storage, name: &str) -> Result<()> {
+    let entity = match storage.player_names.borrow().get(name) {
+        Some(entity) => *entity,
+        None => {
+            warn!("Player {name} is not online");
+            return Ok(());
+        }
+    };
+
+    if let Err(e) = kick_player(world, storage, entity, "You have been kicked.".into()) {
+        error!("Failed to disconnect {name}: {e}");
+    }
+
+    info!("Kicked {name}");
+    Ok(())
+}
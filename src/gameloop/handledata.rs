@@ -1,9 +1,14 @@
+pub mod chat_commands;
 pub mod handle_account;
 pub mod handle_action;
 pub mod handle_general;
+pub mod handle_guild;
 pub mod handle_item;
+pub mod handle_party;
+pub mod handle_quest;
 pub mod handle_trade;
 pub mod mapper;
 pub mod router;
 
+pub use handle_account::{process_load_results, process_login_results};
 pub use router::{SocketID, handle_data};
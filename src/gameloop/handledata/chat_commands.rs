@@ -0,0 +1,1123 @@
+use crate::{
+    containers::{Entity, GlobalKey, IpRange, Storage, UserAccess, World, socket_ip},
+    gameloop::console::{parse_arg, parse_position},
+    gametypes::*,
+    items::Item,
+    maps::spawn_npc,
+    players::{
+        check_inv_space, give_inv_item, kick_player, player_add_up_vital, player_update_stats,
+        player_warp,
+    },
+    socket::{send_level, send_message},
+    sql::{
+        LookupReply, NewSanction, PGLog, PGSanction, SanctionTarget, SaveJob, add_log,
+        player_log_entry, refresh_ip_bans, sql_find_account, sql_load_logs, sql_load_sanctions,
+        update_level,
+    },
+    tasks::{DataTaskToken, vitals_packet},
+};
+use chrono::{DateTime, Duration, Utc};
+use log::{error, info};
+use uuid::Uuid;
+
+/// Arguments produced by a command's parser and handed to its handler.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommandArgs {
+    None,
+    Player(String),
+    Warp(Option<String>, Position),
+    Spawn(u64, Option<Position>),
+    Give(String, u32, u16),
+    SetLevel(String, i32),
+    /// Some(0) minutes lifts the mute and None makes it permanent.
+    Mute(String, Option<i64>, String),
+    Ban(String, Option<i64>, String),
+    IpBan(IpRange, Option<i64>, String),
+    Address(IpRange),
+    History(String, i64),
+}
+
+type CommandParser = fn(&[&str]) -> std::result::Result<CommandArgs, String>;
+type CommandHandler = fn(&mut World, &Storage, GlobalKey, CommandArgs) -> Result<()>;
+
+/// A chat command players can type as /name. Only players with at least
+/// the required access level may run it.
+pub struct ChatCommand {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub access: UserAccess,
+    pub parse: CommandParser,
+    pub handler: CommandHandler,
+}
+
+pub const CHAT_COMMANDS: &[ChatCommand] = &[
+    ChatCommand {
+        name: "who",
+        usage: "/who",
+        access: UserAccess::None,
+        parse: parse_none,
+        handler: command_who,
+    },
+    ChatCommand {
+        name: "kick",
+        usage: "/kick <name>",
+        access: UserAccess::Monitor,
+        parse: parse_player,
+        handler: command_kick,
+    },
+    ChatCommand {
+        name: "mute",
+        usage: "/mute <name> <minutes|perm> [reason] (0 unmutes)",
+        access: UserAccess::Monitor,
+        parse: parse_mute,
+        handler: command_mute,
+    },
+    ChatCommand {
+        name: "sanctions",
+        usage: "/sanctions <name> [count]",
+        access: UserAccess::Monitor,
+        parse: parse_history,
+        handler: command_sanctions,
+    },
+    ChatCommand {
+        name: "ban",
+        usage: "/ban <name> <minutes|perm> [reason]",
+        access: UserAccess::Admin,
+        parse: parse_ban,
+        handler: command_ban,
+    },
+    ChatCommand {
+        name: "unban",
+        usage: "/unban <name>",
+        access: UserAccess::Admin,
+        parse: parse_player,
+        handler: command_unban,
+    },
+    ChatCommand {
+        name: "ipban",
+        usage: "/ipban <ip[/prefix]> <minutes|perm> [reason]",
+        access: UserAccess::Admin,
+        parse: parse_ipban,
+        handler: command_ipban,
+    },
+    ChatCommand {
+        name: "unipban",
+        usage: "/unipban <ip[/prefix]>",
+        access: UserAccess::Admin,
+        parse: parse_address,
+        handler: command_unipban,
+    },
+    ChatCommand {
+        name: "history",
+        usage: "/history <name> [count]",
+        access: UserAccess::Admin,
+        parse: parse_history,
+        handler: command_history,
+    },
+    ChatCommand {
+        name: "warp",
+        usage: "/warp [name] <x> <y> <map x> <map y> <map group>",
+        access: UserAccess::Admin,
+        parse: parse_warp,
+        handler: command_warp,
+    },
+    ChatCommand {
+        name: "spawn",
+        usage: "/spawn <npc id> [x y map x map y map group]",
+        access: UserAccess::Admin,
+        parse: parse_spawn,
+        handler: command_spawn,
+    },
+    ChatCommand {
+        name: "give",
+        usage: "/give <name> <item id> [amount]",
+        access: UserAccess::Admin,
+        parse: parse_give,
+        handler: command_give,
+    },
+    ChatCommand {
+        name: "setlevel",
+        usage: "/setlevel <name> <level>",
+        access: UserAccess::Admin,
+        parse: parse_setlevel,
+        handler: command_setlevel,
+    },
+];
+
+pub fn find_chat_command(name: &str) -> Option<&'static ChatCommand> {
+    let name = name.to_lowercase();
+
+    CHAT_COMMANDS.iter().find(|command| command.name == name)
+}
+
+/// Parses a line typed in chat without its leading / and runs it.
+pub fn run_chat_command(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    line: &str,
+) -> Result<()> {
+    let args: Vec<&str> = line.split_whitespace().collect();
+
+    let Some(name) = args.first() else {
+        return Ok(());
+    };
+
+    let Some(command) = find_chat_command(name) else {
+        return reply(world, storage, entity, format!("Unknown command /{name}"));
+    };
+
+    if !has_access(world, entity, command)? {
+        return deny(world, storage, entity, command, line);
+    }
+
+    match (command.parse)(&args[1..]) {
+        Ok(parsed) => execute_chat_command(world, storage, entity, command, parsed, line),
+        Err(e) => reply(
+            world,
+            storage,
+            entity,
+            format!("{e}. Usage: {}", command.usage),
+        ),
+    }
+}
+
+/// Checks access, logs and runs a command whose arguments are already parsed.
+/// Used for commands coming from the Command packet as well as chat.
+pub fn execute_chat_command(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    command: &ChatCommand,
+    args: CommandArgs,
+    line: &str,
+) -> Result<()> {
+    if !has_access(world, entity, command)? {
+        return deny(world, storage, entity, command, line);
+    }
+
+    write_log(world, storage, entity, LogType::Command, format!("/{line}"))?;
+
+    (command.handler)(world, storage, entity, args)
+}
+
+fn has_access(world: &mut World, entity: GlobalKey, command: &ChatCommand) -> Result<bool> {
+    Ok(user_access(world, entity)? >= command.access)
+}
+
+fn deny(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    command: &ChatCommand,
+    line: &str,
+) -> Result<()> {
+    write_log(
+        world,
+        storage,
+        entity,
+        LogType::Warning,
+        format!("Denied command /{line}"),
+    )?;
+
+    reply(
+        world,
+        storage,
+        entity,
+        format!("You do not have permission to use /{}", command.name),
+    )
+}
+
+fn write_log(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    logtype: LogType,
+    message: String,
+) -> Result<()> {
+    if let Some(log) = player_log_entry(world, storage, entity, logtype, message)? {
+        info!("{}", log.message);
+
+        // A failed log write should not stop the command from running.
+        if let Err(e) = add_log(storage, log) {
+            error!("Failed to queue command log: {e}");
+        }
+    }
+
+    Ok(())
+}
+
+fn reply(world: &mut World, storage: &Storage, entity: GlobalKey, msg: String) -> Result<()> {
+    send_message(
+        world,
+        storage,
+        entity,
+        msg,
+        String::new(),
+        MessageChannel::Private,
+        None,
+    )
+}
+
+// Finds an online player, telling the user if they could not be found.
+fn find_target(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    name: &str,
+) -> Result<Option<GlobalKey>> {
+    let target = storage.player_names.borrow().get(name).copied();
+
+    if target.is_none() {
+        reply(
+            world,
+            storage,
+            entity,
+            "Player is offline or does not exist".into(),
+        )?;
+    }
+
+    Ok(target)
+}
+
+fn user_access(world: &mut World, entity: GlobalKey) -> Result<UserAccess> {
+    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        Ok(p_data.try_lock()?.user_access)
+    } else {
+        Ok(UserAccess::None)
+    }
+}
+
+// Staff can not use moderation commands on anyone above them.
+fn outranks(world: &mut World, entity: GlobalKey, target: GlobalKey) -> Result<bool> {
+    Ok(user_access(world, entity)? >= user_access(world, target)?)
+}
+
+fn account_id(world: &mut World, entity: GlobalKey) -> Result<Option<Uuid>> {
+    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        Ok(Some(p_data.try_lock()?.account.id))
+    } else {
+        Ok(None)
+    }
+}
+
+// Finds an account by name whether the player is online or not and runs then with it
+// and their entity if they are. Offline accounts are looked up off the game loop so then
+// may run a little later. Tells the user if there is no such account or it outranks them.
+fn with_account<F>(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    name: String,
+    then: F,
+) -> Result<()>
+where
+    F: FnOnce(&mut World, &Storage, Uuid, Option<GlobalKey>) -> Result<()> + Send + 'static,
+{
+    let target = storage.player_names.borrow().get(&name).copied();
+
+    let online = match target.map(|target| world.get_opt_entity(target)) {
+        Some(Some(Entity::Player(p_data))) => {
+            let p_data = p_data.try_lock()?;
+
+            Some((p_data.account.id, p_data.user_access))
+        }
+        _ => None,
+    };
+
+    if online.is_some() {
+        return checked_account(world, storage, entity, &name, online, target, then);
+    }
+
+    storage.lookups.lookup(move |pool| async move {
+        let found = sql_find_account(&pool, &name).await;
+
+        Box::new(move |world: &mut World, storage: &Storage| {
+            // They may have logged in while the lookup ran.
+            let target = storage.player_names.borrow().get(&name).copied();
+
+            checked_account(world, storage, entity, &name, found?, target, then)
+        }) as LookupReply
+    });
+
+    Ok(())
+}
+
+fn checked_account<F>(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    name: &str,
+    found: Option<(Uuid, UserAccess)>,
+    target: Option<GlobalKey>,
+    then: F,
+) -> Result<()>
+where
+    F: FnOnce(&mut World, &Storage, Uuid, Option<GlobalKey>) -> Result<()>,
+{
+    let Some((uid, access)) = found else {
+        return reply(world, storage, entity, format!("No account named {name}"));
+    };
+
+    if user_access(world, entity)? < access {
+        return reply(
+            world,
+            storage,
+            entity,
+            format!("You can not do that to {name}"),
+        );
+    }
+
+    then(world, storage, uid, target)
+}
+
+fn expiry(minutes: Option<i64>) -> Option<DateTime<Utc>> {
+    minutes.map(|minutes| Utc::now() + Duration::try_minutes(minutes).unwrap_or_default())
+}
+
+fn duration_text(minutes: Option<i64>) -> String {
+    match minutes {
+        Some(minutes) => format!("for {minutes} minute(s)"),
+        None => "permanently".into(),
+    }
+}
+
+fn parse_none(_args: &[&str]) -> std::result::Result<CommandArgs, String> {
+    Ok(CommandArgs::None)
+}
+
+fn parse_name(args: &[&str]) -> std::result::Result<String, String> {
+    args.first()
+        .map(|name| name.to_string())
+        .ok_or_else(|| "Missing player name".to_string())
+}
+
+fn parse_player(args: &[&str]) -> std::result::Result<CommandArgs, String> {
+    Ok(CommandArgs::Player(parse_name(args)?))
+}
+
+// Everything after the fixed arguments is the reason.
+fn parse_reason(args: &[&str]) -> String {
+    if args.is_empty() {
+        "No reason given".into()
+    } else {
+        args.join(" ")
+    }
+}
+
+// perm for a sanction that never ends, otherwise a number of minutes.
+fn parse_duration(arg: Option<&str>) -> std::result::Result<Option<i64>, String> {
+    if arg == Some("perm") {
+        return Ok(None);
+    }
+
+    let minutes = parse_arg::<i64>(arg, "minutes")?;
+
+    if minutes < 0 {
+        return Err("minutes can not be negative".into());
+    }
+
+    Ok(Some(minutes))
+}
+
+fn parse_mute(args: &[&str]) -> std::result::Result<CommandArgs, String> {
+    let name = parse_name(args)?;
+    let minutes = parse_duration(args.get(1).copied())?;
+
+    Ok(CommandArgs::Mute(
+        name,
+        minutes,
+        parse_reason(args.get(2..).unwrap_or_default()),
+    ))
+}
+
+fn parse_ban(args: &[&str]) -> std::result::Result<CommandArgs, String> {
+    let name = parse_name(args)?;
+    let minutes = parse_duration(args.get(1).copied())?;
+
+    if minutes == Some(0) {
+        return Err("minutes must be at least 1".into());
+    }
+
+    Ok(CommandArgs::Ban(
+        name,
+        minutes,
+        parse_reason(args.get(2..).unwrap_or_default()),
+    ))
+}
+
+fn parse_range(args: &[&str]) -> std::result::Result<IpRange, String> {
+    args.first()
+        .ok_or_else(|| "Missing ip address".to_string())?
+        .parse()
+}
+
+fn parse_ipban(args: &[&str]) -> std::result::Result<CommandArgs, String> {
+    let range = parse_range(args)?;
+    let minutes = parse_duration(args.get(1).copied())?;
+
+    if minutes == Some(0) {
+        return Err("minutes must be at least 1".into());
+    }
+
+    Ok(CommandArgs::IpBan(
+        range,
+        minutes,
+        parse_reason(args.get(2..).unwrap_or_default()),
+    ))
+}
+
+fn parse_address(args: &[&str]) -> std::result::Result<CommandArgs, String> {
+    Ok(CommandArgs::Address(parse_range(args)?))
+}
+
+fn parse_history(args: &[&str]) -> std::result::Result<CommandArgs, String> {
+    let name = parse_name(args)?;
+    let count = match args.get(1) {
+        Some(count) => parse_arg::<i64>(Some(count), "count")?,
+        None => 10,
+    };
+
+    if !(1..=MAX_HISTORY_ROWS).contains(&count) {
+        return Err(format!("count must be within 1-{MAX_HISTORY_ROWS}"));
+    }
+
+    Ok(CommandArgs::History(name, count))
+}
+
+fn parse_warp(args: &[&str]) -> std::result::Result<CommandArgs, String> {
+    // A leading name warps someone else, otherwise the user warps themselves.
+    let (name, position) = match args.len() {
+        5 => (None, args),
+        6 => (Some(args[0].to_string()), &args[1..]),
+        _ => return Err("Wrong number of arguments".into()),
+    };
+
+    Ok(CommandArgs::Warp(
+        name,
+        parse_position(&mut position.iter().copied())?,
+    ))
+}
+
+fn parse_spawn(args: &[&str]) -> std::result::Result<CommandArgs, String> {
+    let index = parse_arg::<u64>(args.first().copied(), "npc id")?;
+
+    let position = if args.len() > 1 {
+        Some(parse_position(&mut args[1..].iter().copied())?)
+    } else {
+        None
+    };
+
+    Ok(CommandArgs::Spawn(index, position))
+}
+
+fn parse_give(args: &[&str]) -> std::result::Result<CommandArgs, String> {
+    let name = parse_name(args)?;
+    let item = parse_arg::<u32>(args.get(1).copied(), "item id")?;
+    let amount = match args.get(2) {
+        Some(amount) => parse_arg::<u16>(Some(amount), "amount")?,
+        None => 1,
+    };
+
+    if amount == 0 {
+        return Err("amount must be at least 1".into());
+    }
+
+    Ok(CommandArgs::Give(name, item, amount))
+}
+
+fn parse_setlevel(args: &[&str]) -> std::result::Result<CommandArgs, String> {
+    let name = parse_name(args)?;
+    let level = parse_arg::<i32>(args.get(1).copied(), "level")?;
+
+    if level < 1 {
+        return Err("level must be at least 1".into());
+    }
+
+    Ok(CommandArgs::SetLevel(name, level))
+}
+
+fn command_who(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    _args: CommandArgs,
+) -> Result<()> {
+    let mut names: Vec<String> = storage.player_names.borrow().keys().cloned().collect();
+    names.sort();
+
+    reply(
+        world,
+        storage,
+        entity,
+        format!("Players online ({}): {}", names.len(), names.join(", ")),
+    )
+}
+
+fn command_kick(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    args: CommandArgs,
+) -> Result<()> {
+    let CommandArgs::Player(name) = args else {
+        return Ok(());
+    };
+
+    let Some(target) = find_target(world, storage, entity, &name)? else {
+        return Ok(());
+    };
+
+    if !outranks(world, entity, target)? {
+        return reply(world, storage, entity, format!("You can not kick {name}"));
+    }
+
+    kick_player(world, storage, target, "You have been kicked.".into())?;
+    reply(world, storage, entity, format!("Kicked {name}"))
+}
+
+/// Mutes an account, online or not. A new mute replaces any the account already has.
+fn command_mute(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    args: CommandArgs,
+) -> Result<()> {
+    let CommandArgs::Mute(name, minutes, reason) = args else {
+        return Ok(());
+    };
+
+    with_account(
+        world,
+        storage,
+        entity,
+        name.clone(),
+        move |world, storage, uid, target| {
+            let issuer = account_id(world, entity)?;
+            let unmute = minutes == Some(0);
+            let expires_on = expiry(minutes);
+
+            storage.saves.save(SaveJob::Sanction {
+                kind: SanctionKind::Mute,
+                target: SanctionTarget::Account(uid),
+                sanction: (!unmute).then(|| NewSanction {
+                    reason: reason.clone(),
+                    issuer,
+                    expires_on,
+                }),
+            })?;
+
+            let (msg, target_msg) = if unmute {
+                (format!("Unmuted {name}"), "You are no longer muted".into())
+            } else {
+                (
+                    format!("Muted {name} {}", duration_text(minutes)),
+                    format!("You have been muted {}: {reason}", duration_text(minutes)),
+                )
+            };
+
+            if let Some(target) = target
+                && let Some(Entity::Player(p_data)) = world.get_opt_entity(target)
+            {
+                let tick = *storage.gettick.borrow();
+
+                {
+                    let mut p_data = p_data.try_lock()?;
+
+                    if unmute {
+                        p_data.mute_timer.clear();
+                    } else {
+                        p_data.mute_timer.set(expires_on, tick);
+                    }
+                }
+
+                reply(world, storage, target, target_msg)?;
+            }
+
+            reply(world, storage, entity, msg)
+        },
+    )
+}
+
+fn command_ban(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    args: CommandArgs,
+) -> Result<()> {
+    let CommandArgs::Ban(name, minutes, reason) = args else {
+        return Ok(());
+    };
+
+    with_account(
+        world,
+        storage,
+        entity,
+        name.clone(),
+        move |world, storage, uid, target| {
+            let issuer = account_id(world, entity)?;
+
+            storage.saves.save(SaveJob::Sanction {
+                kind: SanctionKind::Ban,
+                target: SanctionTarget::Account(uid),
+                sanction: Some(NewSanction {
+                    reason: reason.clone(),
+                    issuer,
+                    expires_on: expiry(minutes),
+                }),
+            })?;
+
+            if let Some(target) = target {
+                kick_player(
+                    world,
+                    storage,
+                    target,
+                    format!("You have been banned {}: {reason}", duration_text(minutes)),
+                )?;
+            }
+
+            reply(
+                world,
+                storage,
+                entity,
+                format!("Banned {name} {}", duration_text(minutes)),
+            )
+        },
+    )
+}
+
+/// Lifts every active ban on an account. The write is queued so there is no telling
+/// here whether the account had one.
+fn command_unban(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    args: CommandArgs,
+) -> Result<()> {
+    let CommandArgs::Player(name) = args else {
+        return Ok(());
+    };
+
+    with_account(
+        world,
+        storage,
+        entity,
+        name.clone(),
+        move |world, storage, uid, _| {
+            storage.saves.save(SaveJob::Sanction {
+                kind: SanctionKind::Ban,
+                target: SanctionTarget::Account(uid),
+                sanction: None,
+            })?;
+
+            reply(world, storage, entity, format!("Unbanned {name}"))
+        },
+    )
+}
+
+/// Bans an address or range and kicks everyone connected from it except the user.
+fn command_ipban(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    args: CommandArgs,
+) -> Result<()> {
+    let CommandArgs::IpBan(range, minutes, reason) = args else {
+        return Ok(());
+    };
+
+    let issuer = account_id(world, entity)?;
+    let expires_on = expiry(minutes);
+
+    storage.saves.save(SaveJob::Sanction {
+        kind: SanctionKind::Ban,
+        target: SanctionTarget::Address(range),
+        sanction: Some(NewSanction {
+            reason: reason.clone(),
+            issuer,
+            expires_on,
+        }),
+    })?;
+
+    // Cached right away so the address can not reconnect before the refresh is back.
+    storage.ip_bans.borrow_mut().ban(range, expires_on);
+    refresh_ip_bans(storage)?;
+
+    let ids: Vec<GlobalKey> = storage.player_ids.borrow().iter().copied().collect();
+    let mut kicked = 0;
+
+    for id in ids {
+        let in_range = match world.get_opt_entity(id) {
+            Some(Entity::Player(p_data)) if id != entity => {
+                socket_ip(&p_data.try_lock()?.socket.addr).is_some_and(|ip| range.contains(ip))
+            }
+            _ => false,
+        };
+
+        if in_range {
+            kick_player(
+                world,
+                storage,
+                id,
+                format!("You have been banned {}: {reason}", duration_text(minutes)),
+            )?;
+            kicked += 1;
+        }
+    }
+
+    reply(
+        world,
+        storage,
+        entity,
+        format!(
+            "Banned {range} {} and kicked {kicked} player(s)",
+            duration_text(minutes)
+        ),
+    )
+}
+
+fn command_unipban(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    args: CommandArgs,
+) -> Result<()> {
+    let CommandArgs::Address(range) = args else {
+        return Ok(());
+    };
+
+    storage.saves.save(SaveJob::Sanction {
+        kind: SanctionKind::Ban,
+        target: SanctionTarget::Address(range),
+        sanction: None,
+    })?;
+
+    let lifted = storage.ip_bans.borrow_mut().unban(range);
+
+    refresh_ip_bans(storage)?;
+
+    let msg = if lifted {
+        format!("Unbanned {range}")
+    } else {
+        format!("{range} is not banned")
+    };
+
+    reply(world, storage, entity, msg)
+}
+
+/// Lists an account's newest bans and mutes, including ones that have ended.
+/// Ones still waiting in the save worker are not listed.
+fn command_sanctions(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    args: CommandArgs,
+) -> Result<()> {
+    let CommandArgs::History(name, count) = args else {
+        return Ok(());
+    };
+
+    with_account(
+        world,
+        storage,
+        entity,
+        name.clone(),
+        move |_, storage, uid, _| {
+            storage.lookups.lookup(move |pool| async move {
+                let sanctions = sql_load_sanctions(&pool, uid, count).await;
+
+                Box::new(move |world: &mut World, storage: &Storage| {
+                    list_sanctions(world, storage, entity, &name, sanctions?)
+                }) as LookupReply
+            });
+
+            Ok(())
+        },
+    )
+}
+
+fn list_sanctions(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    name: &str,
+    sanctions: Vec<PGSanction>,
+) -> Result<()> {
+    if sanctions.is_empty() {
+        return reply(world, storage, entity, format!("{name} has no sanctions"));
+    }
+
+    let now = Utc::now();
+
+    for sanction in sanctions {
+        let ends = match sanction.expires_on {
+            Some(expires_on) if expires_on <= now => "ended".to_string(),
+            Some(expires_on) => format!("until {}", expires_on.format("%Y-%m-%d %H:%M")),
+            None => "permanent".into(),
+        };
+
+        reply(
+            world,
+            storage,
+            entity,
+            format!(
+                "{} [{:?}] {ends}: {}",
+                sanction.created_on.format("%Y-%m-%d %H:%M"),
+                sanction.kind,
+                sanction.reason
+            ),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Lists an account's newest audit log rows. Works for offline players too.
+fn command_history(
+    _world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    args: CommandArgs,
+) -> Result<()> {
+    let CommandArgs::History(name, count) = args else {
+        return Ok(());
+    };
+
+    storage.lookups.lookup(move |pool| async move {
+        let logs: sqlx::Result<Option<Vec<PGLog>>> = async {
+            let Some((uid, _)) = sql_find_account(&pool, &name).await? else {
+                return Ok(None);
+            };
+
+            sql_load_logs(&pool, uid, count).await.map(Some)
+        }
+        .await;
+
+        Box::new(move |world: &mut World, storage: &Storage| match logs? {
+            Some(logs) => list_history(world, storage, entity, &name, logs),
+            None => reply(world, storage, entity, format!("No account named {name}")),
+        }) as LookupReply
+    });
+
+    Ok(())
+}
+
+fn list_history(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    name: &str,
+    logs: Vec<PGLog>,
+) -> Result<()> {
+    if logs.is_empty() {
+        return reply(world, storage, entity, format!("{name} has no history"));
+    }
+
+    reply(
+        world,
+        storage,
+        entity,
+        format!("Last {} log entries for {name}:", logs.len()),
+    )?;
+
+    for log in logs {
+        reply(
+            world,
+            storage,
+            entity,
+            format!(
+                "{} [{:?}] {} ({})",
+                log.logged_on.format("%Y-%m-%d %H:%M:%S"),
+                log.logtype,
+                log.message,
+                log.ipaddress
+            ),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn command_warp(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    args: CommandArgs,
+) -> Result<()> {
+    let CommandArgs::Warp(name, pos) = args else {
+        return Ok(());
+    };
+
+    if !storage.bases.maps.contains_key(&pos.map) {
+        return reply(world, storage, entity, "That map does not exist".into());
+    }
+
+    let target = match &name {
+        Some(name) => match find_target(world, storage, entity, name)? {
+            Some(target) => target,
+            None => return Ok(()),
+        },
+        None => entity,
+    };
+
+    if let Some(name) = name
+        && target != entity
+        && !outranks(world, entity, target)?
+    {
+        return reply(world, storage, entity, format!("You can not warp {name}"));
+    }
+
+    player_warp(world, storage, target, &pos, false)
+}
+
+fn command_spawn(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    args: CommandArgs,
+) -> Result<()> {
+    let CommandArgs::Spawn(index, pos) = args else {
+        return Ok(());
+    };
+
+    if storage.bases.npc(index as usize).is_none() {
+        return reply(
+            world,
+            storage,
+            entity,
+            format!("Npc {index} does not exist"),
+        );
+    }
+
+    let pos = match pos {
+        Some(pos) => pos,
+        None => {
+            if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+                p_data.try_lock()?.movement.pos
+            } else {
+                return Ok(());
+            }
+        }
+    };
+
+    let Some(mapdata) = storage.maps.get(&pos.map) else {
+        return reply(world, storage, entity, "That map does not exist".into());
+    };
+
+    let mut data = mapdata.borrow_mut();
+
+    if let Some(id) = storage.add_npc(world, index)? {
+        data.add_npc(id);
+        spawn_npc(world, pos, None, id)?;
+    }
+
+    Ok(())
+}
+
+fn command_give(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    args: CommandArgs,
+) -> Result<()> {
+    let CommandArgs::Give(name, num, amount) = args else {
+        return Ok(());
+    };
+
+    let Some(base) = storage.bases.item(num as usize) else {
+        return reply(world, storage, entity, format!("Item {num} does not exist"));
+    };
+
+    let Some(target) = find_target(world, storage, entity, &name)? else {
+        return Ok(());
+    };
+
+    let mut item = Item {
+        num,
+        val: if base.stackable {
+            amount.min(base.stacklimit)
+        } else {
+            1
+        },
+        ..Default::default()
+    };
+
+    if !check_inv_space(world, storage, target, &mut item)? {
+        return reply(
+            world,
+            storage,
+            entity,
+            format!("{name} does not have enough inventory space"),
+        );
+    }
+
+    let given = item.val;
+
+    give_inv_item(world, storage, target, &mut item)?;
+    reply(
+        world,
+        storage,
+        entity,
+        format!("Gave {name} {given} of item {num}"),
+    )
+}
+
+fn command_setlevel(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    args: CommandArgs,
+) -> Result<()> {
+    let CommandArgs::SetLevel(name, level) = args else {
+        return Ok(());
+    };
+
+    let max_level = storage.bases.progression.max_level;
+
+    if level > max_level {
+        return reply(
+            world,
+            storage,
+            entity,
+            format!("level must be within 1-{max_level}"),
+        );
+    }
+
+    let Some(target) = find_target(world, storage, entity, &name)? else {
+        return Ok(());
+    };
+
+    let Some(Entity::Player(p_data)) = world.get_opt_entity(target) else {
+        return Ok(());
+    };
+
+    let position = {
+        let mut p_data = p_data.try_lock()?;
+
+        p_data.combat.level = level;
+        p_data.general.levelexp = 0;
+        p_data.movement.pos
+    };
+
+    player_update_stats(world, storage, target)?;
+
+    for i in 0..VitalTypes::Count as usize {
+        p_data.try_lock()?.combat.vitals.vital[i] = player_add_up_vital(world, target, i)?;
+    }
+
+    let vitals = { p_data.try_lock()?.combat.vitals };
+
+    send_level(world, storage, target)?;
+    DataTaskToken::Vitals(position.map).add_task(
+        storage,
+        vitals_packet(target, vitals.vital, vitals.vitalmax)?,
+    )?;
+    update_level(storage, world, target)?;
+
+    reply(
+        world,
+        storage,
+        entity,
+        format!("Set {name} to level {level}"),
+    )
+}
@@ -1,23 +1,78 @@
 use super::SocketID;
 use crate::{
     containers::{
-        Entity, EntityKind, GlobalKey, PlayerConnectionTimer, Socket, Storage, World,
-        create_player_entity,
+        CharacterSelect, Entity, EntityKind, GlobalKey, PlayerConnectionTimer, Socket, Storage,
+        World, create_player_entity, socket_ip,
     },
     gametypes::*,
+    mail::Mail,
     players::{
         is_name_acceptable, is_password_acceptable, joingame, reconnect_player, send_login_info,
         send_reconnect_info,
     },
-    socket::{ClientState, disconnect, send_codes, send_infomsg, send_myindex},
-    sql::{check_existance, find_player, load_player, new_player},
+    socket::{
+        ClientState, MIN_PROTOCOL_VERSION, Protocol, client_protocol, disconnect,
+        send_characterlist, send_codes, send_infomsg, send_myindex, send_protocol_accept,
+        send_protocol_reject, set_client_as_closed,
+    },
+    sql::{
+        LoadRequest, LoadResult, LoginOutcome, LoginRequest, LoginResult, LookupReply,
+        NewCharacter, PGLog, RemovedCharacter, ResetAction, ResetOutcome, ResetRequest,
+        ResetResult, add_log, check_existance, load_player, new_player, player_log,
+        sql_add_character, sql_load_characters, sql_remove_character,
+    },
 };
-use chrono::Duration;
-use log::info;
+use chrono::{Duration, Utc};
+use log::{error, info, warn};
 use mmap_bytey::MByteBuffer;
 use rand::distr::{Alphanumeric, SampleString};
 use regex::Regex;
 use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// The first packet a client sends. Agrees on a protocol version and feature set or
+/// rejects clients too old to be served.
+pub fn handle_protocol_hello(
+    _world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    socket_id: SocketID,
+) -> Result<()> {
+    let version = data.read::<u16>()?;
+    let features = data.read::<u32>()?;
+    let appmajor = data.read::<u16>()? as usize;
+    let appminor = data.read::<u16>()? as usize;
+    let apprevision = data.read::<u16>()? as usize;
+
+    if entity.is_some() {
+        return Err(AscendingError::InvalidSocket);
+    }
+
+    if version < MIN_PROTOCOL_VERSION
+        || (appmajor, appminor, apprevision) < (APP_MAJOR, APP_MINOR, APP_REVISION)
+    {
+        let addr = client_addr(storage, socket_id.id)?;
+
+        info!(
+            "Rejected client with protocol {version} and version {appmajor}.{appminor}.{apprevision}, IP: {addr}"
+        );
+
+        send_protocol_reject(storage, socket_id.id, "Client needs to be updated.".into())?;
+        set_client_as_closed(storage, socket_id.id);
+        return Ok(());
+    }
+
+    let protocol = Protocol::negotiate(version, features);
+
+    if let Some(client) = storage.server.borrow().clients.get(socket_id.id) {
+        client.borrow_mut().protocol = Some(protocol);
+    } else {
+        return Err(AscendingError::InvalidSocket);
+    }
+
+    send_protocol_accept(storage, socket_id.id, protocol)
+}
 
 pub fn handle_register(
     world: &mut World,
@@ -30,9 +85,6 @@ pub fn handle_register(
     let password = data.read::<String>()?;
     let email = data.read::<String>()?;
     let sprite_id = data.read::<u8>()?;
-    let appmajor = data.read::<u16>()? as usize;
-    let appminior = data.read::<u16>()? as usize;
-    let apprevision = data.read::<u16>()? as usize;
 
     if entity.is_some() {
         return Err(AscendingError::InvalidSocket);
@@ -45,15 +97,6 @@ pub fn handle_register(
         return Err(AscendingError::InvalidSocket);
     };
 
-    if APP_MAJOR > appmajor && APP_MINOR > appminior && APP_REVISION > apprevision {
-        return send_infomsg(
-            storage,
-            socket.tls_id,
-            "Client needs to be updated.".into(),
-            1,
-        );
-    }
-
     let email_regex = Regex::new(r#"^[^\s@]+@([^\s@.,]+\.)+[^\s@.,]{2,}$"#)?;
 
     if !username.chars().all(is_name_acceptable) || !password.chars().all(is_password_acceptable) {
@@ -116,8 +159,15 @@ pub fn handle_register(
         Err(_) => return Err(AscendingError::UserNotFound),
     }
 
-    match new_player(storage, username.clone(), email, password, &socket) {
-        Ok(uid) => {
+    match new_player(
+        storage,
+        username.clone(),
+        email,
+        password,
+        sprite_id as u16,
+        &socket,
+    ) {
+        Ok((uid, cid)) => {
             let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
             let handshake = Alphanumeric.sample_string(&mut rand::rng(), 32);
 
@@ -130,13 +180,20 @@ pub fn handle_register(
             }
 
             if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
-                let mut p_data = p_data.try_lock()?;
+                let p_data = &mut *p_data.try_lock()?;
 
                 p_data.account.username.clone_from(&username);
                 p_data.account.id = uid;
+                p_data.account.character = cid;
                 p_data.sprite.id = sprite_id as u16;
+                storage
+                    .bases
+                    .progression
+                    .apply(&mut p_data.combat, &p_data.attributes);
             }
 
+            world.account_id.insert(uid, entity);
+
             storage
                 .hand_shakes
                 .borrow_mut()
@@ -164,7 +221,7 @@ pub fn handle_register(
 
             info!(
                 "New Player {} with IP {}, Logging in.",
-                &username, &socket.addr
+                username, socket.addr
             );
 
             send_myindex(storage, socket.tls_id, entity)?;
@@ -206,8 +263,14 @@ pub fn handle_handshake(
 
                 p_data.socket.id = socket_id.id;
 
+                // The game socket keeps what the login socket negotiated.
+                let protocol = client_protocol(storage, p_data.socket.tls_id);
+
                 if let Some(client) = storage.server.borrow().clients.get(socket_id.id) {
-                    client.borrow_mut().entity = Some(entity);
+                    let mut client = client.borrow_mut();
+
+                    client.entity = Some(entity);
+                    client.protocol = protocol;
                 }
 
                 can_join = true;
@@ -223,7 +286,7 @@ pub fn handle_handshake(
 }
 
 pub fn handle_login(
-    world: &mut World,
+    _world: &mut World,
     storage: &Storage,
     data: &mut MByteBuffer,
     entity: Option<GlobalKey>,
@@ -231,43 +294,111 @@ pub fn handle_login(
 ) -> Result<()> {
     let username = data.read::<String>()?;
     let password = data.read::<String>()?;
-    let appmajor = data.read::<u16>()? as usize;
-    let appminior = data.read::<u16>()? as usize;
-    let apprevision = data.read::<u16>()? as usize;
     let reconnect_code = data.read::<String>()?;
 
     if entity.is_some() {
         return Err(AscendingError::InvalidSocket);
     }
 
-    let socket = if let Some(client) = storage.server.borrow().clients.get(socket_id.id) {
-        let brw_client = client.borrow();
-        Socket::new(usize::MAX, socket_id.id, brw_client.addr.to_string())?
-    } else {
-        return Err(AscendingError::InvalidSocket);
-    };
-
-    if APP_MAJOR > appmajor && APP_MINOR > appminior && APP_REVISION > apprevision {
-        return send_infomsg(
-            storage,
-            socket.tls_id,
-            "Client needs to be updated.".into(),
-            1,
-        );
-    }
+    let addr = client_addr(storage, socket_id.id)?;
 
     if username.len() >= 64 || password.len() >= 128 {
         return send_infomsg(
             storage,
-            socket.tls_id,
+            socket_id.id,
             "Account does not Exist or Password is not Correct.".into(),
             0,
         );
     }
 
-    let id = match find_player(storage, &username, &password)? {
-        Some(id) => id,
-        None => {
+    if socket_ip(&addr).is_some_and(|ip| storage.ip_bans.borrow().is_banned(ip)) {
+        return send_infomsg(storage, socket_id.id, "This address is banned.".into(), 1);
+    }
+
+    if throttled(storage, socket_id.id, &addr)? {
+        return Ok(());
+    }
+
+    // Only one login per socket is checked at a time.
+    if !storage.pending_logins.borrow_mut().insert(socket_id.id) {
+        return Ok(());
+    }
+
+    storage.logins.check(LoginRequest {
+        socket_id: socket_id.id,
+        addr,
+        username,
+        password,
+        reconnect_code,
+    });
+
+    Ok(())
+}
+
+/// Finishes the logins and password resets the login worker has checked since the last call.
+pub fn process_login_results(world: &mut World, storage: &Storage) -> Result<()> {
+    while let Some(result) = storage.logins.next_result() {
+        let socket_id = result.socket_id;
+
+        storage.pending_logins.borrow_mut().remove(&socket_id);
+
+        if let Err(e) = finish_login(world, storage, result) {
+            warn!("Socket {socket_id} was disconnected due to a login error: {e}");
+            set_client_as_closed(storage, socket_id);
+        }
+    }
+
+    while let Some(result) = storage.logins.next_reset() {
+        let socket_id = result.socket_id;
+
+        if let Err(e) = finish_reset(storage, result) {
+            warn!("Password reset for socket {socket_id} failed: {e}");
+        }
+    }
+
+    Ok(())
+}
+
+fn finish_login(world: &mut World, storage: &Storage, result: LoginResult) -> Result<()> {
+    let LoginResult {
+        socket_id,
+        addr,
+        reconnect_code,
+        outcome,
+    } = result;
+
+    // The client could have left while the password was checked and its id been reused.
+    let socket = match storage.server.borrow().clients.get(socket_id) {
+        Some(client) if Arc::ptr_eq(&client.borrow().addr, &addr) => {
+            Socket::new(usize::MAX, socket_id, addr.to_string())?
+        }
+        _ => return Ok(()),
+    };
+
+    let tick = *storage.gettick.borrow();
+
+    let id = match outcome {
+        LoginOutcome::Success(id) => {
+            storage.login_throttle.borrow_mut().succeeded(&socket.addr);
+            id
+        }
+        LoginOutcome::Failed(uid) => {
+            let wait = storage
+                .login_throttle
+                .borrow_mut()
+                .failed(&socket.addr, tick);
+
+            if let Some(uid) = uid {
+                login_log(storage, uid, "Failed login".into(), &socket.addr)?;
+            }
+
+            if let Some(wait) = wait {
+                warn!(
+                    "IP: {} has to wait {wait}ms after too many failed logins.",
+                    socket.addr
+                );
+            }
+
             return send_infomsg(
                 storage,
                 socket.tls_id,
@@ -275,8 +406,100 @@ pub fn handle_login(
                 1,
             );
         }
+        LoginOutcome::Locked { uid, until, newly } => {
+            storage
+                .login_throttle
+                .borrow_mut()
+                .failed(&socket.addr, tick);
+
+            if newly {
+                login_log(
+                    storage,
+                    uid,
+                    format!("Account locked until {until} after too many failed logins"),
+                    &socket.addr,
+                )?;
+            }
+
+            let minutes = ((until - Utc::now()).num_seconds().max(0) + 59) / 60;
+
+            return send_infomsg(
+                storage,
+                socket.tls_id,
+                format!(
+                    "This account is locked after too many failed logins. Try again in {} minute(s).",
+                    minutes.max(1)
+                ),
+                1,
+            );
+        }
+        LoginOutcome::Banned { uid, reason, until } => {
+            login_log(
+                storage,
+                uid,
+                "Refused login while banned".into(),
+                &socket.addr,
+            )?;
+
+            let until = match until {
+                Some(until) => format!("until {}", until.format("%Y-%m-%d %H:%M UTC")),
+                None => "permanently".into(),
+            };
+
+            return send_infomsg(
+                storage,
+                socket.tls_id,
+                format!("This account is banned {until}. Reason: {reason}"),
+                1,
+            );
+        }
+        LoginOutcome::Error(e) => {
+            error!("Login check for IP: {} failed: {e}", socket.addr);
+            return send_infomsg(storage, socket.tls_id, "Error Loading User.".into(), 1);
+        }
+    };
+
+    let reconnect_character = match storage.player_code.borrow().get(&reconnect_code) {
+        Some(old_entity) => match world.get_opt_entity(*old_entity) {
+            Some(Entity::Player(p_data)) => {
+                let p_data = p_data.try_lock()?;
+
+                (p_data.account.id == id).then_some(p_data.account.character)
+            }
+            _ => None,
+        },
+        None => None,
     };
 
+    // A client coming back to a character still in the world skips the select screen.
+    if !reconnect_code.is_empty()
+        && let Some(cid) = reconnect_character
+    {
+        return enter_game(world, storage, socket, id, cid, reconnect_code);
+    }
+
+    storage.character_select.borrow_mut().insert(
+        socket_id,
+        CharacterSelect {
+            uid: id,
+            reconnect_code,
+        },
+    );
+
+    send_character_list(storage, socket_id, id)
+}
+
+/// Puts the account's character into the world, taking over or replacing an older
+/// session of the same account. A fresh character is loaded on the save worker and
+/// finished in process_load_results.
+fn enter_game(
+    world: &mut World,
+    storage: &Storage,
+    socket: Socket,
+    uid: Uuid,
+    cid: Uuid,
+    reconnect_code: String,
+) -> Result<()> {
     // we need to Add all the player types creations in a sub function that Creates the Defaults and then adds them to World.
     let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
     let handshake = Alphanumeric.sample_string(&mut rand::rng(), 32);
@@ -286,6 +509,7 @@ pub fn handle_login(
 
     if let Some(old_entity) = storage.player_code.borrow().get(&reconnect_code)
         && let Some(Entity::Player(p_data)) = world.get_opt_entity(*old_entity)
+        && p_data.try_lock()?.account.character == cid
     {
         if storage.disconnected_player.borrow().contains(old_entity) {
             // Character is on disconnected list
@@ -295,7 +519,7 @@ pub fn handle_login(
 
             info!(
                 "Player {} with IP: {}, Reconnecting from disconnected player.",
-                &name, socket.addr
+                name, socket.addr
             );
 
             {
@@ -326,7 +550,7 @@ pub fn handle_login(
 
                     info!(
                         "Player {} with IP: {}, Reconnecting not in disconnected player.",
-                        &name, &p_data.socket.addr
+                        name, p_data.socket.addr
                     );
 
                     send_reconnect = Some((*old_entity, name, p_data.socket.id));
@@ -371,24 +595,8 @@ pub fn handle_login(
         }
     }
 
-    let user_entity = world.entities.iter().find_map(|(entity, data)| {
-        if let Entity::Player(p_data) = data {
-            if let Ok(player) = p_data.try_lock() {
-                if player.account.username == username {
-                    Some(entity)
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    });
-
     // This check is in case the account is connected on different entity
-    if let Some(old_entity) = user_entity
+    if let Some(old_entity) = world.get_account_id(&uid)
         && let Some(Entity::Player(p_data)) = world.get_opt_entity(old_entity)
     {
         let old_code = { p_data.try_lock()?.relogin_code.clone() };
@@ -405,18 +613,83 @@ pub fn handle_login(
         }
     }
 
-    let entity = world.kinds.insert(EntityKind::Player);
+    let addr = client_addr(storage, socket.tls_id)?;
+
+    // The socket stays pending so it can not log in again while the character loads.
+    storage.pending_logins.borrow_mut().insert(socket.tls_id);
+    storage.saves.load(LoadRequest {
+        socket_id: socket.tls_id,
+        addr,
+        uid,
+        cid,
+    })
+}
+
+/// Puts the characters the save worker has loaded since the last call into the world.
+pub fn process_load_results(world: &mut World, storage: &Storage) -> Result<()> {
+    while let Some(result) = storage.saves.next_load() {
+        let socket_id = result.socket_id;
+
+        storage.pending_logins.borrow_mut().remove(&socket_id);
+
+        if let Err(e) = finish_enter_game(world, storage, result) {
+            warn!("Socket {socket_id} was disconnected due to a login error: {e}");
+            set_client_as_closed(storage, socket_id);
+        }
+    }
+
+    Ok(())
+}
+
+fn finish_enter_game(world: &mut World, storage: &Storage, result: LoadResult) -> Result<()> {
+    let LoadResult {
+        socket_id,
+        addr,
+        uid,
+        cid,
+        data,
+    } = result;
+
+    // The client could have left while the character loaded and its id been reused.
+    let socket = match storage.server.borrow().clients.get(socket_id) {
+        Some(client) if Arc::ptr_eq(&client.borrow().addr, &addr) => {
+            Socket::new(usize::MAX, socket_id, addr.to_string())?
+        }
+        _ => return Ok(()),
+    };
+
+    let data = match data {
+        Ok(data) => data,
+        Err(e) => {
+            error!(
+                "Loading character {cid} for IP: {} failed: {e}",
+                socket.addr
+            );
+            return send_infomsg(storage, socket.tls_id, "Error Loading User.".into(), 1);
+        }
+    };
+
+    // Another session of the account may have come in while this one loaded.
+    if world.get_account_id(&uid).is_some() {
+        return send_infomsg(storage, socket.tls_id, "Error Loading User.".into(), 1);
+    }
+
+    let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
+    let handshake = Alphanumeric.sample_string(&mut rand::rng(), 32);
     let mut player_entity = create_player_entity(code.clone(), handshake.clone(), socket.clone());
 
-    if let Err(_e) = load_player(storage, &mut player_entity, id) {
+    if let Err(_e) = load_player(storage, &mut player_entity, uid, cid, data) {
         return send_infomsg(storage, socket.tls_id, "Error Loading User.".into(), 1);
     }
 
+    let entity = world.kinds.insert(EntityKind::Player);
+
     world
         .entities
         .insert(entity, Entity::Player(Arc::new(Mutex::new(player_entity))));
 
     storage.player_ids.borrow_mut().insert(entity);
+    world.account_id.insert(uid, entity);
 
     if let Some(client) = storage.server.borrow_mut().clients.get_mut(socket.tls_id) {
         client.borrow_mut().entity = Some(entity);
@@ -437,12 +710,484 @@ pub fn handle_login(
     let name = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
         let name = p_data.try_lock()?.account.username.clone();
 
-        info!("Player {} with IP: {}, Logging in.", &name, &socket.addr);
+        info!("Player {} with IP: {}, Logging in.", name, socket.addr);
 
         name
     } else {
         return send_infomsg(storage, socket.tls_id, "Error Loading User.".into(), 1);
     };
 
+    player_log(world, storage, entity, LogType::Login, "Logged in".into())?;
+
     send_login_info(world, storage, entity, code, handshake, socket.tls_id, name)
 }
+
+pub fn handle_request_password_reset(
+    _world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    socket_id: SocketID,
+) -> Result<()> {
+    let email = data.read::<String>()?;
+
+    if entity.is_some() {
+        return Err(AscendingError::InvalidSocket);
+    }
+
+    let addr = client_addr(storage, socket_id.id)?;
+
+    if throttled(storage, socket_id.id, &addr)? {
+        return Ok(());
+    }
+
+    // Requests count as failed attempts so an address can not flood someone's inbox.
+    let tick = *storage.gettick.borrow();
+    storage.login_throttle.borrow_mut().failed(&addr, tick);
+
+    let code = Alphanumeric.sample_string(&mut rand::rng(), PASSWORD_RESET_CODE_LEN);
+
+    if email.len() < 128 {
+        storage.logins.reset(ResetRequest {
+            socket_id: socket_id.id,
+            addr,
+            action: ResetAction::Request { email, code },
+        });
+    }
+
+    // The same reply either way so it does not give away which emails have accounts.
+    send_infomsg(
+        storage,
+        socket_id.id,
+        "If an account uses that email a reset code has been sent to it.".into(),
+        0,
+    )
+}
+
+pub fn handle_reset_password(
+    _world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    socket_id: SocketID,
+) -> Result<()> {
+    let email = data.read::<String>()?;
+    let code = data.read::<String>()?;
+    let password = data.read::<String>()?;
+
+    if entity.is_some() {
+        return Err(AscendingError::InvalidSocket);
+    }
+
+    let addr = client_addr(storage, socket_id.id)?;
+
+    if throttled(storage, socket_id.id, &addr)? {
+        return Ok(());
+    }
+
+    if !password.chars().all(is_password_acceptable) {
+        return send_infomsg(
+            storage,
+            socket_id.id,
+            "Password contains unaccepted Characters".into(),
+            0,
+        );
+    }
+
+    if password.len() >= 128 {
+        return send_infomsg(
+            storage,
+            socket_id.id,
+            "Password has too many Characters, 128 Characters Max".into(),
+            0,
+        );
+    }
+
+    if email.len() >= 128 || code.len() != PASSWORD_RESET_CODE_LEN {
+        return reset_failed(storage, socket_id.id, &addr);
+    }
+
+    storage.logins.reset(ResetRequest {
+        socket_id: socket_id.id,
+        addr,
+        action: ResetAction::Reset {
+            email,
+            code,
+            password,
+        },
+    });
+
+    Ok(())
+}
+
+/// Mails out the codes and finishes the password resets the login worker has done.
+fn finish_reset(storage: &Storage, result: ResetResult) -> Result<()> {
+    let ResetResult {
+        socket_id,
+        addr,
+        outcome,
+    } = result;
+
+    // Replies only go out if the client is still on the socket it asked from.
+    let connected = storage
+        .server
+        .borrow()
+        .clients
+        .get(socket_id)
+        .is_some_and(|client| Arc::ptr_eq(&client.borrow().addr, &addr));
+
+    match outcome {
+        ResetOutcome::Requested {
+            email,
+            code,
+            account: Some((uid, username)),
+        } => {
+            storage.mailer.send(Mail {
+                to: email,
+                subject: "Password reset".into(),
+                body: format!(
+                    "Hello {username},\n\nYour password reset code is {code}\n\nIt expires in {} minutes. If you did not ask for a reset you can ignore this mail.",
+                    PASSWORD_RESET_EXPIRE_SECS / 60
+                ),
+            })?;
+
+            login_log(storage, uid, "Password reset requested".into(), &addr)
+        }
+        ResetOutcome::Requested { account: None, .. } => Ok(()),
+        ResetOutcome::Reset(Some(uid)) => {
+            storage.login_throttle.borrow_mut().succeeded(&addr);
+            login_log(storage, uid, "Password was reset".into(), &addr)?;
+
+            if !connected {
+                return Ok(());
+            }
+
+            send_infomsg(
+                storage,
+                socket_id,
+                "Your password has been changed. You can now log in.".into(),
+                0,
+            )
+        }
+        ResetOutcome::Reset(None) if connected => reset_failed(storage, socket_id, &addr),
+        ResetOutcome::Reset(None) => {
+            let tick = *storage.gettick.borrow();
+            storage.login_throttle.borrow_mut().failed(&addr, tick);
+            Ok(())
+        }
+        ResetOutcome::Error(e) => {
+            error!("Password reset for IP: {addr} failed: {e}");
+            Ok(())
+        }
+    }
+}
+
+fn reset_failed(storage: &Storage, socket_id: usize, addr: &str) -> Result<()> {
+    let tick = *storage.gettick.borrow();
+    storage.login_throttle.borrow_mut().failed(addr, tick);
+
+    send_infomsg(
+        storage,
+        socket_id,
+        "The reset code is not valid or has expired.".into(),
+        0,
+    )
+}
+
+pub fn handle_create_character(
+    _world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    socket_id: SocketID,
+) -> Result<()> {
+    let name = data.read::<String>()?;
+    let sprite_id = data.read::<u8>()?;
+
+    let Some(uid) = selecting_account(storage, entity, socket_id.id)? else {
+        return Err(AscendingError::InvalidSocket);
+    };
+
+    if name.is_empty() || !name.chars().all(is_name_acceptable) {
+        return send_infomsg(
+            storage,
+            socket_id.id,
+            "Name contains unaccepted Characters".into(),
+            0,
+        );
+    }
+
+    if name.len() >= 64 {
+        return send_infomsg(
+            storage,
+            socket_id.id,
+            "Name has too many Characters, 64 Characters Max".into(),
+            0,
+        );
+    }
+
+    if sprite_id >= 6 {
+        return Err(AscendingError::InvalidSocket);
+    }
+
+    let Some(addr) = begin_select_request(storage, socket_id.id)? else {
+        return Ok(());
+    };
+    let socket_id = socket_id.id;
+
+    storage.lookups.lookup(move |pool| async move {
+        let created = sql_add_character(&pool, uid, &name, sprite_id as u16).await;
+
+        Box::new(move |_: &mut World, storage: &Storage| {
+            if !finish_select_request(storage, socket_id, &addr, uid) {
+                return Ok(());
+            }
+
+            let message = match created {
+                Ok(NewCharacter::Created(characters)) => {
+                    login_log(storage, uid, format!("Created character {name}"), &addr)?;
+                    return send_characterlist(storage, socket_id, &characters);
+                }
+                Ok(NewCharacter::NameTaken) => "Name Exists. Please try Another.",
+                Ok(NewCharacter::SlotsFull) => "All character slots are in use.",
+                Err(e) => {
+                    error!("Failed to create character {name}: {e}");
+                    "There was an Issue Creating the character. Please Contact Support."
+                }
+            };
+
+            send_infomsg(storage, socket_id, message.into(), 0)
+        }) as LookupReply
+    });
+
+    Ok(())
+}
+
+pub fn handle_delete_character(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    socket_id: SocketID,
+) -> Result<()> {
+    let slot = data.read::<u8>()?;
+
+    let Some(uid) = selecting_account(storage, entity, socket_id.id)? else {
+        return Err(AscendingError::InvalidSocket);
+    };
+
+    // A character waiting to be reconnected to is still in the world.
+    let online = match world.get_account_id(&uid) {
+        Some(old_entity) => match world.get_opt_entity(old_entity) {
+            Some(Entity::Player(p_data)) => Some(p_data.try_lock()?.account.character),
+            _ => None,
+        },
+        None => None,
+    };
+
+    let Some(addr) = begin_select_request(storage, socket_id.id)? else {
+        return Ok(());
+    };
+    let socket_id = socket_id.id;
+
+    storage.lookups.lookup(move |pool| async move {
+        let removed = sql_remove_character(&pool, uid, slot as i16, online).await;
+
+        Box::new(move |_: &mut World, storage: &Storage| {
+            if !finish_select_request(storage, socket_id, &addr, uid) {
+                return Ok(());
+            }
+
+            let message = match removed? {
+                RemovedCharacter::Deleted(name, characters) => {
+                    if let Some(name) = name {
+                        login_log(storage, uid, format!("Deleted character {name}"), &addr)?;
+                    }
+
+                    return send_characterlist(storage, socket_id, &characters);
+                }
+                RemovedCharacter::Missing => "That character does not exist.",
+                RemovedCharacter::LoggedIn => "That character is still logged in.",
+                RemovedCharacter::InGuild => "Leave your guild before deleting this character.",
+            };
+
+            send_infomsg(storage, socket_id, message.into(), 0)
+        }) as LookupReply
+    });
+
+    Ok(())
+}
+
+pub fn handle_select_character(
+    _world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    socket_id: SocketID,
+) -> Result<()> {
+    let slot = data.read::<u8>()?;
+
+    let Some(uid) = selecting_account(storage, entity, socket_id.id)? else {
+        return Err(AscendingError::InvalidSocket);
+    };
+
+    let Some(addr) = begin_select_request(storage, socket_id.id)? else {
+        return Ok(());
+    };
+    let socket_id = socket_id.id;
+
+    storage.lookups.lookup(move |pool| async move {
+        let characters = sql_load_characters(&pool, uid).await;
+
+        Box::new(move |world: &mut World, storage: &Storage| {
+            if !finish_select_request(storage, socket_id, &addr, uid) {
+                return Ok(());
+            }
+
+            let Some(character) = characters?
+                .into_iter()
+                .find(|character| character.slot == slot as i16)
+            else {
+                return send_infomsg(
+                    storage,
+                    socket_id,
+                    "That character does not exist.".into(),
+                    0,
+                );
+            };
+
+            let Some(select) = storage.character_select.borrow_mut().remove(&socket_id) else {
+                return Ok(());
+            };
+
+            let socket = Socket::new(usize::MAX, socket_id, addr.to_string())?;
+
+            enter_game(
+                world,
+                storage,
+                socket,
+                uid,
+                character.cid,
+                select.reconnect_code,
+            )
+        }) as LookupReply
+    });
+
+    Ok(())
+}
+
+/// The account picking a character on this socket, if it has logged in.
+fn selecting_account(
+    storage: &Storage,
+    entity: Option<GlobalKey>,
+    socket_id: usize,
+) -> Result<Option<Uuid>> {
+    if entity.is_some() {
+        return Err(AscendingError::InvalidSocket);
+    }
+
+    Ok(storage
+        .character_select
+        .borrow()
+        .get(&socket_id)
+        .map(|select| select.uid))
+}
+
+/// Marks the socket as busy while a character select request runs on the lookup worker.
+/// Returns the client's address, or None if an earlier request is still running.
+fn begin_select_request(storage: &Storage, socket_id: usize) -> Result<Option<Arc<String>>> {
+    let addr = client_addr(storage, socket_id)?;
+
+    if !storage.pending_logins.borrow_mut().insert(socket_id) {
+        return Ok(None);
+    }
+
+    Ok(Some(addr))
+}
+
+/// Frees the socket again and tells if the client that asked is still picking a
+/// character on the account, as the socket could have been closed and reused since.
+fn finish_select_request(
+    storage: &Storage,
+    socket_id: usize,
+    addr: &Arc<String>,
+    uid: Uuid,
+) -> bool {
+    storage.pending_logins.borrow_mut().remove(&socket_id);
+
+    let connected = storage
+        .server
+        .borrow()
+        .clients
+        .get(socket_id)
+        .is_some_and(|client| Arc::ptr_eq(&client.borrow().addr, addr));
+
+    connected
+        && storage
+            .character_select
+            .borrow()
+            .get(&socket_id)
+            .is_some_and(|select| select.uid == uid)
+}
+
+/// Sends the account's characters once the lookup worker has read them.
+fn send_character_list(storage: &Storage, socket_id: usize, uid: Uuid) -> Result<()> {
+    let Some(addr) = begin_select_request(storage, socket_id)? else {
+        return Ok(());
+    };
+
+    storage.lookups.lookup(move |pool| async move {
+        let characters = sql_load_characters(&pool, uid).await;
+
+        Box::new(move |_: &mut World, storage: &Storage| {
+            if !finish_select_request(storage, socket_id, &addr, uid) {
+                return Ok(());
+            }
+
+            send_characterlist(storage, socket_id, &characters?)
+        }) as LookupReply
+    });
+
+    Ok(())
+}
+
+fn client_addr(storage: &Storage, socket_id: usize) -> Result<Arc<String>> {
+    match storage.server.borrow().clients.get(socket_id) {
+        Some(client) => Ok(client.borrow().addr.clone()),
+        None => Err(AscendingError::InvalidSocket),
+    }
+}
+
+/// Tells the client how long to wait if its address is still blocked from logging in.
+fn throttled(storage: &Storage, socket_id: usize, addr: &str) -> Result<bool> {
+    let tick = *storage.gettick.borrow();
+    let wait = storage.login_throttle.borrow().wait_left(addr, tick);
+
+    if wait > 0 {
+        send_infomsg(
+            storage,
+            socket_id,
+            format!(
+                "Too many failed attempts. Try again in {} seconds.",
+                (wait + 999) / 1000
+            ),
+            0,
+        )?;
+    }
+
+    Ok(wait > 0)
+}
+
+/// Account events from before a player is loaded are logged against the account.
+fn login_log(storage: &Storage, uid: Uuid, message: String, addr: &str) -> Result<()> {
+    add_log(
+        storage,
+        PGLog::new(
+            storage.config.server_id,
+            uid,
+            LogType::Warning,
+            message,
+            addr.to_string(),
+        ),
+    )
+}
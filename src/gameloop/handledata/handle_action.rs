@@ -5,7 +5,9 @@ use crate::{
     containers::{Entity, GlobalKey, Storage, World},
     gametypes::*,
     maps::player_interact_object,
-    players::player_combat,
+    players::{
+        player_allocate_points, player_cast_skill, player_combat, player_set_hotbar, player_set_pvp,
+    },
     tasks::{DataTaskToken, dir_packet},
 };
 
@@ -88,6 +90,7 @@ pub fn handle_attack(
             if !p_data.combat.death_type.is_alive()
                 || p_data.is_using_type.inuse()
                 || p_data.combat.attacking
+                || p_data.combat.stunned
                 || p_data.combat.attack_timer.0 > *storage.gettick.borrow()
             {
                 return Ok(());
@@ -151,3 +154,85 @@ pub fn handle_settarget(
 
     Err(AscendingError::InvalidSocket)
 }
+
+pub fn handle_castskill(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let skill = data.read::<u16>()?;
+    let target = data
+        .read::<Option<GlobalKey>>()?
+        .filter(|target| world.entities.contains_key(*target));
+    let ground = data.read::<Option<Position>>()?;
+
+    player_cast_skill(world, storage, entity, skill, target, ground)
+}
+
+pub fn handle_sethotbar(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let slot = data.read::<u8>()? as usize;
+    let skill = data.read::<Option<u16>>()?;
+
+    if slot >= MAX_HOTBAR {
+        return Err(AscendingError::InvalidPacket);
+    }
+
+    player_set_hotbar(world, storage, entity, slot, skill)
+}
+
+pub fn handle_allocatepoints(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let attribute = data.read::<AttributeType>()?;
+    let amount = data.read::<u16>()?;
+
+    if attribute == AttributeType::Count {
+        return Err(AscendingError::InvalidPacket);
+    }
+
+    player_allocate_points(world, storage, entity, attribute, amount)
+}
+
+pub fn handle_setpvp(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let on = data.read::<bool>()?;
+
+    player_set_pvp(world, storage, entity, on)
+}
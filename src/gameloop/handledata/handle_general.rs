@@ -1,9 +1,12 @@
 use chrono::Duration;
-use log::{debug, info};
+use log::info;
 use mmap_bytey::MByteBuffer;
 use rand::distr::{Alphanumeric, SampleString};
 
-use super::SocketID;
+use super::{
+    SocketID,
+    chat_commands::{CommandArgs, execute_chat_command, find_chat_command, run_chat_command},
+};
 use crate::{
     containers::{
         Entity, GlobalKey, IsUsingType, PlayerConnectionTimer, Socket, Storage, TradeRequestEntity,
@@ -11,15 +14,16 @@ use crate::{
     },
     gametypes::*,
     items::Item,
-    maps::{can_target, spawn_npc},
+    maps::can_target,
     players::{
         can_trade, check_inv_space, close_trade, give_inv_item, player_give_vals, player_take_vals,
-        player_warp, reconnect_player, send_reconnect_info, send_tls_reconnect, take_inv_itemslot,
+        reconnect_player, send_reconnect_info, send_tls_reconnect, take_inv_itemslot,
     },
     socket::{
         MByteBufferExt, send_clear_data, send_clearisusingtype, send_fltalert, send_gameping,
         send_message, send_traderequest,
     },
+    sql::{item_log_text, player_log},
 };
 use time::Instant;
 
@@ -51,13 +55,18 @@ pub fn handle_message(
     let msg = data.read::<String>()?;
     let name = data.read::<String>()?;
 
-    let (socket_id, p_name) = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
-        let p_data = p_data.try_lock()?;
+    let (socket_id, p_name, mute_timer) =
+        if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+            let p_data = p_data.try_lock()?;
 
-        (p_data.socket.id, p_data.account.username.clone())
-    } else {
-        return Ok(());
-    };
+            (
+                p_data.socket.id,
+                p_data.account.username.clone(),
+                p_data.mute_timer,
+            )
+        } else {
+            return Ok(());
+        };
 
     if msg.len() >= 256 {
         return send_fltalert(
@@ -68,6 +77,19 @@ pub fn handle_message(
         );
     }
 
+    if let Some(line) = msg.strip_prefix('/') {
+        return run_chat_command(world, storage, entity, line);
+    }
+
+    if mute_timer.is_muted(*storage.gettick.borrow()) {
+        return send_fltalert(
+            storage,
+            socket_id,
+            "You are muted and can not chat.".into(),
+            FtlType::Error,
+        );
+    }
+
     match channel {
         MessageChannel::Private => {
             if name.is_empty() {
@@ -101,11 +123,41 @@ pub fn handle_message(
                 }
             };
         }
+        MessageChannel::Party => {
+            let in_party = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+                p_data.try_lock()?.party.party.is_some()
+            } else {
+                false
+            };
+
+            if !in_party {
+                return send_fltalert(
+                    storage,
+                    socket_id,
+                    "You are not in a party".into(),
+                    FtlType::Error,
+                );
+            }
+        }
+        MessageChannel::Guild => {
+            let in_guild = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+                p_data.try_lock()?.guild.membership.is_some()
+            } else {
+                false
+            };
+
+            if !in_guild {
+                return send_fltalert(
+                    storage,
+                    socket_id,
+                    "You are not in a guild".into(),
+                    FtlType::Error,
+                );
+            }
+        }
         MessageChannel::Map
         | MessageChannel::Global
         | MessageChannel::Trade
-        | MessageChannel::Party
-        | MessageChannel::Guild
         | MessageChannel::Help
         | MessageChannel::Quest
         | MessageChannel::Npc => {}
@@ -129,23 +181,36 @@ pub fn handle_command(
     let command = data.read::<Command>()?;
 
     match command {
-        Command::KickPlayer => {}
+        Command::KickPlayer => {
+            let target = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+                p_data.try_lock()?.combat.target.target_entity
+            } else {
+                None
+            };
+
+            if let Some(target) = target
+                && target != entity
+                && let Some(Entity::Player(p2_data)) = world.get_opt_entity(target)
+            {
+                let name = p2_data.try_lock()?.account.username.clone();
+
+                run_packet_command(world, storage, entity, "kick", CommandArgs::Player(name))?;
+            }
+        }
         Command::KickPlayerByName(name) => {
-            debug!("Kicking Player {name:?}");
+            run_packet_command(world, storage, entity, "kick", CommandArgs::Player(name))?;
         }
         Command::WarpTo(pos) => {
-            debug!("Warping to {pos:?}");
-            player_warp(world, storage, entity, &pos, false)?;
+            run_packet_command(world, storage, entity, "warp", CommandArgs::Warp(None, pos))?;
         }
         Command::SpawnNpc(index, pos) => {
-            debug!("Spawning NPC {index} on {pos:?}");
-            if let Some(mapdata) = storage.maps.get(&pos.map) {
-                let mut data = mapdata.borrow_mut();
-                if let Ok(Some(id)) = storage.add_npc(world, index as u64) {
-                    data.add_npc(id);
-                    spawn_npc(world, pos, None, id)?;
-                }
-            }
+            run_packet_command(
+                world,
+                storage,
+                entity,
+                "spawn",
+                CommandArgs::Spawn(index as u64, Some(pos)),
+            )?;
         }
         Command::Trade => {
             if let Some(Entity::Player(p1_data)) = world.get_opt_entity(entity) {
@@ -233,6 +298,23 @@ pub fn handle_command(
     Ok(())
 }
 
+// Runs a command sent through the Command packet with the same checks as chat commands.
+fn run_packet_command(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    name: &str,
+    args: CommandArgs,
+) -> Result<()> {
+    if let Some(command) = find_chat_command(name) {
+        let line = format!("{name} {args:?}");
+
+        execute_chat_command(world, storage, entity, command, args, &line)?;
+    }
+
+    Ok(())
+}
+
 pub fn handle_closestorage(
     world: &mut World,
     storage: &Storage,
@@ -400,8 +482,20 @@ pub fn handle_buyitem(
         };
 
         if check_inv_space(world, storage, entity, &mut item)? {
+            let bought = item_log_text(storage, item.num, item.val as u64);
+
             give_inv_item(world, storage, entity, &mut item)?;
             player_take_vals(world, storage, entity, shopdata.item[slot as usize].price)?;
+            player_log(
+                world,
+                storage,
+                entity,
+                LogType::Item,
+                format!(
+                    "Bought {bought} for {} from shop {shop_index}",
+                    shopdata.item[slot as usize].price
+                ),
+            )?;
         } else {
             return send_message(
                 world,
@@ -477,6 +571,16 @@ pub fn handle_sellitem(
         take_inv_itemslot(world, storage, entity, slot, amount)?;
         player_give_vals(world, storage, entity, total_price)?;
 
+        let sold = item_log_text(storage, inv_item.num, amount as u64);
+
+        player_log(
+            world,
+            storage,
+            entity,
+            LogType::Item,
+            format!("Sold {sold} for {total_price}"),
+        )?;
+
         send_message(
             world,
             storage,
@@ -654,7 +758,7 @@ pub fn handle_reconnect(
 
         info!(
             "Player {} with IP: {}, Reconnecting on handle_reconnect .",
-            &name, address
+            name, address
         );
 
         let code = Alphanumeric.sample_string(&mut rand::rng(), 32);
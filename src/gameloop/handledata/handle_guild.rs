@@ -0,0 +1,158 @@
+use mmap_bytey::MByteBuffer;
+
+use super::SocketID;
+use crate::{
+    containers::{GlobalKey, Storage, World},
+    gametypes::*,
+    players::{
+        guild_accept, guild_create, guild_decline, guild_disband, guild_invite, guild_kick,
+        guild_leave, guild_set_motd, guild_set_rank,
+    },
+};
+
+pub fn handle_guildcreate(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let name = data.read::<String>()?;
+    let tag = data.read::<String>()?;
+
+    guild_create(world, storage, entity, name, tag)
+}
+
+pub fn handle_guildinvite(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let target = data.read::<GlobalKey>()?;
+
+    guild_invite(world, storage, entity, target)
+}
+
+pub fn handle_guildaccept(
+    world: &mut World,
+    storage: &Storage,
+    _data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    guild_accept(world, storage, entity)
+}
+
+pub fn handle_guilddecline(
+    world: &mut World,
+    storage: &Storage,
+    _data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    guild_decline(world, storage, entity)
+}
+
+pub fn handle_guildleave(
+    world: &mut World,
+    storage: &Storage,
+    _data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    guild_leave(world, storage, entity)
+}
+
+pub fn handle_guildkick(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let name = data.read::<String>()?;
+
+    guild_kick(world, storage, entity, name)
+}
+
+pub fn handle_guildsetrank(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let name = data.read::<String>()?;
+    let rank = data.read::<i16>()?;
+
+    guild_set_rank(world, storage, entity, name, rank)
+}
+
+pub fn handle_guildsetmotd(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let motd = data.read::<String>()?;
+
+    guild_set_motd(world, storage, entity, motd)
+}
+
+pub fn handle_guilddisband(
+    world: &mut World,
+    storage: &Storage,
+    _data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    guild_disband(world, storage, entity)
+}
@@ -13,6 +13,7 @@ use crate::{
         set_inv_slot, set_storage_slot, take_inv_itemslot, take_storage_itemslot,
     },
     socket::{send_fltalert, send_message},
+    sql::player_item_log,
     tasks::{DataTaskToken, unload_entity_packet},
 };
 
@@ -430,6 +431,14 @@ pub fn handle_dropitem(
         Some(entity),
     )? {
         take_inv_itemslot(world, storage, entity, slot, amount)?;
+        player_item_log(
+            world,
+            storage,
+            entity,
+            "Dropped",
+            item_data.num,
+            amount as u64,
+        )?;
     }
 
     Ok(())
@@ -450,7 +459,7 @@ pub fn handle_deleteitem(
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
         let slot = data.read::<u16>()? as usize;
 
-        let val = {
+        let item = {
             let p_data = p_data.try_lock()?;
 
             if !p_data.combat.death_type.is_alive()
@@ -465,10 +474,11 @@ pub fn handle_deleteitem(
                 return Ok(());
             }
 
-            p_data.inventory.items[slot].val
+            p_data.inventory.items[slot]
         };
 
-        take_inv_itemslot(world, storage, entity, slot, val)?;
+        take_inv_itemslot(world, storage, entity, slot, item.val)?;
+        player_item_log(world, storage, entity, "Deleted", item.num, item.val as u64)?;
     }
     Ok(())
 }
@@ -575,7 +585,7 @@ pub fn handle_deletestorageitem(
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
         let slot = data.read::<u16>()? as usize;
 
-        let val = {
+        let item = {
             let p_data = p_data.try_lock()?;
 
             if !p_data.combat.death_type.is_alive()
@@ -590,10 +600,18 @@ pub fn handle_deletestorageitem(
                 return Ok(());
             }
 
-            p_data.storage.items[slot].val
+            p_data.storage.items[slot]
         };
 
-        take_storage_itemslot(world, storage, entity, slot, val)?;
+        take_storage_itemslot(world, storage, entity, slot, item.val)?;
+        player_item_log(
+            world,
+            storage,
+            entity,
+            "Deleted from storage",
+            item.num,
+            item.val as u64,
+        )?;
     }
     Ok(())
 }
@@ -648,13 +666,24 @@ pub fn handle_deposititem(
             }
             save_storage_item(world, storage, entity, bank_slot)?;
             take_inv_itemslot(world, storage, entity, inv_slot, amount)?;
+            player_item_log(
+                world,
+                storage,
+                entity,
+                "Deposited",
+                item_data.num,
+                item_data.val as u64,
+            )?;
         } else {
             let (is_less, amount, _started) =
                 check_storage_partial_space(world, storage, entity, &mut item_data)?;
 
             if is_less {
+                let num = item_data.num;
+
                 give_storage_item(world, storage, entity, &mut item_data)?;
                 take_inv_itemslot(world, storage, entity, inv_slot, amount)?;
+                player_item_log(world, storage, entity, "Deposited", num, amount as u64)?;
             } else {
                 send_message(
                     world,
@@ -722,13 +751,24 @@ pub fn handle_withdrawitem(
             }
             save_inv_item(world, storage, entity, inv_slot)?;
             take_storage_itemslot(world, storage, entity, bank_slot, amount)?;
+            player_item_log(
+                world,
+                storage,
+                entity,
+                "Withdrew",
+                item_data.num,
+                item_data.val as u64,
+            )?;
         } else {
             let (is_less, amount, _started) =
                 check_inv_partial_space(world, storage, entity, &mut item_data)?;
 
             if is_less {
+                let num = item_data.num;
+
                 give_inv_item(world, storage, entity, &mut item_data)?;
                 take_storage_itemslot(world, storage, entity, bank_slot, amount)?;
+                player_item_log(world, storage, entity, "Withdrew", num, amount as u64)?;
             } else {
                 send_message(
                     world,
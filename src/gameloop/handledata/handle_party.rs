@@ -0,0 +1,106 @@
+use mmap_bytey::MByteBuffer;
+
+use super::SocketID;
+use crate::{
+    containers::{GlobalKey, Storage, World},
+    gametypes::*,
+    players::{
+        party_accept, party_decline, party_invite, party_kick, party_leave, party_set_leader,
+    },
+};
+
+pub fn handle_partyinvite(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let target = data.read::<GlobalKey>()?;
+
+    party_invite(world, storage, entity, target)
+}
+
+pub fn handle_partyaccept(
+    world: &mut World,
+    storage: &Storage,
+    _data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    party_accept(world, storage, entity)
+}
+
+pub fn handle_partydecline(
+    world: &mut World,
+    storage: &Storage,
+    _data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    party_decline(world, storage, entity)
+}
+
+pub fn handle_partyleave(
+    world: &mut World,
+    storage: &Storage,
+    _data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    party_leave(world, storage, entity)
+}
+
+pub fn handle_partykick(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let target = data.read::<GlobalKey>()?;
+
+    party_kick(world, storage, entity, target)
+}
+
+pub fn handle_partyleader(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let target = data.read::<GlobalKey>()?;
+
+    party_set_leader(world, storage, entity, target)
+}
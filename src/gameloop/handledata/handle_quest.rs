@@ -0,0 +1,60 @@
+use mmap_bytey::MByteBuffer;
+
+use super::SocketID;
+use crate::{
+    containers::{GlobalKey, Storage, World},
+    gametypes::*,
+    players::{quest_abandon, quest_accept, quest_talk},
+};
+
+pub fn handle_questaccept(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let quest = data.read::<u16>()?;
+    let npc = data.read::<Option<GlobalKey>>()?;
+
+    quest_accept(world, storage, entity, quest, npc)
+}
+
+pub fn handle_questabandon(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let quest = data.read::<u16>()?;
+
+    quest_abandon(world, storage, entity, quest)
+}
+
+pub fn handle_questtalk(
+    world: &mut World,
+    storage: &Storage,
+    data: &mut MByteBuffer,
+    entity: Option<GlobalKey>,
+    _socket_id: SocketID,
+) -> Result<()> {
+    let entity = match entity {
+        Some(e) => e,
+        None => return Err(AscendingError::InvalidSocket),
+    };
+
+    let npc = data.read::<GlobalKey>()?;
+
+    quest_talk(world, storage, entity, npc)
+}
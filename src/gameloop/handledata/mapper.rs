@@ -1,6 +1,6 @@
 use super::{
-    SocketID, handle_account::*, handle_action::*, handle_general::*, handle_item::*,
-    handle_trade::*,
+    SocketID, handle_account::*, handle_action::*, handle_general::*, handle_guild::*,
+    handle_item::*, handle_party::*, handle_quest::*, handle_trade::*,
 };
 use crate::{
     containers::{GlobalKey, Storage, World},
@@ -49,6 +49,34 @@ pub fn run_packet(packet: &ClientPacket) -> Option<PacketFunction> {
         ClientPacket::Disconnect => Some(handle_disconnect as PacketFunction),
         ClientPacket::Reconnect => Some(handle_reconnect as PacketFunction),
         ClientPacket::LoginOk => Some(handle_login_ok as PacketFunction),
+        ClientPacket::PartyInvite => Some(handle_partyinvite as PacketFunction),
+        ClientPacket::PartyAccept => Some(handle_partyaccept as PacketFunction),
+        ClientPacket::PartyDecline => Some(handle_partydecline as PacketFunction),
+        ClientPacket::PartyLeave => Some(handle_partyleave as PacketFunction),
+        ClientPacket::PartyKick => Some(handle_partykick as PacketFunction),
+        ClientPacket::PartyLeader => Some(handle_partyleader as PacketFunction),
+        ClientPacket::GuildCreate => Some(handle_guildcreate as PacketFunction),
+        ClientPacket::GuildInvite => Some(handle_guildinvite as PacketFunction),
+        ClientPacket::GuildAccept => Some(handle_guildaccept as PacketFunction),
+        ClientPacket::GuildDecline => Some(handle_guilddecline as PacketFunction),
+        ClientPacket::GuildLeave => Some(handle_guildleave as PacketFunction),
+        ClientPacket::GuildKick => Some(handle_guildkick as PacketFunction),
+        ClientPacket::GuildSetRank => Some(handle_guildsetrank as PacketFunction),
+        ClientPacket::GuildSetMotd => Some(handle_guildsetmotd as PacketFunction),
+        ClientPacket::GuildDisband => Some(handle_guilddisband as PacketFunction),
+        ClientPacket::QuestAccept => Some(handle_questaccept as PacketFunction),
+        ClientPacket::QuestAbandon => Some(handle_questabandon as PacketFunction),
+        ClientPacket::QuestTalk => Some(handle_questtalk as PacketFunction),
+        ClientPacket::CastSkill => Some(handle_castskill as PacketFunction),
+        ClientPacket::SetHotbar => Some(handle_sethotbar as PacketFunction),
+        ClientPacket::RequestPasswordReset => Some(handle_request_password_reset as PacketFunction),
+        ClientPacket::ResetPassword => Some(handle_reset_password as PacketFunction),
+        ClientPacket::CreateCharacter => Some(handle_create_character as PacketFunction),
+        ClientPacket::DeleteCharacter => Some(handle_delete_character as PacketFunction),
+        ClientPacket::SelectCharacter => Some(handle_select_character as PacketFunction),
+        ClientPacket::AllocatePoints => Some(handle_allocatepoints as PacketFunction),
+        ClientPacket::SetPvp => Some(handle_setpvp as PacketFunction),
+        ClientPacket::ProtocolHello => Some(handle_protocol_hello as PacketFunction),
         ClientPacket::OnlineCheck => None,
     }
 }
@@ -2,9 +2,11 @@ use crate::{
     AscendingError,
     containers::{GlobalKey, Storage, World},
     gameloop::handledata::mapper::run_packet,
-    gametypes::Result,
+    gametypes::{LogType, Result},
     socket::*,
+    sql::{PGLog, add_log},
 };
+use uuid::Uuid;
 
 pub struct SocketID {
     pub id: usize,
@@ -18,11 +20,19 @@ pub fn handle_data(
     entity: Option<GlobalKey>,
     socket_id: SocketID,
 ) -> Result<()> {
-    let id: ClientPacket = data.read()?;
+    let id = ClientPacket::from_id(data.read::<u16>()?).ok_or(AscendingError::InvalidPacket)?;
 
     if entity.is_some() {
         match id {
-            ClientPacket::Login | ClientPacket::Register | ClientPacket::HandShake => {
+            ClientPacket::Login
+            | ClientPacket::Register
+            | ClientPacket::HandShake
+            | ClientPacket::RequestPasswordReset
+            | ClientPacket::ResetPassword
+            | ClientPacket::CreateCharacter
+            | ClientPacket::DeleteCharacter
+            | ClientPacket::SelectCharacter
+            | ClientPacket::ProtocolHello => {
                 return Err(AscendingError::MultiLogin);
             }
             _ => {}
@@ -35,8 +45,38 @@ pub fn handle_data(
             | ClientPacket::HandShake
             | ClientPacket::Ping
             | ClientPacket::TlsHandShake
-            | ClientPacket::TlsReconnect => {}
-            _ => return Err(AscendingError::PacketManipulation { name: "".into() }),
+            | ClientPacket::TlsReconnect
+            | ClientPacket::RequestPasswordReset
+            | ClientPacket::ResetPassword
+            | ClientPacket::CreateCharacter
+            | ClientPacket::DeleteCharacter
+            | ClientPacket::SelectCharacter
+            | ClientPacket::ProtocolHello => {}
+            _ => {
+                let ipaddress = storage
+                    .server
+                    .borrow()
+                    .clients
+                    .get(socket_id.id)
+                    .map(|client| client.borrow().addr.to_string())
+                    .unwrap_or_default();
+
+                // There is no account yet so the row is only tied to the address.
+                add_log(
+                    storage,
+                    PGLog::new(
+                        storage.config.server_id,
+                        Uuid::nil(),
+                        LogType::Warning,
+                        format!("Sent {id:?} before logging in"),
+                        ipaddress,
+                    ),
+                )?;
+
+                return Err(AscendingError::PacketManipulation {
+                    name: format!("{id:?}"),
+                });
+            }
         }
     }
 
@@ -44,6 +84,22 @@ pub fn handle_data(
         return Ok(());
     }
 
+    // Accounts can only be used once the client has agreed on a protocol.
+    if matches!(
+        id,
+        ClientPacket::Login
+            | ClientPacket::Register
+            | ClientPacket::RequestPasswordReset
+            | ClientPacket::ResetPassword
+    ) && client_protocol(storage, socket_id.id).is_none()
+    {
+        return send_protocol_reject(
+            storage,
+            socket_id.id,
+            "Client did not send its protocol version. Please update your client.".into(),
+        );
+    }
+
     let fun = match run_packet(&id) {
         Some(fun) => fun,
         None => {
@@ -1,15 +1,23 @@
 use crate::{
-    containers::{Storage, World},
+    containers::{ReloadKind, Storage, World},
+    gameloop::{
+        ConsoleCommand, ShutdownCountdown, process_console, process_load_results,
+        process_login_results, shutdown_server,
+    },
+    gametypes::REGEN_TICK_MS,
     maps::{update_map_items, update_maps},
     npcs::*,
     players::*,
     socket::*,
+    sql::{process_ip_bans, process_lookups, process_save_failures, refresh_ip_bans},
     tasks::{process_data_lists, process_tasks},
 };
 use chrono::Duration;
+use log::{error, info};
+use std::sync::mpsc::Receiver;
 use time::Instant;
 
-pub fn game_loop(world: &mut World, storage: &Storage) {
+pub fn game_loop(world: &mut World, storage: &mut Storage, console: &Receiver<ConsoleCommand>) {
     let mut tick: Instant;
     let mut tmr100: Instant = Instant::recent();
     let mut tmr150: Instant = Instant::recent();
@@ -17,11 +25,14 @@ pub fn game_loop(world: &mut World, storage: &Storage) {
     let mut tmr1000: Instant = Instant::recent();
     let mut tmr60000: Instant = Instant::recent();
     let mut ping_timer: Instant = Instant::recent();
+    let mut regen_timer: Instant = Instant::recent();
 
     let mut entity_progress = 0u64;
     let mut npc_progress = 0u64;
     let mut npc_batch = 0usize;
     let mut max_batch = (storage.npc_ids.borrow().len() as f32 / 5.0).ceil() as usize;
+    let mut shutdown: Option<ShutdownCountdown> = None;
+    let mut reload: Option<ReloadKind> = None;
 
     loop {
         let _ = storage.gettick.replace(Instant::recent());
@@ -35,6 +46,12 @@ pub fn game_loop(world: &mut World, storage: &Storage) {
                 2 => {
                     update_map_items(world, storage).unwrap();
                 }
+                3 => {
+                    update_parties(world, storage).unwrap();
+                }
+                4 => {
+                    update_players_status(world, storage).unwrap();
+                }
                 _ => {
                     update_players(world, storage).unwrap();
                     entity_progress = 0;
@@ -63,6 +80,9 @@ pub fn game_loop(world: &mut World, storage: &Storage) {
                     3 => {
                         update_npcs_combat(world, storage, npc_batch).unwrap();
                     }
+                    4 => {
+                        update_npcs_status(world, storage, npc_batch).unwrap();
+                    }
                     _ => {
                         update_npcs_spawn(world, storage, npc_batch).unwrap();
                         npc_progress = 0;
@@ -79,7 +99,14 @@ pub fn game_loop(world: &mut World, storage: &Storage) {
             tmr500 = tick + Duration::try_milliseconds(500).unwrap_or_default();
         }
 
+        if tick > regen_timer {
+            update_regen(world, storage).unwrap();
+            regen_timer = tick + Duration::try_milliseconds(REGEN_TICK_MS).unwrap_or_default();
+        }
+
         if tick > tmr1000 {
+            process_save_failures(storage);
+            storage.login_throttle.borrow_mut().prune(tick);
             tmr1000 = tick + Duration::try_milliseconds(1000).unwrap_or_default();
         }
 
@@ -93,6 +120,11 @@ pub fn game_loop(world: &mut World, storage: &Storage) {
                     time.hour = 0;
                 }
             }
+
+            if let Err(e) = refresh_ip_bans(storage) {
+                error!("Failed to queue ip ban refresh: {e}");
+            }
+
             tmr60000 = tick + Duration::try_milliseconds(60000).unwrap_or_default();
         }
 
@@ -102,10 +134,33 @@ pub fn game_loop(world: &mut World, storage: &Storage) {
             ping_timer = tick + Duration::try_hours(2).unwrap_or_default();
         }
 
+        process_console(world, storage, console, &mut shutdown, &mut reload).unwrap();
+
+        if let Some(countdown) = shutdown.as_mut()
+            && countdown.update(world, storage).unwrap()
+        {
+            break;
+        }
+
         poll_events(world, storage).unwrap();
         process_packets(world, storage).unwrap();
+        process_login_results(world, storage).unwrap();
+        process_load_results(world, storage).unwrap();
+        process_lookups(world, storage);
+        process_ip_bans(storage);
         process_data_lists(world, storage).unwrap();
         process_tasks(world, storage).unwrap();
+
+        // Swapping data needs Storage mutably so it can only happen here between ticks.
+        if let Some(kind) = reload.take() {
+            match storage.reload_bases(world, kind) {
+                Ok(()) => info!("Reload of {kind:?} finished."),
+                Err(e) => error!("Reload of {kind:?} failed, keeping the current data: {e}"),
+            }
+        }
+
         std::thread::sleep(std::time::Duration::from_millis(1));
     }
+
+    shutdown_server(world, storage).unwrap();
 }
@@ -0,0 +1,243 @@
+use crate::{
+    containers::{Entity, GlobalKey, Storage, World},
+    gameloop::ConsoleCommand,
+    gametypes::*,
+    socket::*,
+    sql::process_save_failures,
+    tasks::{process_data_lists, process_tasks},
+};
+use chrono::Duration;
+use log::{error, info, warn};
+use std::{
+    sync::mpsc::Sender,
+    thread::{self, JoinHandle},
+};
+use time::Instant;
+
+/// How long we keep polling sockets to flush the final packets before closing them.
+const SHUTDOWN_DRAIN_MS: i64 = 3000;
+
+/// Counts down to a shutdown, announcing the time left to everyone online.
+pub struct ShutdownCountdown {
+    pub seconds_left: u64,
+    pub next_tick: Instant,
+}
+
+impl ShutdownCountdown {
+    pub fn start(world: &mut World, storage: &Storage, seconds: u64) -> Result<Self> {
+        if seconds > 0 {
+            announce_shutdown(world, storage, seconds)?;
+        }
+
+        Ok(Self {
+            seconds_left: seconds,
+            next_tick: *storage.gettick.borrow()
+                + Duration::try_milliseconds(1000).unwrap_or_default(),
+        })
+    }
+
+    /// Returns true once the countdown has finished and the server should stop.
+    pub fn update(&mut self, world: &mut World, storage: &Storage) -> Result<bool> {
+        let tick = *storage.gettick.borrow();
+
+        if self.seconds_left > 0 && tick > self.next_tick {
+            self.seconds_left -= 1;
+            self.next_tick = tick + Duration::try_milliseconds(1000).unwrap_or_default();
+
+            if self.seconds_left > 0
+                && (self.seconds_left.is_multiple_of(60)
+                    || matches!(self.seconds_left, 30 | 15 | 10 | 1..=5))
+            {
+                announce_shutdown(world, storage, self.seconds_left)?;
+            }
+        }
+
+        Ok(self.seconds_left == 0)
+    }
+}
+
+/// Starts a shutdown or shortens the one already running.
+pub fn request_shutdown(
+    world: &mut World,
+    storage: &Storage,
+    shutdown: &mut Option<ShutdownCountdown>,
+    seconds: Option<u64>,
+) -> Result<()> {
+    let seconds = seconds.unwrap_or(storage.config.shutdown_countdown);
+
+    match shutdown {
+        Some(countdown) if countdown.seconds_left <= seconds => {
+            info!(
+                "Shutdown already in progress. {} seconds left.",
+                countdown.seconds_left
+            );
+        }
+        _ => {
+            info!("Shutdown requested. Stopping in {seconds} seconds.");
+            *shutdown = Some(ShutdownCountdown::start(world, storage, seconds)?);
+        }
+    }
+
+    Ok(())
+}
+
+fn announce_shutdown(world: &mut World, storage: &Storage, seconds: u64) -> Result<()> {
+    let time = if seconds >= 60 && seconds.is_multiple_of(60) {
+        format!("{} minute(s)", seconds / 60)
+    } else {
+        format!("{seconds} second(s)")
+    };
+
+    info!("Server shutting down in {time}.");
+
+    let players: Vec<GlobalKey> = storage.player_ids.borrow().iter().copied().collect();
+
+    for entity in players {
+        send_message(
+            world,
+            storage,
+            entity,
+            format!("The server will shut down in {time}."),
+            "[Server]".into(),
+            MessageChannel::Private,
+            None,
+        )?;
+    }
+
+    Ok(())
+}
+
+// Waits for SIGINT or SIGTERM.
+#[cfg(unix)]
+async fn wait_for_signal() -> std::io::Result<()> {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut terminate = signal(SignalKind::terminate())?;
+
+    tokio::select! {
+        res = tokio::signal::ctrl_c() => res,
+        _ = terminate.recv() => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> std::io::Result<()> {
+    tokio::signal::ctrl_c().await
+}
+
+/// Spawns a thread that turns SIGINT/SIGTERM into a shutdown command.
+/// The first signal starts the normal countdown, a second one shuts down right away.
+pub fn spawn_signal_handler(sender: Sender<ConsoleCommand>) -> std::io::Result<JoinHandle<()>> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    thread::Builder::new()
+        .name("signals".into())
+        .spawn(move || {
+            rt.block_on(async move {
+                let mut received = false;
+
+                loop {
+                    if let Err(e) = wait_for_signal().await {
+                        error!("Failed to listen for shutdown signals: {e}");
+                        return;
+                    }
+
+                    let seconds = if received { Some(0) } else { None };
+                    received = true;
+
+                    if sender.send(ConsoleCommand::Shutdown(seconds)).is_err() {
+                        return;
+                    }
+                }
+            });
+        })
+}
+
+/// Keeps polling until every client has written its queued packets or we run out of time.
+fn drain_sends(world: &mut World, storage: &Storage) -> Result<()> {
+    let end = Instant::recent() + Duration::try_milliseconds(SHUTDOWN_DRAIN_MS).unwrap_or_default();
+
+    loop {
+        let _ = storage.gettick.replace(Instant::recent());
+        poll_events(world, storage)?;
+
+        let pending = storage
+            .server
+            .borrow()
+            .clients
+            .iter()
+            .any(|(_, client)| !client.borrow().sends.is_empty());
+
+        if !pending {
+            return Ok(());
+        }
+
+        if *storage.gettick.borrow() > end {
+            warn!("Shutdown: some clients did not receive their final packets.");
+            return Ok(());
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+}
+
+pub fn shutdown_server(world: &mut World, storage: &Storage) -> Result<()> {
+    info!("Shutting down. No longer accepting connections.");
+    storage
+        .server
+        .borrow_mut()
+        .stop_listening(&storage.poll.borrow())?;
+
+    let players: Vec<GlobalKey> = storage.player_ids.borrow().iter().copied().collect();
+
+    for entity in &players {
+        if let Some(Entity::Player(p_data)) = world.get_opt_entity(*entity) {
+            let socket_id = p_data.try_lock()?.socket.id;
+
+            send_infomsg(storage, socket_id, "The server has shut down.".into(), 1)?;
+        }
+    }
+
+    // Send everything still sitting in the packet cache before the sockets go away.
+    process_data_lists(world, storage)?;
+    process_tasks(world, storage)?;
+    drain_sends(world, storage)?;
+
+    info!("Saving {} players.", players.len());
+
+    // disconnect saves the player. Slots are already saved as they change.
+    for entity in players {
+        if let Err(e) = disconnect(entity, world, storage) {
+            error!("Failed to disconnect player {entity:?} on shutdown: {e}");
+        }
+    }
+
+    info!("Waiting for queued saves to finish.");
+
+    if let Err(e) = storage.saves.flush() {
+        error!("Failed to flush the save queue on shutdown: {e}");
+    }
+
+    process_save_failures(storage);
+
+    let tokens: Vec<usize> = storage
+        .server
+        .borrow()
+        .clients
+        .iter()
+        .map(|(token, _)| token)
+        .collect();
+
+    for token in tokens {
+        if let Some(client) = storage.server.borrow().clients.get(token) {
+            client.borrow_mut().close_socket(world, storage)?;
+        }
+    }
+
+    storage.server.borrow_mut().clients.clear();
+
+    info!("Shutdown complete.");
+    Ok(())
+}
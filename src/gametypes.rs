@@ -14,6 +14,7 @@ pub use sharedstructs::*;
 
 pub const EQUIPMENT_TYPE_MAX: usize = EquipmentType::Count as usize;
 pub const VITALS_MAX: usize = VitalTypes::Count as usize;
+pub const ATTRIBUTES_MAX: usize = AttributeType::Count as usize;
 
 pub const MAXCONNECTIONS: usize = 500;
 pub const APP_MAJOR: usize = 1;
@@ -30,13 +31,14 @@ pub const MAX_TILE: usize = MAP_MAX_X * MAP_MAX_Y - 1;
 pub const MAX_NPCS: usize = 1000;
 pub const MAX_ITEMS: usize = 2000;
 pub const MAX_SHOPS: usize = 100;
+pub const MAX_QUESTS: usize = 500;
+pub const MAX_SKILLS: usize = 500;
 pub const MAX_PLAYERS: usize = 1000;
 pub const MAX_SOCKET_PLAYERS: usize = 2000;
 
 pub const MAX_WORLD_NPCS: usize = 100_000;
 pub const NPCS_SPAWNCAP: usize = 10;
 
-pub const MAX_LVL: usize = 200;
 pub const MAX_INV: usize = 30;
 pub const MAX_TRADE_SLOT: usize = 30;
 pub const MAX_STORAGE: usize = 70;
@@ -44,7 +46,47 @@ pub const MAX_EQPT: usize = 5;
 pub const MAX_ITEM_VAL: usize = 999;
 pub const MAX_NAME_LENGTH: usize = 32;
 pub const MAX_PARTY_SIZE: usize = 12;
+// How many maps away a party member can be and still share experience.
+pub const PARTY_EXP_MAP_RANGE: i32 = 1;
+pub const MAX_GUILD_NAME_LENGTH: usize = 24;
+pub const MAX_GUILD_TAG_LENGTH: usize = 5;
+pub const MAX_GUILD_MOTD_LENGTH: usize = 256;
+// Most audit log rows the /history command will show at once.
+pub const MAX_HISTORY_ROWS: i64 = 50;
 pub const MAX_SHOP_ITEM: usize = 20;
+pub const MAX_ACTIVE_QUESTS: usize = 20;
+pub const MAX_HOTBAR: usize = 10;
+// How many tiles away a player can be from an npc to talk to it.
+pub const QUEST_TALK_RANGE: i32 = 2;
+// How often in milliseconds poison and regen effects are applied.
+pub const STATUS_TICK_MS: i64 = 1000;
+// How often in milliseconds players and npcs regenerate their vitals.
+pub const REGEN_TICK_MS: i64 = 2000;
+// How long in milliseconds after the last hit given or taken an entity stays in combat.
+pub const COMBAT_TIMER_MS: i64 = 5000;
+// Regen is multiplied by this while out of combat.
+pub const OUT_OF_COMBAT_REGEN: u32 = 3;
+// Failed logins an address gets before it has to wait between attempts.
+pub const LOGIN_FREE_ATTEMPTS: u32 = 3;
+// First wait in milliseconds once an address runs out of attempts. Doubles per failure.
+pub const LOGIN_BACKOFF_MS: i64 = 1000;
+pub const LOGIN_BACKOFF_MAX_MS: i64 = 300_000;
+// How long in milliseconds an address's failures are remembered after its last attempt.
+pub const LOGIN_ATTEMPT_WINDOW_MS: i64 = 900_000;
+// Failed logins in a row before an account is locked.
+pub const ACCOUNT_LOCK_ATTEMPTS: i32 = 5;
+// First lockout in seconds. Doubles for every further failure.
+pub const ACCOUNT_LOCK_SECS: i32 = 60;
+pub const ACCOUNT_LOCK_MAX_SECS: i32 = 3600;
+// Length of the code mailed out for a password reset and how many seconds it stays valid.
+pub const PASSWORD_RESET_CODE_LEN: usize = 12;
+pub const PASSWORD_RESET_EXPIRE_SECS: i32 = 900;
+// Character slots per account.
+pub const MAX_CHARACTERS: usize = 3;
+// How long in milliseconds a player has to wait between turning pvp on or off.
+pub const PVP_TOGGLE_COOLDOWN_MS: i64 = 60000;
+// How long in milliseconds pk status lasts after the last attack on a player without pvp.
+pub const PK_DURATION_MS: i64 = 600000;
 
 pub const DIR_UP: usize = 0;
 pub const DIR_RIGHT: usize = 1;
@@ -83,6 +83,8 @@ impl AIBehavior {
     Serialize,
     Deserialize,
     Default,
+    Readable,
+    Writable,
     MByteBufferRead,
     MByteBufferWrite,
 )]
@@ -161,6 +163,27 @@ pub enum VitalTypes {
     Count,
 }
 
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Default,
+    MByteBufferRead,
+    MByteBufferWrite,
+)]
+pub enum AttributeType {
+    Strength,
+    Dexterity,
+    Vitality,
+    Intelligence,
+    #[default]
+    Count,
+}
+
 #[derive(
     Copy,
     Clone,
@@ -253,6 +276,48 @@ pub enum LogType {
     Item,
     Warning,
     Error,
+    Command,
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Readable,
+    Writable,
+    MByteBufferRead,
+    MByteBufferWrite,
+    sqlx::Type,
+)]
+#[sqlx(type_name = "status_kind")]
+pub enum StatusKind {
+    Stun,        // can not move, attack or cast
+    Poison,      // power is damage per tick for each stack
+    Regen,       // power is health per tick
+    Slow,        // power is the percent movement is slowed by
+    DamageBuff,  // power is added to damage dealt
+    DefenseBuff, // power is added to defense
+    Silence,     // can not cast skills
+}
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "sanction_kind")]
+pub enum SanctionKind {
+    Ban,  // can not connect or log in
+    Mute, // can not chat
+}
+
+impl StatusKind {
+    pub fn max_stacks(self) -> u8 {
+        match self {
+            StatusKind::Poison => 5,
+            _ => 1,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -37,6 +37,14 @@ pub enum AscendingError {
     NpcNotFound(u64),
     #[error("Packet buffer {0:?} not found")]
     PacketCacheNotFound(DataTaskToken),
+    #[error("Invalid data in {file}: {message}")]
+    InvalidData { file: String, message: String },
+    #[error("The save worker has stopped")]
+    SaveWorkerClosed,
+    #[error(
+        "Database schema version {database} is newer than {server}, the newest this server knows"
+    )]
+    SchemaTooNew { database: i32, server: i32 },
     #[error("Error: {error}, BackTrace: {backtrace}")]
     AddrParseError {
         #[from]
@@ -1,4 +1,4 @@
-use crate::gametypes::{ItemTypes, Rgba};
+use crate::gametypes::{AscendingError, ItemTypes, MAX_ITEMS, Result, Rgba};
 use educe::Educe;
 use serde::{Deserialize, Serialize};
 use speedy::{Readable, Writable};
@@ -26,36 +26,39 @@ pub struct ItemData {
     pub sound_index: Option<String>,
 }
 
-pub fn get_item() -> Vec<ItemData> {
+pub fn get_item() -> Result<Vec<ItemData>> {
     let mut item_data: Vec<ItemData> = Vec::new();
 
-    let mut count = 0;
-    let mut got_data = true;
-
-    while got_data {
-        if let Some(data) = load_file(count) {
-            item_data.push(data);
-            count += 1;
-            got_data = true;
-        } else {
-            got_data = false;
+    while let Some(data) = load_file(item_data.len())? {
+        if item_data.len() >= MAX_ITEMS {
+            return Err(AscendingError::InvalidData {
+                file: "./data/items/".into(),
+                message: format!("more than {MAX_ITEMS} items"),
+            });
         }
+
+        item_data.push(data);
     }
 
-    item_data
+    Ok(item_data)
 }
 
-fn load_file(id: usize) -> Option<ItemData> {
+fn load_file(id: usize) -> Result<Option<ItemData>> {
     let name = format!("./data/items/{id}.bin");
 
-    match OpenOptions::new().read(true).open(name) {
+    match OpenOptions::new().read(true).open(&name) {
         Ok(mut file) => {
             let mut bytes = Vec::new();
-            match file.read_to_end(&mut bytes) {
-                Ok(_) => Some(ItemData::read_from_buffer(&bytes).unwrap()),
-                Err(_) => None,
+            file.read_to_end(&mut bytes)?;
+
+            match ItemData::read_from_buffer(&bytes) {
+                Ok(data) => Ok(Some(data)),
+                Err(e) => Err(AscendingError::InvalidData {
+                    file: name,
+                    message: e.to_string(),
+                }),
             }
         }
-        Err(_) => None,
+        Err(_) => Ok(None),
     }
 }
@@ -1,4 +1,4 @@
-use crate::gametypes::MAX_SHOP_ITEM;
+use crate::gametypes::{AscendingError, MAX_SHOP_ITEM, MAX_SHOPS, Result};
 use educe::Educe;
 use serde::{Deserialize, Serialize};
 use speedy::{Readable, Writable};
@@ -21,36 +21,39 @@ pub struct ShopData {
     pub item: [ShopItem; MAX_SHOP_ITEM],
 }
 
-pub fn get_shop() -> Vec<ShopData> {
+pub fn get_shop() -> Result<Vec<ShopData>> {
     let mut shop_data: Vec<ShopData> = Vec::new();
 
-    let mut count = 0;
-    let mut got_data = true;
-
-    while got_data {
-        if let Some(data) = load_file(count) {
-            shop_data.push(data);
-            count += 1;
-            got_data = true;
-        } else {
-            got_data = false;
+    while let Some(data) = load_file(shop_data.len())? {
+        if shop_data.len() >= MAX_SHOPS {
+            return Err(AscendingError::InvalidData {
+                file: "./data/shops/".into(),
+                message: format!("more than {MAX_SHOPS} shops"),
+            });
         }
+
+        shop_data.push(data);
     }
 
-    shop_data
+    Ok(shop_data)
 }
 
-fn load_file(id: usize) -> Option<ShopData> {
+fn load_file(id: usize) -> Result<Option<ShopData>> {
     let name = format!("./data/shops/{id}.bin");
 
-    match OpenOptions::new().read(true).open(name) {
+    match OpenOptions::new().read(true).open(&name) {
         Ok(mut file) => {
             let mut bytes = Vec::new();
-            match file.read_to_end(&mut bytes) {
-                Ok(_) => Some(ShopData::read_from_buffer(&bytes).unwrap()),
-                Err(_) => None,
+            file.read_to_end(&mut bytes)?;
+
+            match ShopData::read_from_buffer(&bytes) {
+                Ok(data) => Ok(Some(data)),
+                Err(e) => Err(AscendingError::InvalidData {
+                    file: name,
+                    message: e.to_string(),
+                }),
             }
         }
-        Err(_) => None,
+        Err(_) => Ok(None),
     }
 }
@@ -0,0 +1,49 @@
+use crate::gametypes::*;
+use log::info;
+use std::{fs::OpenOptions, io::Write};
+
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Delivers mail to players. Called from the game loop so implementations that talk
+/// to a real mail server should queue the mail and send it from their own thread.
+pub trait Mailer {
+    fn send(&self, mail: Mail) -> Result<()>;
+}
+
+/// Writes mail to a file or to stdout instead of sending it, for running a server
+/// locally without a mail server.
+pub struct LocalMailer {
+    path: Option<String>,
+}
+
+impl LocalMailer {
+    pub fn new(path: Option<String>) -> Self {
+        Self { path }
+    }
+}
+
+impl Mailer for LocalMailer {
+    fn send(&self, mail: Mail) -> Result<()> {
+        let text = format!(
+            "To: {}\nSubject: {}\n\n{}\n\n",
+            mail.to, mail.subject, mail.body
+        );
+
+        match &self.path {
+            Some(path) => OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(path)?
+                .write_all(text.as_bytes())?,
+            None => println!("{text}"),
+        }
+
+        info!("Mail \"{}\" sent to {}", mail.subject, mail.to);
+
+        Ok(())
+    }
+}
@@ -6,9 +6,13 @@ mod containers;
 mod gameloop;
 mod gametypes;
 mod items;
+mod mail;
 mod maps;
 mod npcs;
 mod players;
+mod progression;
+mod quests;
+mod skills;
 mod socket;
 mod sql;
 mod tasks;
@@ -19,19 +23,11 @@ use containers::{Storage, World};
 use gameloop::*;
 use gametypes::*;
 use log::{Level, Metadata, Record, error, info};
-use std::{env, fs::File, io::Write, panic};
+use std::{env, fs::File, io::Write, panic, sync::mpsc};
 
 use crate::containers::read_config;
 use time::Updater;
 
-// used to get string input when we add a command console to control the game.
-// until then we will just not use this.
-fn read_line() -> String {
-    let mut rv = String::new();
-    std::io::stdin().read_line(&mut rv).unwrap();
-    rv.replace("\r\n", "").replace('\n', "")
-}
-
 // creates a static global logger type for setting the logger
 static MY_LOGGER: MyLogger = MyLogger(Level::Debug);
 
@@ -48,7 +44,7 @@ impl log::Log for MyLogger {
     fn log(&self, record: &Record) {
         if self.enabled(record.metadata()) {
             let msg = format!("{} - {}\n", record.level(), record.args());
-            println!("{}", &msg);
+            println!("{}", msg);
 
             let mut file = match File::options()
                 .append(true)
@@ -62,7 +58,7 @@ impl log::Log for MyLogger {
             let _ = file.write(msg.as_bytes());
         } else if self.0 == Level::Info {
             let msg = format!("{} - {}\n", record.level(), record.args());
-            println!("{}", &msg);
+            println!("{}", msg);
         }
     }
     fn flush(&self) {}
@@ -97,12 +93,17 @@ fn main() {
     let updater = Updater::new().unwrap();
 
     info!("Initializing Storage");
-    let storage = Storage::new(config).unwrap();
+    let mut storage = Storage::new(config).unwrap();
     info!("Initializing World");
     let mut world = World::default();
 
+    info!("Initializing Console");
+    let (console_tx, console_rx) = mpsc::channel();
+    spawn_signal_handler(console_tx.clone()).unwrap();
+    spawn_console(console_tx).unwrap();
+
     info!("Game Server is Running.");
-    game_loop(&mut world, &storage);
+    game_loop(&mut world, &mut storage, &console_rx);
 
     updater.stop().unwrap();
 }
@@ -42,6 +42,10 @@ pub enum MapAttribute {
     ItemSpawn(ItemSpawnData),
     Storage,
     Shop(u16),
+    /// Players can not attack or be attacked by other players here.
+    SafeZone,
+    /// Players can fight each other here without pvp turned on and without pk penalties.
+    Arena,
     Count,
 }
 
@@ -93,45 +97,57 @@ impl Map {
     }
 }
 
-pub fn get_maps() -> Vec<Map> {
-    let entries = fs::read_dir(MAP_PATH).unwrap();
+/// The attribute of the tile at the position or None if its map is not loaded.
+pub fn tile_attribute(storage: &Storage, pos: Position) -> Option<&MapAttribute> {
+    storage
+        .bases
+        .maps
+        .get(&pos.map)
+        .and_then(|map| map.attribute.get(pos.as_tile()))
+}
+
+pub fn is_safe_zone(storage: &Storage, pos: Position) -> bool {
+    matches!(tile_attribute(storage, pos), Some(MapAttribute::SafeZone))
+}
+
+pub fn is_arena(storage: &Storage, pos: Position) -> bool {
+    matches!(tile_attribute(storage, pos), Some(MapAttribute::Arena))
+}
+
+pub fn get_maps() -> Result<Vec<Map>> {
+    let entries = fs::read_dir(MAP_PATH)?;
 
     let mut map_data: Vec<Map> = Vec::new();
 
     for entry_data in entries.flatten() {
         if let Ok(filename) = entry_data.file_name().into_string()
-            && let Some(mapdata) = load_map(filename)
+            && let Some(mapdata) = load_map(filename)?
         {
             map_data.push(mapdata);
         }
     }
 
-    map_data
+    Ok(map_data)
 }
 
-fn load_map(filename: String) -> Option<Map> {
+fn load_map(filename: String) -> Result<Option<Map>> {
     let name = format!("{MAP_PATH}{filename}");
 
     if !Path::new(&name).exists() {
         println!("Map does not exist");
-        return None;
+        return Ok(None);
     }
 
-    match OpenOptions::new().read(true).open(&name) {
-        Ok(mut file) => {
-            let mut bytes = Vec::new();
-            match file.read_to_end(&mut bytes) {
-                Ok(_) => Some(Map::read_from_buffer(&bytes).unwrap()),
-                Err(e) => {
-                    println!("Failed to load {name}, Err {e:?}");
-                    None
-                }
-            }
-        }
-        Err(e) => {
-            println!("Failed to load {name}, Err {e:?}");
-            None
-        }
+    let mut file = OpenOptions::new().read(true).open(&name)?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+
+    match Map::read_from_buffer(&bytes) {
+        Ok(map) => Ok(Some(map)),
+        Err(e) => Err(AscendingError::InvalidData {
+            file: name,
+            message: e.to_string(),
+        }),
     }
 }
 
@@ -163,6 +179,44 @@ pub struct MapData {
 }
 
 impl MapData {
+    pub fn from_map(map: &Map) -> MapData {
+        let mut data = MapData {
+            position: map.position,
+            ..Default::default()
+        };
+
+        data.build_grid(map);
+        data
+    }
+
+    /// Resets the move grid and item spawns from the map's attributes.
+    /// Entities standing on the map need to be re-added to the grid afterwards.
+    pub fn build_grid(&mut self, map: &Map) {
+        self.move_grid = [GridTile::default(); MAP_MAX_X * MAP_MAX_Y];
+        self.spawnable_item.clear();
+
+        for id in 0..MAP_MAX_X * MAP_MAX_Y {
+            match map.attribute[id].clone() {
+                MapAttribute::Blocked | MapAttribute::Storage | MapAttribute::Shop(_) => {
+                    self.move_grid[id].attr = GridAttribute::Blocked;
+                }
+                MapAttribute::NpcBlocked => {
+                    self.move_grid[id].attr = GridAttribute::NpcBlock;
+                }
+                MapAttribute::ItemSpawn(itemdata) => {
+                    self.add_spawnable_item(
+                        Position::new(id as i32 % 32, id as i32 / 32, map.position),
+                        itemdata.index,
+                        itemdata.amount,
+                        itemdata.timer,
+                    );
+                }
+                _ => {}
+            }
+            self.move_grid[id].dir_block = map.dir_block[id];
+        }
+    }
+
     pub fn get_surrounding(&self, include_corners: bool) -> Vec<MapPosition> {
         get_surrounding(self.position, include_corners)
     }
@@ -1,3 +1,4 @@
+mod casting;
 mod combat;
 mod logic;
 mod movement;
@@ -6,6 +7,7 @@ mod npcdata;
 mod pathfinding;
 mod targeting;
 
+pub use casting::*;
 pub use combat::*;
 pub use logic::*;
 pub use movement::*;
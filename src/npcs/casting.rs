@@ -0,0 +1,323 @@
+use crate::{
+    containers::{Entity, EntityKind, GlobalKey, NpcSupportTarget, Storage, World},
+    gametypes::*,
+    maps::can_target,
+    npcs::*,
+    players::{area_targets, entity_state, has_status, skill_apply_effect},
+    skills::{SkillArea, SkillData},
+    tasks::{DataTaskToken, cast_packet, vitals_packet},
+};
+use chrono::Duration;
+
+fn npc_skill_base(storage: &Storage, skill: u16) -> Option<&SkillData> {
+    storage
+        .bases
+        .skills
+        .get(skill as usize)
+        .filter(|base| base.is_valid())
+}
+
+/// Returns how much health the npc has left from 0.0 to 1.0 if it is alive and hurt.
+fn npc_hurt_ratio(world: &mut World, entity: GlobalKey) -> Result<Option<f64>> {
+    if let Some(Entity::Npc(n_data)) = world.get_opt_entity(entity) {
+        let n_data = n_data.try_lock()?;
+        let hp = n_data.combat.vitals.vital[VitalTypes::Hp as usize];
+        let maxhp = n_data.combat.vitals.vitalmax[VitalTypes::Hp as usize];
+
+        if n_data.combat.death_type.is_alive() && maxhp > 0 && hp < maxhp {
+            return Ok(Some(hp as f64 / maxhp as f64));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns true if the npc is on the caster's side. Npcs never count themselves as allies
+/// of an npc type they are set up to attack.
+fn npc_is_ally(world: &mut World, base: &NpcData, entity: GlobalKey) -> Result<bool> {
+    if let Some(Entity::Npc(n_data)) = world.get_opt_entity(entity) {
+        return Ok(!base.enemies.contains(&n_data.try_lock()?.index));
+    }
+
+    Ok(false)
+}
+
+/// Picks the most hurt ally within sight for a healer to cast its heals on.
+/// Healers without allies only ever look after themselves.
+pub fn npc_support_targeting(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    base: &NpcData,
+) -> Result<()> {
+    if !base.is_healer() || !base.has_friendonly {
+        return Ok(());
+    }
+
+    let Some(Entity::Npc(n_data)) = world.get_opt_entity(entity) else {
+        return Ok(());
+    };
+
+    let pos = n_data.try_lock()?.movement.pos;
+
+    let allies = if base.has_allys {
+        area_targets(world, storage, pos, base.sight, EntityKind::Npc)?
+    } else {
+        vec![entity]
+    };
+
+    let mut support: Option<(GlobalKey, f64)> = None;
+
+    for ally in allies {
+        if ally != entity && !npc_is_ally(world, base, ally)? {
+            continue;
+        }
+
+        if let Some(ratio) = npc_hurt_ratio(world, ally)?
+            && support.is_none_or(|(_, lowest)| ratio < lowest)
+        {
+            support = Some((ally, ratio));
+        }
+    }
+
+    n_data.try_lock()?.support = NpcSupportTarget(support.map(|(ally, _)| ally));
+
+    Ok(())
+}
+
+/// Returns every entity the npc's skill should hit in the area. Players are only hit
+/// if the npc can attack them and npcs only if they are one of the npc's enemies.
+fn npc_area_enemies(
+    world: &mut World,
+    storage: &Storage,
+    base: &NpcData,
+    center: Position,
+    radius: i32,
+) -> Result<Vec<GlobalKey>> {
+    let mut enemies = Vec::new();
+
+    if base.can_attack_player {
+        for target in area_targets(world, storage, center, radius, EntityKind::Player)? {
+            if let Some(Entity::Player(p_data)) = world.get_opt_entity(target)
+                && !p_data.try_lock()?.is_using_type.inuse()
+            {
+                enemies.push(target);
+            }
+        }
+    }
+
+    if base.has_enemies {
+        for target in area_targets(world, storage, center, radius, EntityKind::Npc)? {
+            if !npc_is_ally(world, base, target)? {
+                enemies.push(target);
+            }
+        }
+    }
+
+    Ok(enemies)
+}
+
+fn npc_area_allies(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    base: &NpcData,
+    center: Position,
+    radius: i32,
+) -> Result<Vec<GlobalKey>> {
+    if !base.has_allys {
+        return Ok(vec![entity]);
+    }
+
+    let mut allies = Vec::new();
+
+    for target in area_targets(world, storage, center, radius, EntityKind::Npc)? {
+        if target == entity || npc_is_ally(world, base, target)? {
+            allies.push(target);
+        }
+    }
+
+    Ok(allies)
+}
+
+/// Works out where the skill would land and who it is aimed at.
+/// Returns None if the npc has nothing worth casting the skill on right now.
+fn npc_skill_aim(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    base: &NpcData,
+    skill: &SkillData,
+) -> Result<Option<(Position, Option<GlobalKey>)>> {
+    let (pos, target, support) = if let Some(Entity::Npc(n_data)) = world.get_opt_entity(entity) {
+        let n_data = n_data.try_lock()?;
+
+        (
+            n_data.movement.pos,
+            n_data.combat.target.target_entity,
+            n_data.support.0,
+        )
+    } else {
+        return Ok(None);
+    };
+
+    match skill.cast_type {
+        // Buffs are only worth casting once the npc is fighting something.
+        NpcCastType::SelfOnly if base.has_selfonly && target.is_some() => {
+            Ok(Some((pos, Some(entity))))
+        }
+        NpcCastType::Friend if base.has_friendonly => {
+            let Some(ally) = support else {
+                return Ok(None);
+            };
+
+            if npc_hurt_ratio(world, ally)?.is_none() {
+                return Ok(None);
+            }
+
+            match entity_state(world, ally)? {
+                Some((ally_pos, death_type))
+                    if can_target(pos, ally_pos, death_type, skill.range) =>
+                {
+                    Ok(Some((ally_pos, Some(ally))))
+                }
+                _ => Ok(None),
+            }
+        }
+        NpcCastType::Enemy if base.can_attack => {
+            let Some(target) = target else {
+                return Ok(None);
+            };
+
+            if !try_cast(
+                world,
+                storage,
+                entity,
+                base,
+                target,
+                skill.range,
+                NpcCastType::Enemy,
+            )? {
+                return Ok(None);
+            }
+
+            Ok(entity_state(world, target)?.map(|(target_pos, _)| (target_pos, Some(target))))
+        }
+        // Ground skills are dropped on the target's position rather than aimed at them.
+        NpcCastType::Ground if base.has_groundonly => {
+            let Some(target) = target else {
+                return Ok(None);
+            };
+
+            match entity_state(world, target)? {
+                Some((target_pos, death_type))
+                    if can_target(pos, target_pos, death_type, skill.range) =>
+                {
+                    Ok(Some((target_pos, None)))
+                }
+                _ => Ok(None),
+            }
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Casts the first of the npc's skills that is ready, affordable and has something to hit.
+/// Returns true if a skill was cast so the npc does not also melee this turn.
+pub fn npc_cast_skill(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    base: &NpcData,
+) -> Result<bool> {
+    if base.skills.is_empty() || has_status(world, entity, StatusKind::Silence)? {
+        return Ok(false);
+    }
+
+    let Some(Entity::Npc(n_data)) = world.get_opt_entity(entity) else {
+        return Ok(false);
+    };
+
+    let tick = *storage.gettick.borrow();
+
+    let (pos, mp) = {
+        let n_data = n_data.try_lock()?;
+
+        (
+            n_data.movement.pos,
+            n_data.combat.vitals.vital[VitalTypes::Mp as usize],
+        )
+    };
+
+    for &skill in &base.skills {
+        let Some(skill_data) = npc_skill_base(storage, skill) else {
+            continue;
+        };
+
+        if skill_data.mp_cost > mp
+            || n_data
+                .try_lock()?
+                .skill_cooldowns
+                .0
+                .get(&skill)
+                .is_some_and(|cooldown| *cooldown > tick)
+        {
+            continue;
+        }
+
+        let Some((center, primary)) = npc_skill_aim(world, storage, entity, base, skill_data)?
+        else {
+            continue;
+        };
+
+        let targets = match (skill_data.area, skill_data.cast_type) {
+            (SkillArea::Single, NpcCastType::Ground) => {
+                npc_area_enemies(world, storage, base, center, 0)?
+            }
+            (SkillArea::Single, _) => primary.into_iter().collect(),
+            (SkillArea::Radius(radius), NpcCastType::SelfOnly | NpcCastType::Friend) => {
+                npc_area_allies(world, storage, entity, base, center, radius)?
+            }
+            (SkillArea::Radius(radius), NpcCastType::Enemy | NpcCastType::Ground) => {
+                let mut targets = npc_area_enemies(world, storage, base, center, radius)?;
+
+                if let Some(primary) = primary
+                    && !targets.contains(&primary)
+                {
+                    targets.push(primary);
+                }
+
+                targets
+            }
+        };
+
+        let vitals = {
+            let mut n_data = n_data.try_lock()?;
+
+            n_data.combat.vitals.vital[VitalTypes::Mp as usize] -= skill_data.mp_cost;
+            n_data.skill_cooldowns.0.insert(
+                skill,
+                tick + Duration::try_milliseconds(skill_data.cooldown).unwrap_or_default(),
+            );
+
+            n_data.combat.vitals
+        };
+
+        DataTaskToken::Cast(pos.map).add_task(storage, cast_packet(entity, skill, center)?)?;
+
+        if skill_data.mp_cost > 0 {
+            DataTaskToken::Vitals(pos.map).add_task(
+                storage,
+                vitals_packet(entity, vitals.vital, vitals.vitalmax)?,
+            )?;
+        }
+
+        for target in targets {
+            skill_apply_effect(world, storage, entity, target, skill_data.effect)?;
+        }
+
+        return Ok(true);
+    }
+
+    Ok(false)
+}
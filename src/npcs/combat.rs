@@ -10,14 +10,25 @@ use crate::{
 };
 use rand::{Rng, rng};
 
-pub fn damage_npc(world: &mut World, entity: GlobalKey, damage: i32) -> Result<()> {
+/// Takes the damage off the target's hp and puts both sides in combat.
+pub fn damage_npc(
+    world: &mut World,
+    storage: &Storage,
+    attacker: GlobalKey,
+    entity: GlobalKey,
+    damage: i32,
+) -> Result<()> {
     if let Some(Entity::Npc(n_data)) = world.get_opt_entity(entity) {
-        let mut n_data = n_data.try_lock()?;
+        {
+            let mut n_data = n_data.try_lock()?;
+
+            n_data.combat.vitals.vital[VitalTypes::Hp as usize] = n_data.combat.vitals.vital
+                [VitalTypes::Hp as usize]
+                .saturating_sub(damage)
+                .max(0);
+        }
 
-        n_data.combat.vitals.vital[VitalTypes::Hp as usize] = n_data.combat.vitals.vital
-            [VitalTypes::Hp as usize]
-            .saturating_sub(damage)
-            .max(0);
+        enter_combat(world, storage, attacker, entity)?;
     }
     Ok(())
 }
@@ -189,6 +200,10 @@ pub fn npc_combat(
     base: &NpcData,
 ) -> Result<()> {
     if let Some(Entity::Npc(n_data)) = world.get_opt_entity(entity) {
+        if npc_cast_skill(world, storage, entity, base)? || !base.can_attack {
+            return Ok(());
+        }
+
         let target = npc_cast(world, storage, entity, base)?;
 
         if let Some(t_entity) = target {
@@ -202,7 +217,7 @@ pub fn npc_combat(
                 match e_result {
                     Entity::Player(p2_data) => {
                         let damage = npc_combat_damage(world, storage, entity, t_entity, base)?;
-                        damage_player(world, t_entity, damage)?;
+                        damage_player(world, storage, entity, t_entity, damage)?;
 
                         let (t_pos, t_vitals) = {
                             let p2_data = p2_data.try_lock()?;
@@ -223,12 +238,12 @@ pub fn npc_combat(
                             })?;
                         } else {
                             remove_all_npc_target(world, t_entity)?;
-                            kill_player(world, storage, t_entity)?;
+                            kill_player(world, storage, t_entity, Some(entity))?;
                         }
                     }
                     Entity::Npc(n2_data) => {
                         let damage = npc_combat_damage(world, storage, entity, t_entity, base)?;
-                        damage_npc(world, t_entity, damage)?;
+                        damage_npc(world, storage, entity, t_entity, damage)?;
 
                         let (t_pos, t_vitals) = {
                             let n2_data = n2_data.try_lock()?;
@@ -249,7 +264,7 @@ pub fn npc_combat(
                             })?;
                             try_target_entity(world, storage, t_entity, entity)?;
                         } else {
-                            kill_npc(world, storage, t_entity)?;
+                            kill_npc(world, storage, t_entity, None)?;
                         }
                     }
                     _ => {}
@@ -285,12 +300,15 @@ pub fn npc_combat_damage(
 
                 p_data.combat.physical.defense
                     + armor_def as u32
-                    + p_data.combat.level.saturating_div(5) as u32
+                    + p_data.combat.effects.power(StatusKind::DefenseBuff).max(0) as u32
             } else {
                 0
             }
         } else if let Some(Entity::Npc(n2_data)) = world.get_opt_entity(enemy_entity) {
-            n2_data.try_lock()?.combat.physical.defense
+            let n2_data = n2_data.try_lock()?;
+
+            n2_data.combat.physical.defense
+                + n2_data.combat.effects.power(StatusKind::DefenseBuff).max(0) as u32
         } else {
             0
         };
@@ -301,17 +319,20 @@ pub fn npc_combat_damage(
             2
         };
 
+        let buff = n_data.combat.effects.power(StatusKind::DamageBuff).max(0) as u32;
+        let maxdamage = base.maxdamage.saturating_add(buff);
         let mut damage = n_data
             .combat
             .physical
             .damage
+            .saturating_add(buff)
             .saturating_sub(def / offset)
             .max(base.mindamage);
         let mut rng = rng();
 
         //set to max before we set to max i32 just in case. Order matters here.
-        if damage > base.maxdamage {
-            damage = base.maxdamage;
+        if damage > maxdamage {
+            damage = maxdamage;
         }
 
         //protect from accidental heals due to u32 to i32 conversion.
@@ -333,7 +354,13 @@ pub fn npc_combat_damage(
     }
 }
 
-pub fn kill_npc(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+/// Kills the npc and rolls its drops. killer is the player credited with the kill for quests.
+pub fn kill_npc(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    killer: Option<GlobalKey>,
+) -> Result<()> {
     if let Some(Entity::Npc(n_data)) = world.get_opt_entity(entity) {
         let (npc_index, npc_pos) = {
             let mut n_data = n_data.try_lock()?;
@@ -373,6 +400,10 @@ pub fn kill_npc(world: &mut World, storage: &Storage, entity: GlobalKey) -> Resu
                 }
             }
         }
+
+        if let Some(killer) = killer {
+            quest_npc_killed(world, storage, killer, npc_index)?;
+        }
     }
 
     Ok(())
@@ -2,6 +2,7 @@ use crate::{
     containers::{DeathType, Entity, Storage, World},
     gametypes::*,
     npcs::*,
+    players::update_status_effects,
     tasks::*,
 };
 use chrono::Duration;
@@ -39,6 +40,7 @@ pub fn update_npcs_targetting(
                     None => continue,
                 }
             {
+                npc_support_targeting(world, storage, id, npcdata)?;
                 targeting(world, storage, id, npcdata)?;
             }
         }
@@ -64,16 +66,19 @@ pub fn update_npcs_movement(
         };
 
         if let Some(Entity::Npc(n_data)) = world.get_opt_entity(id) {
-            let (death_type, entity_index, movement_timer) = {
+            let (death_type, entity_index, movement_timer, stunned, slow) = {
                 let n_data = n_data.try_lock()?;
                 (
                     n_data.combat.death_type,
                     n_data.index,
                     n_data.movement.move_timer,
+                    n_data.combat.stunned,
+                    n_data.combat.effects.power(StatusKind::Slow).max(0) as i64,
                 )
             };
 
             if death_type.is_alive()
+                && !stunned
                 && let Some(npcdata) = storage.bases.npcs.get(entity_index as usize)
             {
                 //movement
@@ -81,7 +86,10 @@ pub fn update_npcs_movement(
                     npc_update_path(world, storage, id, npcdata)?;
                     npc_movement(world, storage, id, npcdata)?;
                     n_data.try_lock()?.movement.move_timer.0 = tick
-                        + Duration::try_milliseconds(npcdata.movement_wait).unwrap_or_default();
+                        + Duration::try_milliseconds(
+                            npcdata.movement_wait + npcdata.movement_wait * slow / 100,
+                        )
+                        .unwrap_or_default();
                 }
             }
         }
@@ -103,7 +111,7 @@ pub fn update_npcs_combat(world: &mut World, storage: &Storage, batch_index: usi
         };
 
         if let Some(Entity::Npc(n_data)) = world.get_opt_entity(id) {
-            let (death_type, entity_index, map_pos, attack_timer) = {
+            let (death_type, entity_index, map_pos, attack_timer, stunned) = {
                 let n_data = n_data.try_lock()?;
 
                 (
@@ -111,14 +119,16 @@ pub fn update_npcs_combat(world: &mut World, storage: &Storage, batch_index: usi
                     n_data.index,
                     n_data.movement.pos.map,
                     n_data.combat.attack_timer,
+                    n_data.combat.stunned,
                 )
             };
 
             if death_type.is_alive()
+                && !stunned
                 && let Some(npcdata) = storage.bases.npcs.get(entity_index as usize)
             {
-                //attacking
-                if npcdata.can_attack
+                //attacking and spellcasting
+                if (npcdata.can_attack || !npcdata.skills.is_empty())
                     && match storage.maps.get(&map_pos) {
                         Some(map) => map.borrow().players_on_map(),
                         None => continue,
@@ -137,6 +147,22 @@ pub fn update_npcs_combat(world: &mut World, storage: &Storage, batch_index: usi
     Ok(())
 }
 
+pub fn update_npcs_status(world: &mut World, storage: &Storage, batch_index: usize) -> Result<()> {
+    let start = 5 * batch_index;
+    let end = start + 5;
+
+    for index in start..end {
+        let id = match storage.npc_ids.borrow().get_index(index) {
+            Some(data) => *data,
+            None => return Ok(()),
+        };
+
+        update_status_effects(world, storage, id)?;
+    }
+
+    Ok(())
+}
+
 pub fn update_npcs_spawn(world: &mut World, storage: &Storage, batch_index: usize) -> Result<()> {
     let tick = *storage.gettick.borrow();
 
@@ -183,28 +209,26 @@ pub fn update_npcs_spawn(world: &mut World, storage: &Storage, batch_index: usiz
                     }
                 }
                 DeathType::Dead => storage.unload_npc.borrow_mut().push(id),
-                DeathType::Spawning => {
-                    if spawn_timer < tick {
-                        let map_data = match storage.maps.get(&spawn_pos.map) {
-                            Some(map) => map,
-                            None => continue,
-                        };
+                DeathType::Spawning if spawn_timer < tick => {
+                    let map_data = match storage.maps.get(&spawn_pos.map) {
+                        Some(map) => map,
+                        None => continue,
+                    };
 
-                        let world_entity_type = world.get_kind(id)?;
+                    let world_entity_type = world.get_kind(id)?;
 
-                        //make sure we can spawn here before even spawning them.
-                        if !map_data
-                            .borrow()
-                            .is_blocked_tile(spawn_pos, world_entity_type)
+                    //make sure we can spawn here before even spawning them.
+                    if !map_data
+                        .borrow()
+                        .is_blocked_tile(spawn_pos, world_entity_type)
+                    {
                         {
-                            {
-                                n_data.try_lock()?.combat.death_type = DeathType::Alive;
-                            }
-                            map_data.borrow_mut().add_entity_to_grid(spawn_pos);
-
-                            DataTaskToken::NpcSpawn(spawn_pos.map)
-                                .add_task(storage, npc_spawn_packet(world, id, true)?)?;
+                            n_data.try_lock()?.combat.death_type = DeathType::Alive;
                         }
+                        map_data.borrow_mut().add_entity_to_grid(spawn_pos);
+
+                        DataTaskToken::NpcSpawn(spawn_pos.map)
+                            .add_task(storage, npc_spawn_packet(world, id, true)?)?;
                     }
                 }
                 _ => {}
@@ -67,6 +67,14 @@ pub struct NpcData {
     pub drops: [NpcDrop; 10],
     pub free_shares: u32,
     pub exp: i64,
+    /// Skills the npc can cast, tried in order each time it attacks.
+    #[serde(default)]
+    #[speedy(default_on_eof)]
+    pub skills: Vec<u16>,
+    /// Vitals restored every regen tick, indexed by VitalTypes.
+    #[serde(default)]
+    #[speedy(default_on_eof)]
+    pub regens: [u32; VITALS_MAX],
     // Drop Data
     #[speedy(skip)]
     pub drop_ranges: RangeMap<u32, usize>,
@@ -102,53 +110,56 @@ impl NpcData {
     }
 }
 
-pub fn get_npc() -> Vec<NpcData> {
+pub fn get_npc() -> Result<Vec<NpcData>> {
     let mut npc_data: Vec<NpcData> = Vec::new();
 
-    let mut count = 0;
-    let mut got_data = true;
+    while let Some(mut data) = load_file(npc_data.len() as u64)? {
+        if npc_data.len() >= MAX_NPCS {
+            return Err(AscendingError::InvalidData {
+                file: "./data/npcs/".into(),
+                message: format!("more than {MAX_NPCS} npcs"),
+            });
+        }
 
-    while got_data {
-        if let Some(mut data) = load_file(count) {
-            // Setup drop
-            let mut pos = 0;
-            let mut max_shares = 0;
-            for (slot_id, drops) in data.drops.iter().enumerate() {
-                if drops.shares == 0 {
-                    continue;
-                }
+        // Setup drop
+        let mut pos = 0;
+        let mut max_shares = 0;
+        for (slot_id, drops) in data.drops.iter().enumerate() {
+            if drops.shares == 0 {
+                continue;
+            }
 
-                max_shares += drops.shares;
+            max_shares += drops.shares;
 
-                let range = pos..pos + drops.shares;
-                pos += drops.shares;
-                data.drop_ranges.insert(range, slot_id);
-            }
-            max_shares += data.free_shares;
-            data.max_shares = max_shares;
-
-            npc_data.push(data);
-            count += 1;
-            got_data = true;
-        } else {
-            got_data = false;
+            let range = pos..pos + drops.shares;
+            pos += drops.shares;
+            data.drop_ranges.insert(range, slot_id);
         }
+        max_shares += data.free_shares;
+        data.max_shares = max_shares;
+
+        npc_data.push(data);
     }
 
-    npc_data
+    Ok(npc_data)
 }
 
-fn load_file(id: u64) -> Option<NpcData> {
+fn load_file(id: u64) -> Result<Option<NpcData>> {
     let name = format!("./data/npcs/{id}.bin");
 
-    match OpenOptions::new().read(true).open(name) {
+    match OpenOptions::new().read(true).open(&name) {
         Ok(mut file) => {
             let mut bytes = Vec::new();
-            match file.read_to_end(&mut bytes) {
-                Ok(_) => Some(NpcData::read_from_buffer(&bytes).unwrap()),
-                Err(_) => None,
+            file.read_to_end(&mut bytes)?;
+
+            match NpcData::read_from_buffer(&bytes) {
+                Ok(data) => Ok(Some(data)),
+                Err(e) => Err(AscendingError::InvalidData {
+                    file: name,
+                    message: e.to_string(),
+                }),
             }
         }
-        Err(_) => None,
+        Err(_) => Ok(None),
     }
 }
@@ -1,16 +1,34 @@
+mod attributes;
 mod combat;
+mod guild;
 mod inv;
 mod logic;
 pub mod movement;
+mod party;
 mod player;
 mod player_storage;
+mod pvp;
+mod quest;
+mod regen;
+mod skill;
+mod status;
+#[cfg(test)]
+mod tests;
 
+pub use attributes::*;
 pub use combat::*;
+pub use guild::*;
 pub use inv::*;
 pub use logic::*;
 pub use movement::*;
+pub use party::*;
 pub use player::*;
 pub use player_storage::*;
+pub use pvp::*;
+pub use quest::*;
+pub use regen::*;
+pub use skill::*;
+pub use status::*;
 
 pub const fn is_name_acceptable(n: char) -> bool {
     matches!(n, '!' | '$' | '&' | '_' | '~' | '0'..='9' | 'A'..='Z' | 'a'..='z')
@@ -0,0 +1,61 @@
+use crate::{
+    containers::{Entity, GlobalKey, Storage, World},
+    gametypes::*,
+    socket::*,
+    sql::SaveJob,
+    tasks::{DataTaskToken, vitals_packet},
+};
+
+/// Spends unspent points on an attribute and recalculates the player's stats.
+/// Asking for more points than the player has is ignored.
+pub fn player_allocate_points(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    attribute: AttributeType,
+    amount: u16,
+) -> Result<()> {
+    if attribute == AttributeType::Count || amount == 0 {
+        return Ok(());
+    }
+
+    let (uid, attributes, map, vitals) =
+        if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+            let p_data = &mut *p_data.try_lock()?;
+            let amount = amount as i32;
+
+            if p_data.attributes.points < amount {
+                return Ok(());
+            }
+
+            p_data.attributes.points -= amount;
+            p_data.attributes.values[attribute as usize] =
+                p_data.attributes.values[attribute as usize].saturating_add(amount);
+
+            storage
+                .bases
+                .progression
+                .apply(&mut p_data.combat, &p_data.attributes);
+
+            (
+                p_data.account.character,
+                p_data.attributes,
+                p_data.movement.pos.map,
+                p_data.combat.vitals,
+            )
+        } else {
+            return Ok(());
+        };
+
+    storage.saves.save(SaveJob::Attributes {
+        uid,
+        attributes: attributes.values,
+        stat_points: attributes.points,
+    })?;
+
+    send_attributes(world, storage, entity)?;
+    DataTaskToken::Vitals(map).add_task(
+        storage,
+        vitals_packet(entity, vitals.vital, vitals.vitalmax)?,
+    )
+}
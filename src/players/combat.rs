@@ -1,23 +1,42 @@
 use crate::{
     containers::{DeathType, Entity, EntityKind, GlobalKey, Storage, World},
     gametypes::*,
-    maps::{can_target, is_dir_blocked},
+    items::Item,
+    maps::{DropItem, can_target, is_arena, is_dir_blocked, try_drop_item},
     npcs::{can_attack_npc, damage_npc, kill_npc, try_target_entity},
     players::*,
-    tasks::{DataTaskToken, attack_packet, damage_packet, vitals_packet},
+    socket::*,
+    sql::{player_item_log, update_player},
+    tasks::{DataTaskToken, attack_packet, damage_packet, death_packet, vitals_packet},
 };
-use rand::*;
+use chrono::Duration;
+use rand::{seq::IteratorRandom, *};
 use std::cmp;
 
+/// Takes the damage off the target's hp and puts both sides in combat.
 #[inline]
-pub fn damage_player(world: &mut World, entity: GlobalKey, damage: i32) -> Result<()> {
+pub fn damage_player(
+    world: &mut World,
+    storage: &Storage,
+    attacker: GlobalKey,
+    entity: GlobalKey,
+    damage: i32,
+) -> Result<()> {
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
-        let mut p_data = p_data.try_lock()?;
+        {
+            let mut p_data = p_data.try_lock()?;
+
+            p_data.combat.vitals.vital[VitalTypes::Hp as usize] = p_data.combat.vitals.vital
+                [VitalTypes::Hp as usize]
+                .saturating_sub(damage)
+                .max(0);
+        }
+
+        enter_combat(world, storage, attacker, entity)?;
 
-        p_data.combat.vitals.vital[VitalTypes::Hp as usize] = p_data.combat.vitals.vital
-            [VitalTypes::Hp as usize]
-            .saturating_sub(damage)
-            .max(0);
+        if attacker != entity && world.get_kind_or_default(attacker) == EntityKind::Player {
+            player_pvp_attack(world, storage, attacker, entity)?;
+        }
     }
     Ok(())
 }
@@ -28,11 +47,15 @@ pub fn get_damage_percentage(damage: u32, hp: (u32, u32)) -> f64 {
     abs_damage / curhp as f64
 }
 
+/// Checks the caster can reach the target. hostile casts on players also have to be
+/// allowed by the pvp rules.
 pub fn try_player_cast(
     world: &mut World,
     storage: &Storage,
     caster: GlobalKey,
     target: GlobalKey,
+    range: i32,
+    hostile: bool,
 ) -> Result<bool> {
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(caster) {
         if caster == target {
@@ -76,6 +99,13 @@ pub fn try_player_cast(
             _ => return Ok(false),
         };
 
+        if hostile
+            && target_kind == EntityKind::Player
+            && !can_attack_player(world, storage, caster, target)?
+        {
+            return Ok(false);
+        }
+
         if let Some(dir) = caster_pos.checkdirection(target_pos) {
             if is_dir_blocked(storage, caster_pos, dir as u8) {
                 return Ok(false);
@@ -84,7 +114,7 @@ pub fn try_player_cast(
             return Ok(false);
         }
 
-        Ok(can_target(caster_pos, target_pos, life, 1))
+        Ok(can_target(caster_pos, target_pos, life, range))
     } else {
         Ok(false)
     }
@@ -100,7 +130,7 @@ pub fn player_combat(
         return Ok(false);
     }
 
-    if try_player_cast(world, storage, entity, target_entity)? {
+    if try_player_cast(world, storage, entity, target_entity, 1, true)? {
         if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
             let pos = {
                 let p_data = p_data.try_lock()?;
@@ -112,7 +142,7 @@ pub fn player_combat(
                 match e_result {
                     Entity::Player(p2_data) => {
                         let damage = player_combat_damage(world, storage, entity, target_entity)?;
-                        damage_player(world, target_entity, damage)?;
+                        damage_player(world, storage, entity, target_entity, damage)?;
 
                         let (t_pos, t_vitals) = {
                             let p2_data = p2_data.try_lock()?;
@@ -131,14 +161,14 @@ pub fn player_combat(
                                 vitals_packet(target_entity, t_vitals.vital, t_vitals.vitalmax)?
                             })?;
                         } else {
-                            kill_player(world, storage, target_entity)?;
+                            kill_player(world, storage, target_entity, Some(entity))?;
                         }
                     }
                     Entity::Npc(n2_data) => {
                         if can_attack_npc(world, storage, target_entity)? {
                             let damage =
                                 player_combat_damage(world, storage, entity, target_entity)?;
-                            damage_npc(world, target_entity, damage)?;
+                            damage_npc(world, storage, entity, target_entity, damage)?;
 
                             let (t_pos, t_vitals, npc_index, level) = {
                                 let n2_data = n2_data.try_lock()?;
@@ -169,7 +199,7 @@ pub fn player_combat(
                                 let exp = base.exp;
 
                                 player_earn_exp(world, storage, entity, level, exp, 1.0)?;
-                                kill_npc(world, storage, target_entity)?;
+                                kill_npc(world, storage, target_entity, Some(entity))?;
                             }
                         } else {
                             return Ok(false);
@@ -209,12 +239,15 @@ pub fn player_combat_damage(
 
                 p2_data.combat.physical.defense
                     + armor_def as u32
-                    + p2_data.combat.level.saturating_div(5) as u32
+                    + p2_data.combat.effects.power(StatusKind::DefenseBuff).max(0) as u32
             } else {
                 0
             }
         } else if let Some(Entity::Npc(n2_data)) = world.get_opt_entity(target_entity) {
-            n2_data.try_lock()?.combat.physical.defense
+            let n2_data = n2_data.try_lock()?;
+
+            n2_data.combat.physical.defense
+                + n2_data.combat.effects.power(StatusKind::DefenseBuff).max(0) as u32
         } else {
             0
         };
@@ -229,6 +262,7 @@ pub fn player_combat_damage(
             .combat
             .physical
             .damage
+            .saturating_add(p_data.combat.effects.power(StatusKind::DamageBuff).max(0) as u32)
             .saturating_sub(def / offset)
             .max(1);
         let mut rng = rng();
@@ -254,24 +288,209 @@ pub fn player_combat_damage(
     }
 }
 
-pub fn kill_player(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
-    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
-        let (vitals, spawn) = {
-            let mut p_data = p_data.try_lock()?;
+/// Turns the player into a spirit until the death timer runs out and takes the exp,
+/// money and items the progression data says a death costs. Player killers pay more
+/// and lose their pk status while arena deaths cost nothing. killer is whoever made
+/// the kill and owns the drops when it is a player.
+pub fn kill_player(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    killer: Option<GlobalKey>,
+) -> Result<()> {
+    let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) else {
+        return Ok(());
+    };
+
+    let tick = *storage.gettick.borrow();
+    let rules = &storage.bases.progression.death;
+
+    let (pos, exp_lost, money, item_drops, was_pk) = {
+        let mut p_data = p_data.try_lock()?;
+
+        if !p_data.combat.death_type.is_alive() {
+            return Ok(());
+        }
+
+        let was_pk = p_data.general.pk;
+        let (exp_loss_percent, money_drop_percent, item_drops) =
+            if is_arena(storage, p_data.movement.pos) {
+                (0.0, 0.0, 0)
+            } else if was_pk {
+                (
+                    rules.pk_exp_loss_percent,
+                    rules.pk_money_drop_percent,
+                    rules.pk_item_drops,
+                )
+            } else {
+                (
+                    rules.exp_loss_percent,
+                    rules.money_drop_percent,
+                    rules.item_drops,
+                )
+            };
+
+        p_data.combat.death_type = DeathType::Spirit;
+        p_data.combat.death_timer.0 =
+            tick + Duration::try_milliseconds(rules.spirit_ms).unwrap_or_default();
+        p_data.combat.vitals.vital[VitalTypes::Hp as usize] = 0;
+        p_data.combat.target.target_entity = None;
+
+        let next_level_exp = storage
+            .bases
+            .progression
+            .next_level_exp(p_data.combat.level);
+        let exp_lost = ((next_level_exp as f64 * exp_loss_percent / 100.0) as u64)
+            .min(p_data.general.levelexp);
+
+        p_data.general.levelexp -= exp_lost;
+        p_data.general.pk = false;
+
+        let money = (p_data.money.vals as f64 * money_drop_percent / 100.0) as u64;
 
-            p_data.combat.death_type = DeathType::Alive;
+        (p_data.movement.pos, exp_lost, money, item_drops, was_pk)
+    };
 
-            p_data.combat.vitals.vital = p_data.combat.vitals.vitalmax;
-            p_data.combat.target.target_entity = None;
+    DataTaskToken::Death(pos.map).add_task(storage, death_packet(entity, DeathType::Spirit)?)?;
 
-            (p_data.combat.vitals, p_data.movement.spawn)
+    // Npcs can not pick items up so the dead player gets first claim on what they drop.
+    let owner = killer
+        .filter(|killer| {
+            *killer != entity && world.get_kind_or_default(*killer) == EntityKind::Player
+        })
+        .unwrap_or(entity);
+
+    drop_death_items(world, storage, entity, owner, pos, money, item_drops)?;
+
+    if was_pk {
+        send_pk(world, storage, entity, false)?;
+    }
+
+    let socket_id = p_data.try_lock()?.socket.id;
+
+    send_fltalert(
+        storage,
+        socket_id,
+        format!("You have died and lost {exp_lost} exp."),
+        FtlType::Level,
+    )?;
+    send_level(world, storage, entity)?;
+    update_player(storage, world, entity)
+}
+
+/// Drops the money and item_drops random inventory stacks where the player died.
+fn drop_death_items(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    owner: GlobalKey,
+    pos: Position,
+    money: u64,
+    item_drops: u32,
+) -> Result<()> {
+    let tick = *storage.gettick.borrow();
+    let rules = &storage.bases.progression.death;
+    let despawn = Some(tick + Duration::try_milliseconds(rules.despawn_ms).unwrap_or_default());
+    let ownertimer = Some(tick + Duration::try_milliseconds(rules.owner_ms).unwrap_or_default());
+
+    // Money lies on the ground as item 0 and is capped by what one map item can hold.
+    let money = money.min(u16::MAX as u64);
+
+    if money > 0
+        && try_drop_item(
+            world,
+            storage,
+            DropItem {
+                index: 0,
+                amount: money as u16,
+                pos,
+            },
+            despawn,
+            ownertimer,
+            Some(owner),
+        )?
+    {
+        player_take_vals(world, storage, entity, money)?;
+    }
+
+    if item_drops == 0 {
+        return Ok(());
+    }
+
+    let slots: Vec<(usize, Item)> =
+        if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+            let p_data = p_data.try_lock()?;
+
+            p_data
+                .inventory
+                .items
+                .iter()
+                .copied()
+                .enumerate()
+                .filter(|(_, item)| item.val > 0)
+                .choose_multiple(&mut rng(), item_drops as usize)
+        } else {
+            return Ok(());
         };
 
-        player_warp(world, storage, entity, &spawn.pos, false)?;
+    for (slot, item) in slots {
+        if !try_drop_item(
+            world,
+            storage,
+            DropItem {
+                index: item.num,
+                amount: item.val,
+                pos,
+            },
+            despawn,
+            ownertimer,
+            Some(owner),
+        )? {
+            break;
+        }
 
-        DataTaskToken::Vitals(spawn.pos.map).add_task(storage, {
-            vitals_packet(entity, vitals.vital, vitals.vitalmax)?
-        })?;
+        take_inv_itemslot(world, storage, entity, slot, item.val)?;
+        player_item_log(
+            world,
+            storage,
+            entity,
+            "Dropped on death",
+            item.num,
+            item.val as u64,
+        )?;
     }
+
     Ok(())
 }
+
+/// Brings a spirit back to life at their spawn with full vitals.
+pub fn revive_player(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) else {
+        return Ok(());
+    };
+
+    let (pos, spawn, vitals) = {
+        let mut p_data = p_data.try_lock()?;
+
+        p_data.combat.death_type = DeathType::Alive;
+
+        //lets heal them fully on revival.
+        for i in 0..VITALS_MAX {
+            p_data.combat.vitals.vital[i] = p_data.combat.vitals.vitalmax[i];
+        }
+
+        (
+            p_data.movement.pos,
+            p_data.movement.spawn,
+            p_data.combat.vitals,
+        )
+    };
+
+    player_warp(world, storage, entity, &spawn.pos, false)?;
+
+    DataTaskToken::Death(pos.map).add_task(storage, death_packet(entity, DeathType::Alive)?)?;
+    DataTaskToken::Vitals(spawn.pos.map).add_task(storage, {
+        vitals_packet(entity, vitals.vital, vitals.vitalmax)?
+    })?;
+    update_player(storage, world, entity)
+}
@@ -0,0 +1,701 @@
+use crate::{
+    containers::{
+        Entity, GUILD_LEADER_RANK, GlobalKey, Guild, GuildMembership, GuildPermissions, Storage,
+        World, default_guild_ranks,
+    },
+    gametypes::*,
+    players::is_name_acceptable,
+    socket::*,
+    sql::*,
+    tasks::{DataTaskToken, player_spawn_packet},
+};
+use chrono::Duration;
+use uuid::Uuid;
+
+fn guild_message(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    msg: String,
+) -> Result<()> {
+    send_message(
+        world,
+        storage,
+        entity,
+        msg,
+        String::new(),
+        MessageChannel::Private,
+        None,
+    )
+}
+
+// Returns the player's name, character id and guild membership.
+fn guild_player(
+    world: &mut World,
+    entity: GlobalKey,
+) -> Result<Option<(String, Uuid, Option<GuildMembership>)>> {
+    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        let p_data = p_data.try_lock()?;
+
+        Ok(Some((
+            p_data.account.username.clone(),
+            p_data.account.character,
+            p_data.guild.membership.clone(),
+        )))
+    } else {
+        Ok(None)
+    }
+}
+
+// Whether the player still has the membership they had when a lookup was started.
+fn same_membership(
+    world: &mut World,
+    entity: GlobalKey,
+    membership: &GuildMembership,
+) -> Result<bool> {
+    Ok(matches!(
+        guild_player(world, entity)?,
+        Some((_, _, Some(current))) if current == *membership
+    ))
+}
+
+// The member's entity if they are online on the character that is in the guild.
+fn online_member(world: &mut World, member: &PGGuildMember) -> Result<Option<GlobalKey>> {
+    let Some(entity) = world.get_account_id(&member.uid) else {
+        return Ok(None);
+    };
+
+    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity)
+        && p_data.try_lock()?.account.character == member.cid
+    {
+        return Ok(Some(entity));
+    }
+
+    Ok(None)
+}
+
+fn has_guild_permission(
+    storage: &Storage,
+    membership: &GuildMembership,
+    permission: GuildPermissions,
+) -> bool {
+    storage
+        .guilds
+        .borrow()
+        .get(&membership.id)
+        .is_some_and(|guild| guild.has_permission(membership.rank, permission))
+}
+
+fn set_membership(
+    world: &mut World,
+    entity: GlobalKey,
+    membership: Option<GuildMembership>,
+) -> Result<()> {
+    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        p_data.try_lock()?.guild.membership = membership;
+    }
+
+    Ok(())
+}
+
+// Respawns the player for everyone on the map so their guild tag is redrawn.
+fn refresh_guild_tag(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let pos = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        p_data.try_lock()?.movement.pos
+    } else {
+        return Ok(());
+    };
+
+    DataTaskToken::PlayerSpawn(pos.map)
+        .add_task(storage, player_spawn_packet(world, entity, false)?)
+}
+
+// Removes a member who is online from their guild.
+fn clear_membership(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    set_membership(world, entity, None)?;
+    send_guildleave(world, storage, entity)?;
+    refresh_guild_tag(world, storage, entity)
+}
+
+pub fn guild_create(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    name: String,
+    tag: String,
+) -> Result<()> {
+    let Some((_, _, membership)) = guild_player(world, entity)? else {
+        return Ok(());
+    };
+
+    if membership.is_some() {
+        return guild_message(world, storage, entity, "You are already in a guild".into());
+    }
+
+    if !(3..=MAX_GUILD_NAME_LENGTH).contains(&name.len()) || !name.chars().all(is_name_acceptable) {
+        return guild_message(
+            world,
+            storage,
+            entity,
+            format!("Guild names must be 3 to {MAX_GUILD_NAME_LENGTH} letters or numbers"),
+        );
+    }
+
+    if !(2..=MAX_GUILD_TAG_LENGTH).contains(&tag.len())
+        || !tag.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return guild_message(
+            world,
+            storage,
+            entity,
+            format!("Guild tags must be 2 to {MAX_GUILD_TAG_LENGTH} letters or numbers"),
+        );
+    }
+
+    storage.lookups.lookup(move |pool| async move {
+        let exists = sql_guild_exists(&pool, &name, &tag).await;
+
+        Box::new(move |world: &mut World, storage: &Storage| {
+            found_guild(world, storage, entity, name, tag, exists?)
+        }) as LookupReply
+    });
+
+    Ok(())
+}
+
+// Finishes guild_create once the database has been checked for the name and tag.
+fn found_guild(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    name: String,
+    tag: String,
+    exists: bool,
+) -> Result<()> {
+    // They may have joined a guild while the lookup ran.
+    let Some((_, character_id, None)) = guild_player(world, entity)? else {
+        return Ok(());
+    };
+
+    // Guilds made since the lookup started are not saved yet but are in the cache.
+    let (lower_name, lower_tag) = (name.to_lowercase(), tag.to_lowercase());
+    let cached = storage.guilds.borrow().values().any(|guild| {
+        guild.name.to_lowercase() == lower_name || guild.tag.to_lowercase() == lower_tag
+    });
+
+    if exists || cached {
+        return guild_message(
+            world,
+            storage,
+            entity,
+            "That guild name or tag is already taken".into(),
+        );
+    }
+
+    let guild = Guild {
+        id: Uuid::now_v7(),
+        name: name.clone(),
+        tag,
+        motd: String::new(),
+        ranks: default_guild_ranks(),
+    };
+
+    storage.saves.save(SaveJob::NewGuild {
+        uid: guild.id,
+        name: guild.name.clone(),
+        tag: guild.tag.clone(),
+    })?;
+    storage.saves.save(SaveJob::GuildMember {
+        uid: character_id,
+        guild: Some((guild.id, GUILD_LEADER_RANK)),
+    })?;
+
+    set_membership(
+        world,
+        entity,
+        Some(GuildMembership {
+            id: guild.id,
+            tag: guild.tag.clone(),
+            rank: GUILD_LEADER_RANK,
+        }),
+    )?;
+
+    storage.guilds.borrow_mut().insert(guild.id, guild);
+
+    send_guilddata(world, storage, entity)?;
+    refresh_guild_tag(world, storage, entity)?;
+    guild_message(world, storage, entity, format!("You have founded {name}"))
+}
+
+pub fn guild_invite(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    target: GlobalKey,
+) -> Result<()> {
+    let Some((name, _, Some(membership))) = guild_player(world, entity)? else {
+        return Ok(());
+    };
+
+    if !has_guild_permission(storage, &membership, GuildPermissions::INVITE) {
+        return guild_message(
+            world,
+            storage,
+            entity,
+            "You are not allowed to invite players".into(),
+        );
+    }
+
+    let Some(Entity::Player(p2_data)) = world.get_opt_entity(target) else {
+        return guild_message(world, storage, entity, "Could not find player".into());
+    };
+
+    let tick = *storage.gettick.borrow();
+
+    {
+        let mut p2_data = p2_data.try_lock()?;
+
+        if p2_data.guild.membership.is_some() {
+            drop(p2_data);
+            return guild_message(
+                world,
+                storage,
+                entity,
+                "Player is already in a guild".into(),
+            );
+        }
+
+        p2_data.guild.invite = Some(membership.id);
+        p2_data.guild.invite_timer = tick + Duration::try_milliseconds(60000).unwrap_or_default();
+        // 1 Minute
+    }
+
+    let guild_name = storage
+        .guilds
+        .borrow()
+        .get(&membership.id)
+        .map(|guild| guild.name.clone())
+        .unwrap_or_default();
+
+    send_guildinvite(world, storage, target, name, guild_name)?;
+    guild_message(world, storage, entity, "Guild invite sent".into())
+}
+
+pub fn guild_accept(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) else {
+        return Ok(());
+    };
+
+    let (invite, name, character_id) = {
+        let mut p_data = p_data.try_lock()?;
+        let invite = p_data.guild.invite.take();
+
+        if p_data.guild.membership.is_some()
+            || p_data.guild.invite_timer < *storage.gettick.borrow()
+        {
+            return Ok(());
+        }
+
+        (
+            invite,
+            p_data.account.username.clone(),
+            p_data.account.character,
+        )
+    };
+
+    let Some(guild_id) = invite else {
+        return Ok(());
+    };
+
+    // Invites only come from members so the guild is cached unless it was disbanded.
+    let Some(guild) = storage.guilds.borrow().get(&guild_id).cloned() else {
+        return guild_message(world, storage, entity, "That guild no longer exists".into());
+    };
+
+    let rank = guild.lowest_rank();
+
+    storage.saves.save(SaveJob::GuildMember {
+        uid: character_id,
+        guild: Some((guild.id, rank)),
+    })?;
+    set_membership(
+        world,
+        entity,
+        Some(GuildMembership {
+            id: guild.id,
+            tag: guild.tag,
+            rank,
+        }),
+    )?;
+
+    send_guilddata(world, storage, entity)?;
+    refresh_guild_tag(world, storage, entity)?;
+    send_guildnotice(
+        world,
+        storage,
+        guild.id,
+        format!("{name} has joined the guild"),
+    )
+}
+
+pub fn guild_decline(world: &mut World, _storage: &Storage, entity: GlobalKey) -> Result<()> {
+    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        p_data.try_lock()?.guild.invite = None;
+    }
+
+    Ok(())
+}
+
+pub fn guild_leave(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let Some((name, character_id, Some(membership))) = guild_player(world, entity)? else {
+        return Ok(());
+    };
+
+    if membership.rank == GUILD_LEADER_RANK {
+        let guild_id = membership.id;
+
+        storage.lookups.lookup(move |pool| async move {
+            let count = sql_count_guild_members(&pool, guild_id).await;
+
+            Box::new(move |world: &mut World, storage: &Storage| {
+                if !same_membership(world, entity, &membership)? {
+                    return Ok(());
+                }
+
+                if count? > 1 {
+                    return guild_message(
+                        world,
+                        storage,
+                        entity,
+                        "Hand leadership to another member or disband the guild before leaving"
+                            .into(),
+                    );
+                }
+
+                guild_disband(world, storage, entity)
+            }) as LookupReply
+        });
+
+        return Ok(());
+    }
+
+    storage.saves.save(SaveJob::GuildMember {
+        uid: character_id,
+        guild: None,
+    })?;
+    clear_membership(world, storage, entity)?;
+    guild_message(world, storage, entity, "You have left the guild".into())?;
+    send_guildnotice(
+        world,
+        storage,
+        membership.id,
+        format!("{name} has left the guild"),
+    )
+}
+
+pub fn guild_kick(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    name: String,
+) -> Result<()> {
+    let Some((_, _, Some(membership))) = guild_player(world, entity)? else {
+        return Ok(());
+    };
+
+    if !has_guild_permission(storage, &membership, GuildPermissions::KICK) {
+        return guild_message(
+            world,
+            storage,
+            entity,
+            "You are not allowed to remove members".into(),
+        );
+    }
+
+    let guild_id = membership.id;
+
+    storage.lookups.lookup(move |pool| async move {
+        let target = sql_find_guild_member(&pool, guild_id, &name).await;
+
+        Box::new(move |world: &mut World, storage: &Storage| {
+            kick_member(world, storage, entity, membership, name, target?)
+        }) as LookupReply
+    });
+
+    Ok(())
+}
+
+// Finishes guild_kick once the member has been looked up.
+fn kick_member(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    membership: GuildMembership,
+    name: String,
+    target: Option<PGGuildMember>,
+) -> Result<()> {
+    if !same_membership(world, entity, &membership)? {
+        return Ok(());
+    }
+
+    let Some(target) = target else {
+        return guild_message(
+            world,
+            storage,
+            entity,
+            format!("{name} is not in your guild"),
+        );
+    };
+
+    if target.rank <= membership.rank {
+        return guild_message(world, storage, entity, format!("You can not remove {name}"));
+    }
+
+    storage.saves.save(SaveJob::GuildMember {
+        uid: target.cid,
+        guild: None,
+    })?;
+
+    if let Some(target_entity) = online_member(world, &target)? {
+        clear_membership(world, storage, target_entity)?;
+        guild_message(
+            world,
+            storage,
+            target_entity,
+            "You have been removed from the guild".into(),
+        )?;
+    }
+
+    send_guildnotice(
+        world,
+        storage,
+        membership.id,
+        format!("{name} has been removed from the guild"),
+    )
+}
+
+/// Changes a member's rank. The leader can hand over leadership by giving someone
+/// the leader rank, which moves them down to the next rank.
+pub fn guild_set_rank(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    name: String,
+    rank: i16,
+) -> Result<()> {
+    let Some((_, _, Some(membership))) = guild_player(world, entity)? else {
+        return Ok(());
+    };
+
+    if !has_guild_permission(storage, &membership, GuildPermissions::SET_RANK) {
+        return guild_message(
+            world,
+            storage,
+            entity,
+            "You are not allowed to change ranks".into(),
+        );
+    }
+
+    let rank_exists = storage
+        .guilds
+        .borrow()
+        .get(&membership.id)
+        .is_some_and(|guild| guild.get_rank(rank).is_some());
+
+    if !rank_exists {
+        return guild_message(world, storage, entity, "That rank does not exist".into());
+    }
+
+    let guild_id = membership.id;
+
+    storage.lookups.lookup(move |pool| async move {
+        let target = sql_find_guild_member(&pool, guild_id, &name).await;
+
+        Box::new(move |world: &mut World, storage: &Storage| {
+            set_member_rank(world, storage, entity, membership, name, rank, target?)
+        }) as LookupReply
+    });
+
+    Ok(())
+}
+
+// Finishes guild_set_rank once the member has been looked up.
+fn set_member_rank(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    membership: GuildMembership,
+    name: String,
+    rank: i16,
+    target: Option<PGGuildMember>,
+) -> Result<()> {
+    let Some((_, character_id, Some(current))) = guild_player(world, entity)? else {
+        return Ok(());
+    };
+
+    if current != membership {
+        return Ok(());
+    }
+
+    let Some(guild) = storage.guilds.borrow().get(&membership.id).cloned() else {
+        return Ok(());
+    };
+
+    let Some(rank_data) = guild.get_rank(rank) else {
+        return Ok(());
+    };
+
+    let Some(target) = target else {
+        return guild_message(
+            world,
+            storage,
+            entity,
+            format!("{name} is not in your guild"),
+        );
+    };
+
+    let transfer = membership.rank == GUILD_LEADER_RANK && rank == GUILD_LEADER_RANK;
+
+    if target.cid == character_id
+        || (!transfer && (target.rank <= membership.rank || rank <= membership.rank))
+    {
+        return guild_message(
+            world,
+            storage,
+            entity,
+            format!("You can not change the rank of {name}"),
+        );
+    }
+
+    storage.saves.save(SaveJob::GuildMember {
+        uid: target.cid,
+        guild: Some((guild.id, rank)),
+    })?;
+
+    if let Some(target_entity) = online_member(world, &target)? {
+        if let Some(Entity::Player(p_data)) = world.get_opt_entity(target_entity) {
+            let mut p_data = p_data.try_lock()?;
+
+            if let Some(target_membership) = p_data.guild.membership.as_mut() {
+                target_membership.rank = rank;
+            }
+        }
+
+        send_guilddata(world, storage, target_entity)?;
+    }
+
+    if transfer {
+        let next_rank = guild
+            .ranks
+            .iter()
+            .map(|data| data.rank)
+            .filter(|rank| *rank > GUILD_LEADER_RANK)
+            .min()
+            .unwrap_or(guild.lowest_rank());
+
+        storage.saves.save(SaveJob::GuildMember {
+            uid: character_id,
+            guild: Some((guild.id, next_rank)),
+        })?;
+        set_membership(
+            world,
+            entity,
+            Some(GuildMembership {
+                rank: next_rank,
+                ..membership.clone()
+            }),
+        )?;
+        send_guilddata(world, storage, entity)?;
+    }
+
+    send_guildnotice(
+        world,
+        storage,
+        membership.id,
+        format!("{name} is now {}", rank_data.name),
+    )
+}
+
+pub fn guild_set_motd(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    motd: String,
+) -> Result<()> {
+    let Some((_, _, Some(membership))) = guild_player(world, entity)? else {
+        return Ok(());
+    };
+
+    if !has_guild_permission(storage, &membership, GuildPermissions::SET_MOTD) {
+        return guild_message(
+            world,
+            storage,
+            entity,
+            "You are not allowed to change the message of the day".into(),
+        );
+    }
+
+    if motd.len() > MAX_GUILD_MOTD_LENGTH {
+        return guild_message(
+            world,
+            storage,
+            entity,
+            format!("The message of the day can only be {MAX_GUILD_MOTD_LENGTH} characters"),
+        );
+    }
+
+    storage.saves.save(SaveJob::GuildMotd {
+        uid: membership.id,
+        motd: motd.clone(),
+    })?;
+
+    if let Some(guild) = storage.guilds.borrow_mut().get_mut(&membership.id) {
+        guild.motd.clone_from(&motd);
+    }
+
+    send_guildmotd(world, storage, membership.id, motd)
+}
+
+pub fn guild_disband(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let Some((_, _, Some(membership))) = guild_player(world, entity)? else {
+        return Ok(());
+    };
+
+    if membership.rank != GUILD_LEADER_RANK {
+        return guild_message(
+            world,
+            storage,
+            entity,
+            "Only the guild leader can disband the guild".into(),
+        );
+    }
+
+    send_guildnotice(
+        world,
+        storage,
+        membership.id,
+        "The guild has been disbanded".into(),
+    )?;
+
+    storage
+        .saves
+        .save(SaveJob::DeleteGuild { uid: membership.id })?;
+
+    let players: Vec<GlobalKey> = storage.player_ids.borrow().iter().copied().collect();
+
+    for player in players {
+        let in_guild = if let Some(Entity::Player(p_data)) = world.get_opt_entity(player) {
+            p_data.try_lock()?.guild.guild_id() == Some(membership.id)
+        } else {
+            false
+        };
+
+        if in_guild {
+            clear_membership(world, storage, player)?;
+        }
+    }
+
+    storage.guilds.borrow_mut().remove(&membership.id);
+
+    Ok(())
+}
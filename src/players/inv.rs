@@ -239,7 +239,8 @@ pub fn give_inv_item(
 ) -> Result<()> {
     let base = &storage.bases.items[item.num as usize];
 
-    auto_set_inv_item(world, storage, entity, item, base)
+    auto_set_inv_item(world, storage, entity, item, base)?;
+    quest_items_changed(world, storage, entity)
 }
 
 pub fn check_inv_space(
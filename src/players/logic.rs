@@ -4,6 +4,7 @@ use crate::{
         TradeMoney, TradeRequestEntity, TradeStatus, World,
     },
     gametypes::*,
+    items::Item,
     maps::can_target,
     npcs::npc_clear_move_path,
     players::*,
@@ -20,41 +21,25 @@ pub fn update_players(world: &mut World, storage: &Storage) -> Result<()> {
 
     for id in &*storage.player_ids.borrow() {
         if let Some(Entity::Player(p_data)) = world.get_opt_entity(*id) {
-            let (spawn, onlinetype, deathtype, deathtimer, pos, is_using_type) = {
+            let (onlinetype, deathtype, deathtimer, is_using_type) = {
                 let p_data = p_data.try_lock()?;
 
                 (
-                    p_data.movement.spawn,
                     p_data.online_type,
                     p_data.combat.death_type,
                     p_data.combat.death_timer,
-                    p_data.movement.pos,
                     p_data.is_using_type,
                 )
             };
 
             if onlinetype == OnlineType::Online {
-                if deathtype == DeathType::Spirit {
-                    //timers
-                    if deathtimer.0 < tick {
-                        {
-                            let mut p_data = p_data.try_lock()?;
-
-                            p_data.combat.death_type = DeathType::Alive;
-
-                            //lets heal them fully on revival.
-                            for i in 0..VITALS_MAX {
-                                p_data.combat.vitals.vital[i] = p_data.combat.vitals.vitalmax[i];
-                            }
-                        }
-
-                        player_warp(world, storage, *id, &spawn.pos, false)?;
-
-                        DataTaskToken::Death(pos.map)
-                            .add_task(storage, death_packet(*id, DeathType::Alive)?)?;
-                    }
+                //timers
+                if deathtype == DeathType::Spirit && deathtimer.0 < tick {
+                    revive_player(world, storage, *id)?;
                 }
 
+                update_pk(world, storage, *id)?;
+
                 // Movement
                 process_player_movement(world, storage, *id)?;
 
@@ -141,6 +126,7 @@ pub fn send_connection_pings(world: &mut World, storage: &Storage) -> Result<()>
     Ok(())
 }
 
+/// Gives experience for a kill, splitting it evenly with party members in range.
 pub fn player_earn_exp(
     world: &mut World,
     storage: &Storage,
@@ -148,27 +134,55 @@ pub fn player_earn_exp(
     victimlevel: i32,
     expval: i64,
     spercent: f64,
+) -> Result<()> {
+    if expval == 0 {
+        return Ok(());
+    }
+
+    let members = party_exp_members(world, storage, entity)?;
+
+    if members.len() <= 1 {
+        return player_gain_exp(world, storage, entity, victimlevel, expval, spercent);
+    }
+
+    let share = cmp::max(expval / members.len() as i64, 1);
+
+    for member in members {
+        player_gain_exp(world, storage, member, victimlevel, share, spercent)?;
+    }
+
+    Ok(())
+}
+
+fn player_gain_exp(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    victimlevel: i32,
+    expval: i64,
+    spercent: f64,
 ) -> Result<()> {
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
         let mut giveexp = expval;
 
         giveexp = (giveexp as f64 * spercent) as i64;
 
-        let (mut cur_level, socket_id, position) = {
+        let level = {
             let mut p_data = p_data.try_lock()?;
 
-            if p_data.combat.level >= MAX_LVL as i32 || expval == 0 {
+            if p_data.combat.level >= storage.bases.progression.max_level || expval == 0 {
                 return Ok(());
             }
 
             p_data.combat.in_combat = true;
-            p_data.combat.combat_timer.0 =
-                *storage.gettick.borrow() + Duration::try_milliseconds(2000).unwrap_or_default();
+            p_data.combat.combat_timer.0 = p_data.combat.combat_timer.0.max(
+                *storage.gettick.borrow() + Duration::try_milliseconds(2000).unwrap_or_default(),
+            );
 
-            (p_data.combat.level, p_data.socket.id, p_data.movement.pos)
+            p_data.combat.level
         };
 
-        let leveldifference = victimlevel - cur_level;
+        let leveldifference = victimlevel - level;
 
         if (1..=5).contains(&leveldifference) {
             giveexp = (giveexp as f64 * 1.1) as i64;
@@ -176,6 +190,45 @@ pub fn player_earn_exp(
             giveexp += (giveexp as f64 * (leveldifference as f64 * 0.1)) as i64;
         }
 
+        player_add_exp(world, storage, entity, giveexp)?;
+    }
+    Ok(())
+}
+
+/// Gives exp earned outside of combat, like quest rewards, in full to the player alone.
+pub fn player_reward_exp(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    expval: i64,
+) -> Result<()> {
+    if expval == 0 {
+        return Ok(());
+    }
+
+    player_add_exp(world, storage, entity, expval)
+}
+
+fn player_add_exp(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    giveexp: i64,
+) -> Result<()> {
+    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        let max_level = storage.bases.progression.max_level;
+
+        let (mut cur_level, socket_id, position) = {
+            let p_data = p_data.try_lock()?;
+
+            if p_data.combat.level >= max_level {
+                return Ok(());
+            }
+
+            (p_data.combat.level, p_data.socket.id, p_data.movement.pos)
+        };
+        let start_level = cur_level;
+
         let mut levelexp = {
             let mut p_data = p_data.try_lock()?;
 
@@ -184,29 +237,27 @@ pub fn player_earn_exp(
             p_data.general.levelexp
         };
 
-        while levelexp >= player_get_next_lvl_exp(world, entity)? && cur_level != MAX_LVL as i32 {
+        while levelexp >= player_get_next_lvl_exp(world, storage, entity)? && cur_level < max_level
+        {
             {
                 let mut p_data = p_data.try_lock()?;
+                let next_level_exp = storage
+                    .bases
+                    .progression
+                    .next_level_exp(p_data.combat.level);
 
                 p_data.combat.level += 1;
-                p_data.general.levelexp = p_data
-                    .general
-                    .levelexp
-                    .saturating_sub(player_get_next_lvl_exp(world, entity)?);
+                p_data.general.levelexp = p_data.general.levelexp.saturating_sub(next_level_exp);
+                p_data.attributes.points = p_data
+                    .attributes
+                    .points
+                    .saturating_add(storage.bases.progression.attributes.points_per_level);
 
                 cur_level = p_data.combat.level;
                 levelexp = p_data.general.levelexp;
             }
 
-            let maxhp = player_calc_max_hp(world, entity)?;
-            let maxmp = player_calc_max_mp(world, entity)?;
-
-            {
-                let mut p_data = p_data.try_lock()?;
-
-                p_data.combat.vitals.vitalmax[VitalTypes::Hp as usize] = maxhp;
-                p_data.combat.vitals.vitalmax[VitalTypes::Mp as usize] = maxmp;
-            }
+            player_update_stats(world, storage, entity)?;
 
             send_message(
                 world,
@@ -227,6 +278,11 @@ pub fn player_earn_exp(
 
         let vitals = { p_data.try_lock()?.combat.vitals };
 
+        if cur_level > start_level {
+            send_attributes(world, storage, entity)?;
+            update_attributes(storage, world, entity)?;
+        }
+
         send_level(world, storage, entity)?;
         DataTaskToken::Vitals(position.map).add_task(
             storage,
@@ -237,47 +293,32 @@ pub fn player_earn_exp(
     Ok(())
 }
 
-pub fn player_get_next_lvl_exp(world: &mut World, entity: GlobalKey) -> Result<u64> {
+pub fn player_get_next_lvl_exp(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+) -> Result<u64> {
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
         let level = p_data.try_lock()?.combat.level;
 
-        let exp_per_level = match level {
-            1..=10 => 100,
-            11..=20 => 250,
-            21..=30 => 400,
-            31..=40 => 550,
-            41..=50 => 700,
-            51..=60 => 850,
-            61..=70 => 1000,
-            71..=80 => 1150,
-            81..=90 => 1300,
-            91..=100 => 1450,
-            101..=120 => 2000,
-            121..=150 => 3000,
-            151..=199 => 4000,
-            _ => 0,
-        };
-
-        Ok(level as u64 * exp_per_level as u64)
+        Ok(storage.bases.progression.next_level_exp(level))
     } else {
         Ok(0)
     }
 }
 
-pub fn player_calc_max_hp(world: &mut World, entity: GlobalKey) -> Result<i32> {
+/// Sets the player's max vitals, regen, damage and defense from their level and attributes.
+pub fn player_update_stats(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
-        Ok(p_data.try_lock()?.combat.level * 25)
-    } else {
-        Ok(0)
-    }
-}
+        let p_data = &mut *p_data.try_lock()?;
 
-pub fn player_calc_max_mp(world: &mut World, entity: GlobalKey) -> Result<i32> {
-    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
-        Ok(p_data.try_lock()?.combat.level * 25)
-    } else {
-        Ok(0)
+        storage
+            .bases
+            .progression
+            .apply(&mut p_data.combat, &p_data.attributes);
     }
+
+    Ok(())
 }
 
 pub fn player_get_weapon_damage(
@@ -430,6 +471,11 @@ pub fn joingame(world: &mut World, storage: &Storage, entity: GlobalKey) -> Resu
         send_inv(world, storage, entity)?;
         send_level(world, storage, entity)?;
         send_money(world, storage, entity)?;
+        send_guilddata(world, storage, entity)?;
+        send_questlist(world, storage, entity)?;
+        send_hotbar(world, storage, entity)?;
+        send_attributes(world, storage, entity)?;
+        send_status_effects(world, storage, entity)?;
 
         DataTaskToken::MapChat(position.map).add_task(
             storage,
@@ -474,6 +520,8 @@ pub fn left_game(world: &mut World, storage: &Storage, entity: GlobalKey) -> Res
             return Ok(());
         }
 
+        player_log(world, storage, entity, LogType::Logout, "Logged out".into())?;
+
         DataTaskToken::MapChat(position.map).add_task(
             storage,
             message_packet(
@@ -676,11 +724,66 @@ pub fn process_player_trade(
         }
         player_give_vals(world, storage, entity, target_money)?;
 
+        log_trade(
+            world,
+            storage,
+            entity,
+            target_entity,
+            &entity_item.items,
+            entity_money,
+        )?;
+        log_trade(
+            world,
+            storage,
+            target_entity,
+            entity,
+            &target_item.items,
+            target_money,
+        )?;
+
         return Ok(true);
     }
     Ok(false)
 }
 
+/// Logs what one side of a finished trade handed over.
+fn log_trade(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    target_entity: GlobalKey,
+    items: &[Item],
+    money: u64,
+) -> Result<()> {
+    let target_name = if let Some(Entity::Player(p_data)) = world.get_opt_entity(target_entity) {
+        p_data.try_lock()?.account.username.clone()
+    } else {
+        return Ok(());
+    };
+
+    let mut given: Vec<String> = items
+        .iter()
+        .filter(|item| item.val > 0)
+        .map(|item| item_log_text(storage, item.num, item.val as u64))
+        .collect();
+
+    if money > 0 {
+        given.push(format!("{money} vals"));
+    }
+
+    if given.is_empty() {
+        return Ok(());
+    }
+
+    player_log(
+        world,
+        storage,
+        entity,
+        LogType::Item,
+        format!("Traded {} to {target_name}", given.join(", ")),
+    )
+}
+
 pub fn close_trade(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
         let mut p_data = p_data.try_lock()?;
@@ -703,3 +806,33 @@ pub fn can_trade(world: &mut World, storage: &Storage, entity: GlobalKey) -> Res
         Ok(false)
     }
 }
+
+/// Tells the player why they are being removed, saves them and closes their sockets.
+pub fn kick_player(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    reason: String,
+) -> Result<()> {
+    let sockets = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        let p_data = p_data.try_lock()?;
+
+        (p_data.socket.id, p_data.socket.tls_id)
+    } else {
+        return Ok(());
+    };
+
+    if sockets.0 != usize::MAX {
+        send_infomsg(storage, sockets.0, reason, 1)?;
+    }
+
+    disconnect(entity, world, storage)?;
+
+    for socket in [sockets.0, sockets.1] {
+        if socket != usize::MAX {
+            set_client_as_closed(storage, socket);
+        }
+    }
+
+    Ok(())
+}
@@ -211,8 +211,11 @@ pub fn process_player_movement(
             }
 
             {
+                //slow effects add their power as a percent onto the movement delay.
+                let slow = p_data.combat.effects.power(StatusKind::Slow).max(0) as i64;
+
                 p_data.movement.move_timer.0 =
-                    tick + Duration::try_milliseconds(200).unwrap_or_default();
+                    tick + Duration::try_milliseconds(200 + 200 * slow / 100).unwrap_or_default();
             }
 
             (
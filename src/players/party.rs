@@ -0,0 +1,430 @@
+use crate::{
+    containers::{Entity, GlobalKey, Party, PartyKey, Storage, World},
+    gametypes::*,
+    socket::*,
+};
+use chrono::Duration;
+
+fn party_message(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    msg: String,
+) -> Result<()> {
+    send_message(
+        world,
+        storage,
+        entity,
+        msg,
+        String::new(),
+        MessageChannel::Private,
+        None,
+    )
+}
+
+fn player_party(world: &mut World, entity: GlobalKey) -> Result<Option<PartyKey>> {
+    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        Ok(p_data.try_lock()?.party.party)
+    } else {
+        Ok(None)
+    }
+}
+
+fn player_name(world: &mut World, entity: GlobalKey) -> Result<String> {
+    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        Ok(p_data.try_lock()?.account.username.clone())
+    } else {
+        Ok(String::new())
+    }
+}
+
+fn party_members(storage: &Storage, party: PartyKey) -> Vec<GlobalKey> {
+    storage
+        .parties
+        .borrow()
+        .get(party)
+        .map(|party| party.member_list())
+        .unwrap_or_default()
+}
+
+fn is_party_leader(storage: &Storage, party: PartyKey, entity: GlobalKey) -> bool {
+    storage
+        .parties
+        .borrow()
+        .get(party)
+        .is_some_and(|party| party.leader == entity)
+}
+
+pub fn party_invite(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    target: GlobalKey,
+) -> Result<()> {
+    if target == entity {
+        return Ok(());
+    }
+
+    let Some(Entity::Player(p2_data)) = world.get_opt_entity(target) else {
+        return party_message(world, storage, entity, "Could not find player".into());
+    };
+
+    if let Some(party) = player_party(world, entity)? {
+        if !is_party_leader(storage, party, entity) {
+            return party_message(
+                world,
+                storage,
+                entity,
+                "Only the party leader can invite players".into(),
+            );
+        }
+
+        if storage
+            .parties
+            .borrow()
+            .get(party)
+            .is_some_and(|party| party.is_full())
+        {
+            return party_message(world, storage, entity, "Your party is full".into());
+        }
+    }
+
+    let tick = *storage.gettick.borrow();
+
+    {
+        let mut p2_data = p2_data.try_lock()?;
+
+        if p2_data.party.party.is_some() {
+            drop(p2_data);
+            return party_message(
+                world,
+                storage,
+                entity,
+                "Player is already in a party".into(),
+            );
+        }
+
+        p2_data.party.invite = Some(entity);
+        p2_data.party.invite_timer = tick + Duration::try_milliseconds(60000).unwrap_or_default();
+        // 1 Minute
+    }
+
+    let name = player_name(world, entity)?;
+
+    send_partyinvite(world, storage, target, entity, name)?;
+    party_message(world, storage, entity, "Party invite sent".into())
+}
+
+pub fn party_accept(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) else {
+        return Ok(());
+    };
+
+    let inviter = {
+        let mut p_data = p_data.try_lock()?;
+        let inviter = p_data.party.invite.take();
+
+        if p_data.party.party.is_some() || p_data.party.invite_timer < *storage.gettick.borrow() {
+            return Ok(());
+        }
+
+        inviter
+    };
+
+    let Some(inviter) = inviter else {
+        return Ok(());
+    };
+
+    let Some(Entity::Player(p2_data)) = world.get_opt_entity(inviter) else {
+        return party_message(world, storage, entity, "That party no longer exists".into());
+    };
+
+    let party = {
+        let mut p2_data = p2_data.try_lock()?;
+        let mut parties = storage.parties.borrow_mut();
+
+        let party = match p2_data.party.party {
+            Some(party) => party,
+            None => {
+                let party = parties.insert(Party::new(inviter));
+
+                p2_data.party.party = Some(party);
+                party
+            }
+        };
+
+        let joined = match parties.get_mut(party) {
+            Some(data) if !data.is_full() => {
+                data.members.insert(entity, None);
+                true
+            }
+            _ => false,
+        };
+
+        if !joined {
+            drop(parties);
+            drop(p2_data);
+            return party_message(world, storage, entity, "That party is full".into());
+        }
+
+        party
+    };
+
+    p_data.try_lock()?.party.party = Some(party);
+
+    let name = player_name(world, entity)?;
+
+    send_partydata(world, storage, party)?;
+
+    let members = party_members(storage, party);
+
+    for member in members {
+        party_message(
+            world,
+            storage,
+            member,
+            format!("{name} has joined the party"),
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn party_decline(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let inviter = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        p_data.try_lock()?.party.invite.take()
+    } else {
+        return Ok(());
+    };
+
+    if let Some(inviter) = inviter {
+        let name = player_name(world, entity)?;
+
+        party_message(
+            world,
+            storage,
+            inviter,
+            format!("{name} has declined your party invite"),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Removes the player from their party. A party left with a single member is disbanded
+/// and the leader is handed to the longest standing member if the leader leaves.
+pub fn party_leave(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let party = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        p_data.try_lock()?.party.party.take()
+    } else {
+        return Ok(());
+    };
+
+    let Some(party) = party else {
+        return Ok(());
+    };
+
+    let (disbanded, members) = {
+        let mut parties = storage.parties.borrow_mut();
+
+        let members = match parties.get_mut(party) {
+            Some(data) => {
+                data.members.shift_remove(&entity);
+
+                if data.leader == entity
+                    && let Some(leader) = data.members.keys().next()
+                {
+                    data.leader = *leader;
+                }
+
+                data.member_list()
+            }
+            None => Vec::new(),
+        };
+
+        let disbanded = members.len() <= 1;
+
+        if disbanded {
+            parties.remove(party);
+        }
+
+        (disbanded, members)
+    };
+
+    send_partyleave(world, storage, entity)?;
+
+    if disbanded {
+        for member in members {
+            if let Some(Entity::Player(p_data)) = world.get_opt_entity(member) {
+                p_data.try_lock()?.party.party = None;
+            }
+
+            send_partyleave(world, storage, member)?;
+            party_message(
+                world,
+                storage,
+                member,
+                "Your party has been disbanded".into(),
+            )?;
+        }
+    } else {
+        let name = player_name(world, entity)?;
+
+        send_partydata(world, storage, party)?;
+
+        for member in members {
+            party_message(world, storage, member, format!("{name} has left the party"))?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn party_kick(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    target: GlobalKey,
+) -> Result<()> {
+    let Some(party) = player_party(world, entity)? else {
+        return Ok(());
+    };
+
+    if target == entity
+        || !is_party_leader(storage, party, entity)
+        || player_party(world, target)? != Some(party)
+    {
+        return Ok(());
+    }
+
+    party_leave(world, storage, target)?;
+    party_message(
+        world,
+        storage,
+        target,
+        "You have been removed from the party".into(),
+    )
+}
+
+pub fn party_set_leader(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    target: GlobalKey,
+) -> Result<()> {
+    let Some(party) = player_party(world, entity)? else {
+        return Ok(());
+    };
+
+    if target == entity || !is_party_leader(storage, party, entity) {
+        return Ok(());
+    }
+
+    {
+        let mut parties = storage.parties.borrow_mut();
+
+        match parties.get_mut(party) {
+            Some(data) if data.contains(target) => data.leader = target,
+            _ => return Ok(()),
+        }
+    }
+
+    let name = player_name(world, target)?;
+
+    send_partydata(world, storage, party)?;
+
+    let members = party_members(storage, party);
+
+    for member in members {
+        party_message(
+            world,
+            storage,
+            member,
+            format!("{name} is now the party leader"),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Returns the party members who should share experience earned by this player.
+/// Only living members on the same or a nearby map take a share.
+pub fn party_exp_members(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+) -> Result<Vec<GlobalKey>> {
+    let (party, pos) = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        let p_data = p_data.try_lock()?;
+
+        (p_data.party.party, p_data.movement.pos)
+    } else {
+        return Ok(Vec::new());
+    };
+
+    let members = match party {
+        Some(party) => party_members(storage, party),
+        None => return Ok(vec![entity]),
+    };
+
+    let mut sharing = Vec::with_capacity(members.len());
+
+    for member in members {
+        if member == entity {
+            sharing.push(member);
+            continue;
+        }
+
+        if let Some(Entity::Player(p_data)) = world.get_opt_entity(member) {
+            let p_data = p_data.try_lock()?;
+
+            if p_data.combat.death_type.is_alive()
+                && pos.map.checkdistance(p_data.movement.pos.map) <= PARTY_EXP_MAP_RANGE
+            {
+                sharing.push(member);
+            }
+        }
+    }
+
+    Ok(sharing)
+}
+
+/// Sends any vitals that changed since the last update to the rest of the party.
+/// Members on other maps do not get these through the normal map vitals packets.
+pub fn update_parties(world: &mut World, storage: &Storage) -> Result<()> {
+    let parties: Vec<(PartyKey, Vec<GlobalKey>)> = storage
+        .parties
+        .borrow()
+        .iter()
+        .map(|(key, party)| (key, party.member_list()))
+        .collect();
+
+    for (party, members) in parties {
+        for member in members {
+            let vitals = if let Some(Entity::Player(p_data)) = world.get_opt_entity(member) {
+                p_data.try_lock()?.combat.vitals
+            } else {
+                continue;
+            };
+
+            let changed = match storage
+                .parties
+                .borrow_mut()
+                .get_mut(party)
+                .and_then(|data| data.members.get_mut(&member))
+            {
+                Some(last) if *last != Some(vitals) => {
+                    *last = Some(vitals);
+                    true
+                }
+                _ => false,
+            };
+
+            if changed {
+                send_partyvitals(world, storage, party, member, vitals)?;
+            }
+        }
+    }
+
+    Ok(())
+}
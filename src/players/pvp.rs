@@ -0,0 +1,155 @@
+use crate::{
+    containers::{Entity, GlobalKey, Storage, World},
+    gametypes::*,
+    maps::{is_arena, is_safe_zone},
+    socket::*,
+    sql::update_player,
+};
+use chrono::Duration;
+
+/// Whether the attacker may hurt the target player. Nobody can fight in or into a safe
+/// zone and anyone can fight when both stand in an arena. Everywhere else the attacker
+/// needs pvp turned on.
+pub fn can_attack_player(
+    world: &mut World,
+    storage: &Storage,
+    attacker: GlobalKey,
+    target: GlobalKey,
+) -> Result<bool> {
+    let (Some(Entity::Player(p_data)), Some(Entity::Player(p2_data))) =
+        (world.get_opt_entity(attacker), world.get_opt_entity(target))
+    else {
+        return Ok(false);
+    };
+
+    let (pos, pvpon) = {
+        let p_data = p_data.try_lock()?;
+
+        (p_data.movement.pos, p_data.general.pvpon)
+    };
+    let target_pos = { p2_data.try_lock()?.movement.pos };
+
+    if is_safe_zone(storage, pos) || is_safe_zone(storage, target_pos) {
+        return Ok(false);
+    }
+
+    if is_arena(storage, pos) && is_arena(storage, target_pos) {
+        return Ok(true);
+    }
+
+    Ok(pvpon)
+}
+
+/// Flags the attacker as a player killer when they hurt a player outside an arena
+/// who has neither pvp on nor pk status. Further attacks like that restart the timer.
+pub fn player_pvp_attack(
+    world: &mut World,
+    storage: &Storage,
+    attacker: GlobalKey,
+    target: GlobalKey,
+) -> Result<()> {
+    let (Some(Entity::Player(p_data)), Some(Entity::Player(p2_data))) =
+        (world.get_opt_entity(attacker), world.get_opt_entity(target))
+    else {
+        return Ok(());
+    };
+
+    let (innocent, target_pos) = {
+        let p2_data = p2_data.try_lock()?;
+
+        (
+            !p2_data.general.pvpon && !p2_data.general.pk,
+            p2_data.movement.pos,
+        )
+    };
+
+    let newly = {
+        let mut p_data = p_data.try_lock()?;
+
+        if !innocent || (is_arena(storage, p_data.movement.pos) && is_arena(storage, target_pos)) {
+            return Ok(());
+        }
+
+        p_data.pvp_timer.pk_timer = *storage.gettick.borrow()
+            + Duration::try_milliseconds(PK_DURATION_MS).unwrap_or_default();
+
+        !std::mem::replace(&mut p_data.general.pk, true)
+    };
+
+    if newly {
+        send_pk(world, storage, attacker, false)?;
+        update_player(storage, world, attacker)?;
+    }
+
+    Ok(())
+}
+
+/// Turns pvp on or off. Changes are limited by PVP_TOGGLE_COOLDOWN_MS and pvp stays on
+/// while the player is in combat or a player killer.
+pub fn player_set_pvp(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    on: bool,
+) -> Result<()> {
+    let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) else {
+        return Ok(());
+    };
+
+    let tick = *storage.gettick.borrow();
+
+    let (socket_id, error) = {
+        let mut p_data = p_data.try_lock()?;
+
+        if p_data.general.pvpon == on {
+            return Ok(());
+        }
+
+        let error = if p_data.pvp_timer.toggle_timer > tick {
+            Some("You can not change pvp again yet")
+        } else if !on && p_data.general.pk {
+            Some("You can not turn pvp off while you are a player killer")
+        } else if !on && p_data.combat.in_combat {
+            Some("You can not turn pvp off while in combat")
+        } else {
+            p_data.general.pvpon = on;
+            p_data.pvp_timer.toggle_timer =
+                tick + Duration::try_milliseconds(PVP_TOGGLE_COOLDOWN_MS).unwrap_or_default();
+            None
+        };
+
+        (p_data.socket.id, error)
+    };
+
+    if let Some(error) = error {
+        return send_fltalert(storage, socket_id, error.into(), FtlType::Error);
+    }
+
+    send_pvp(world, storage, entity, false)?;
+    send_fltalert(
+        storage,
+        socket_id,
+        if on { "Pvp is on." } else { "Pvp is off." }.into(),
+        FtlType::Message,
+    )
+}
+
+/// Clears pk status once it has worn off. Called from update_players.
+pub fn update_pk(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) else {
+        return Ok(());
+    };
+
+    {
+        let mut p_data = p_data.try_lock()?;
+
+        if !p_data.general.pk || p_data.pvp_timer.pk_timer > *storage.gettick.borrow() {
+            return Ok(());
+        }
+
+        p_data.general.pk = false;
+    }
+
+    send_pk(world, storage, entity, false)?;
+    update_player(storage, world, entity)
+}
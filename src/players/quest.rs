@@ -0,0 +1,470 @@
+use crate::{
+    containers::{Entity, GlobalKey, Storage, World},
+    gametypes::*,
+    items::Item,
+    players::*,
+    quests::{QuestData, QuestObjective},
+    socket::*,
+    sql::{PGQuest, SaveJob},
+};
+
+fn quest_message(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    msg: String,
+) -> Result<()> {
+    send_message(
+        world,
+        storage,
+        entity,
+        msg,
+        String::new(),
+        MessageChannel::Quest,
+        None,
+    )
+}
+
+fn quest_base(storage: &Storage, quest: u16) -> Option<&QuestData> {
+    storage
+        .bases
+        .quests
+        .get(quest as usize)
+        .filter(|base| base.is_valid())
+}
+
+fn active_quests(world: &mut World, entity: GlobalKey) -> Result<Vec<u16>> {
+    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        Ok(p_data.try_lock()?.quests.active.keys().copied().collect())
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+fn save_quest(world: &mut World, storage: &Storage, entity: GlobalKey, quest: u16) -> Result<()> {
+    let (uid, data) = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        let p_data = p_data.try_lock()?;
+        let progress = p_data.quests.active.get(&quest);
+
+        (
+            p_data.account.character,
+            PGQuest {
+                quest: quest as i16,
+                active: progress.is_some(),
+                progress: progress
+                    .map(|progress| progress.iter().map(|val| *val as i16).collect())
+                    .unwrap_or_default(),
+                completed: p_data.quests.times_completed(quest) as i32,
+            },
+        )
+    } else {
+        return Ok(());
+    };
+
+    storage.saves.save(SaveJob::Quest { uid, data })
+}
+
+/// Returns the npc's index if it is alive and close enough for the player to talk to.
+fn npc_in_talk_range(world: &mut World, entity: GlobalKey, npc: GlobalKey) -> Result<Option<u64>> {
+    let pos = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        let p_data = p_data.try_lock()?;
+
+        if !p_data.combat.death_type.is_alive() {
+            return Ok(None);
+        }
+
+        p_data.movement.pos
+    } else {
+        return Ok(None);
+    };
+
+    if let Some(Entity::Npc(n_data)) = world.get_opt_entity(npc) {
+        let n_data = n_data.try_lock()?;
+
+        if n_data.combat.death_type.is_alive()
+            && n_data.movement.pos.map == pos.map
+            && in_range(QUEST_TALK_RANGE, n_data.movement.pos, pos)
+        {
+            return Ok(Some(n_data.index));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Applies the change to every active quest objective it returns a new value for.
+/// Changed quests are saved and sent, then checked for completion.
+fn update_objectives<F>(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    mut update: F,
+) -> Result<()>
+where
+    F: FnMut(&mut World, &QuestObjective, u16) -> Result<Option<u16>>,
+{
+    for quest in active_quests(world, entity)? {
+        let Some(base) = quest_base(storage, quest) else {
+            continue;
+        };
+
+        let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) else {
+            return Ok(());
+        };
+
+        let mut progress = match p_data.try_lock()?.quests.active.get(&quest) {
+            Some(progress) => progress.clone(),
+            None => continue,
+        };
+
+        // The quest data could have been reloaded with a different number of objectives.
+        progress.resize(base.objectives.len(), 0);
+
+        let mut changed = false;
+
+        for (id, objective) in base.objectives.iter().enumerate() {
+            if let Some(val) = update(world, objective, progress[id])? {
+                let val = val.min(objective.required());
+
+                if val != progress[id] {
+                    progress[id] = val;
+                    changed = true;
+                }
+            }
+        }
+
+        if !changed {
+            continue;
+        }
+
+        if let Some(active) = p_data.try_lock()?.quests.active.get_mut(&quest) {
+            *active = progress.clone();
+        }
+
+        save_quest(world, storage, entity, quest)?;
+        send_questprogress(world, storage, entity, quest)?;
+
+        if base.is_done(&progress) {
+            match base.turn_in {
+                Some(npc) => quest_message(
+                    world,
+                    storage,
+                    entity,
+                    format!(
+                        "{} is ready to be handed in to {}",
+                        base.name, storage.bases.npcs[npc as usize].name
+                    ),
+                )?,
+                None => quest_complete(world, storage, entity, quest)?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn quest_accept(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    quest: u16,
+    npc: Option<GlobalKey>,
+) -> Result<()> {
+    let Some(base) = quest_base(storage, quest) else {
+        return Ok(());
+    };
+
+    if let Some(giver) = base.giver {
+        let npc_index = match npc {
+            Some(npc) => npc_in_talk_range(world, entity, npc)?,
+            None => None,
+        };
+
+        if npc_index != Some(giver) {
+            return Ok(());
+        }
+    }
+
+    let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) else {
+        return Ok(());
+    };
+
+    let error = {
+        let p_data = p_data.try_lock()?;
+
+        if p_data.quests.active.contains_key(&quest) {
+            Some("You are already on this quest")
+        } else if !base.repeatable && p_data.quests.times_completed(quest) > 0 {
+            Some("You have already completed this quest")
+        } else if p_data.combat.level < base.levelreq {
+            Some("You are not a high enough level for this quest")
+        } else if base
+            .prerequisite
+            .is_some_and(|prerequisite| p_data.quests.times_completed(prerequisite) == 0)
+        {
+            Some("You have not completed the quest needed to start this one")
+        } else if p_data.quests.active.len() >= MAX_ACTIVE_QUESTS {
+            Some("You can not take on any more quests")
+        } else {
+            None
+        }
+    };
+
+    if let Some(error) = error {
+        return quest_message(world, storage, entity, error.into());
+    }
+
+    p_data
+        .try_lock()?
+        .quests
+        .active
+        .insert(quest, vec![0; base.objectives.len()]);
+
+    save_quest(world, storage, entity, quest)?;
+    send_questprogress(world, storage, entity, quest)?;
+    quest_message(
+        world,
+        storage,
+        entity,
+        format!("Quest accepted: {}", base.name),
+    )?;
+
+    // The player may already be carrying what the quest asks for.
+    quest_items_changed(world, storage, entity)
+}
+
+pub fn quest_abandon(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    quest: u16,
+) -> Result<()> {
+    let removed = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        p_data
+            .try_lock()?
+            .quests
+            .active
+            .shift_remove(&quest)
+            .is_some()
+    } else {
+        return Ok(());
+    };
+
+    if !removed {
+        return Ok(());
+    }
+
+    save_quest(world, storage, entity, quest)?;
+    send_questabandon(world, storage, entity, quest)?;
+
+    if let Some(base) = quest_base(storage, quest) {
+        quest_message(
+            world,
+            storage,
+            entity,
+            format!("Quest abandoned: {}", base.name),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Takes the collected items, pays out the rewards and marks the quest as completed.
+/// Does nothing if the objectives are not done or the rewards do not fit in the inventory.
+pub fn quest_complete(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    quest: u16,
+) -> Result<()> {
+    let Some(base) = quest_base(storage, quest) else {
+        return Ok(());
+    };
+
+    let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) else {
+        return Ok(());
+    };
+
+    let (progress, inventory) = {
+        let p_data = p_data.try_lock()?;
+
+        match p_data.quests.active.get(&quest) {
+            Some(progress) => (progress.clone(), p_data.inventory.items.clone()),
+            None => return Ok(()),
+        }
+    };
+
+    if !base.is_done(&progress) {
+        return Ok(());
+    }
+
+    // Items could have been dropped or traded away since the objective was updated.
+    for objective in &base.objectives {
+        if let QuestObjective::Collect { item, count } = objective
+            && count_inv_item(*item, &inventory) < *count as u64
+        {
+            return quest_message(
+                world,
+                storage,
+                entity,
+                format!("You no longer have everything needed for {}", base.name),
+            );
+        }
+    }
+
+    for reward in &base.items {
+        let mut item = Item {
+            num: reward.item,
+            val: reward.amount,
+            ..Default::default()
+        };
+
+        if !check_inv_space(world, storage, entity, &mut item)? {
+            return quest_message(
+                world,
+                storage,
+                entity,
+                "You do not have enough inventory space for the quest reward".into(),
+            );
+        }
+    }
+
+    {
+        let mut p_data = p_data.try_lock()?;
+
+        p_data.quests.active.shift_remove(&quest);
+        *p_data.quests.completed.entry(quest).or_insert(0) += 1;
+    }
+
+    save_quest(world, storage, entity, quest)?;
+
+    for objective in &base.objectives {
+        if let QuestObjective::Collect { item, count } = objective {
+            take_inv_items(world, storage, entity, *item, *count)?;
+        }
+    }
+
+    send_questcomplete(world, storage, entity, quest)?;
+    quest_message(
+        world,
+        storage,
+        entity,
+        format!("Quest completed: {}", base.name),
+    )?;
+
+    let socket_id = p_data.try_lock()?.socket.id;
+
+    send_fltalert(
+        storage,
+        socket_id,
+        format!("{} Complete!", base.name),
+        FtlType::Quest,
+    )?;
+
+    player_reward_exp(world, storage, entity, base.exp)?;
+
+    if base.vals > 0 {
+        player_give_vals(world, storage, entity, base.vals)?;
+    }
+
+    for reward in &base.items {
+        let mut item = Item {
+            num: reward.item,
+            val: reward.amount,
+            ..Default::default()
+        };
+
+        give_inv_item(world, storage, entity, &mut item)?;
+    }
+
+    Ok(())
+}
+
+/// Advances kill objectives for the player who killed the npc.
+pub fn quest_npc_killed(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    npc_index: u64,
+) -> Result<()> {
+    update_objectives(world, storage, entity, |_, objective, current| {
+        Ok(match objective {
+            QuestObjective::Kill { npc, .. } if *npc == npc_index => {
+                Some(current.saturating_add(1))
+            }
+            _ => None,
+        })
+    })
+}
+
+/// Recounts collect objectives against what is currently in the inventory.
+pub fn quest_items_changed(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let inventory = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        let p_data = p_data.try_lock()?;
+
+        if p_data.quests.active.is_empty() {
+            return Ok(());
+        }
+
+        p_data.inventory.items.clone()
+    } else {
+        return Ok(());
+    };
+
+    update_objectives(world, storage, entity, |_, objective, _| {
+        Ok(match objective {
+            QuestObjective::Collect { item, count } => {
+                Some(count_inv_item(*item, &inventory).min(*count as u64) as u16)
+            }
+            _ => None,
+        })
+    })
+}
+
+/// Handles the player speaking to an npc. This finishes talk objectives, hands over
+/// items for deliver objectives and completes any finished quests the npc takes in.
+pub fn quest_talk(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    npc: GlobalKey,
+) -> Result<()> {
+    let Some(npc_index) = npc_in_talk_range(world, entity, npc)? else {
+        return Ok(());
+    };
+
+    update_objectives(world, storage, entity, |world, objective, current| {
+        Ok(match *objective {
+            QuestObjective::Talk { npc: index } if index == npc_index => Some(1),
+            QuestObjective::Deliver {
+                item,
+                count,
+                npc: index,
+            } if index == npc_index && current < count => {
+                let held = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+                    count_inv_item(item, &p_data.try_lock()?.inventory.items)
+                } else {
+                    0
+                };
+                let amount = (count - current).min(held.min(u16::MAX as u64) as u16);
+
+                if amount == 0 {
+                    None
+                } else {
+                    let left = take_inv_items(world, storage, entity, item, amount)?;
+
+                    Some(current + amount - left)
+                }
+            }
+            _ => None,
+        })
+    })?;
+
+    for quest in active_quests(world, entity)? {
+        if quest_base(storage, quest).is_some_and(|base| base.turn_in == Some(npc_index)) {
+            quest_complete(world, storage, entity, quest)?;
+        }
+    }
+
+    Ok(())
+}
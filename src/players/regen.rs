@@ -0,0 +1,93 @@
+use crate::{
+    containers::{GlobalKey, Storage, World},
+    gametypes::*,
+    players::*,
+    tasks::{DataTaskToken, vitals_packet},
+};
+use chrono::Duration;
+
+/// Puts both sides of an attack in combat, which holds their regen at the slower rate
+/// until COMBAT_TIMER_MS passes without another hit.
+pub fn enter_combat(
+    world: &mut World,
+    storage: &Storage,
+    attacker: GlobalKey,
+    target: GlobalKey,
+) -> Result<()> {
+    let tick = *storage.gettick.borrow();
+    let until = tick + Duration::try_milliseconds(COMBAT_TIMER_MS).unwrap_or_default();
+
+    for entity in [attacker, target] {
+        with_combat(world, entity, |combat, _| {
+            combat.in_combat = true;
+            combat.combat_timer.0 = combat.combat_timer.0.max(until);
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Restores each vital by its regen, multiplied by OUT_OF_COMBAT_REGEN once the combat
+/// timer runs out. Dead and spirit entities do not regen.
+pub fn regen_vitals(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let tick = *storage.gettick.borrow();
+
+    let Some((pos, vitals)) = with_combat(world, entity, |combat, pos| {
+        if combat.in_combat && combat.combat_timer.0 <= tick {
+            combat.in_combat = false;
+        }
+
+        if !combat.death_type.is_alive() {
+            return None;
+        }
+
+        let rate = if combat.in_combat {
+            1
+        } else {
+            OUT_OF_COMBAT_REGEN
+        };
+        let vitals = &mut combat.vitals;
+        let mut changed = false;
+
+        for i in 0..VITALS_MAX {
+            if vitals.regens[i] == 0 || vitals.vital[i] >= vitals.vitalmax[i] {
+                continue;
+            }
+
+            let amount = vitals.regens[i].saturating_mul(rate).min(i32::MAX as u32) as i32;
+
+            vitals.vital[i] = vitals.vital[i]
+                .saturating_add(amount)
+                .min(vitals.vitalmax[i]);
+            changed = true;
+        }
+
+        changed.then_some((pos, *vitals))
+    })?
+    .flatten() else {
+        return Ok(());
+    };
+
+    DataTaskToken::Vitals(pos.map).add_task(
+        storage,
+        vitals_packet(entity, vitals.vital, vitals.vitalmax)?,
+    )
+}
+
+/// Runs a regen tick for every player and npc. Changed vitals are batched per map
+/// through the Vitals data task.
+pub fn update_regen(world: &mut World, storage: &Storage) -> Result<()> {
+    let ids: Vec<GlobalKey> = storage
+        .player_ids
+        .borrow()
+        .iter()
+        .chain(storage.npc_ids.borrow().iter())
+        .copied()
+        .collect();
+
+    for id in ids {
+        regen_vitals(world, storage, id)?;
+    }
+
+    Ok(())
+}
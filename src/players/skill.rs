@@ -0,0 +1,426 @@
+use crate::{
+    containers::{DeathType, Entity, EntityKind, GlobalKey, Storage, World},
+    gametypes::*,
+    maps::{can_target, get_maps_in_range},
+    npcs::{can_attack_npc, damage_npc, kill_npc, try_target_entity},
+    players::*,
+    skills::{SkillArea, SkillData, SkillEffect},
+    socket::*,
+    sql::{PGHotbarSlot, SaveJob},
+    tasks::{DataTaskToken, cast_packet, damage_packet, vitals_packet},
+};
+use chrono::Duration;
+use rand::{Rng, rng};
+
+fn skill_base(storage: &Storage, skill: u16) -> Option<&SkillData> {
+    storage
+        .bases
+        .skills
+        .get(skill as usize)
+        .filter(|base| base.is_valid())
+}
+
+pub fn entity_state(world: &mut World, entity: GlobalKey) -> Result<Option<(Position, DeathType)>> {
+    match world.get_opt_entity(entity) {
+        Some(Entity::Player(p_data)) => {
+            let p_data = p_data.try_lock()?;
+
+            Ok(Some((p_data.movement.pos, p_data.combat.death_type)))
+        }
+        Some(Entity::Npc(n_data)) => {
+            let n_data = n_data.try_lock()?;
+
+            Ok(Some((n_data.movement.pos, n_data.combat.death_type)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Returns the living players or npcs within radius tiles of the center.
+pub fn area_targets(
+    world: &mut World,
+    storage: &Storage,
+    center: Position,
+    radius: i32,
+    kind: EntityKind,
+) -> Result<Vec<GlobalKey>> {
+    let mut targets = Vec::new();
+
+    for id in get_maps_in_range(storage, &center, radius) {
+        let Some(map) = id.get().and_then(|map| storage.maps.get(&map)) else {
+            continue;
+        };
+
+        let ids: Vec<GlobalKey> = match kind {
+            EntityKind::Player => map.borrow().players.iter().copied().collect(),
+            EntityKind::Npc => map.borrow().npcs.iter().copied().collect(),
+            _ => continue,
+        };
+
+        for target in ids {
+            if let Some((pos, death_type)) = entity_state(world, target)?
+                && can_target(center, pos, death_type, radius)
+            {
+                targets.push(target);
+            }
+        }
+    }
+
+    Ok(targets)
+}
+
+fn enemies_in_area(
+    world: &mut World,
+    storage: &Storage,
+    center: Position,
+    radius: i32,
+) -> Result<Vec<GlobalKey>> {
+    let mut enemies = Vec::new();
+
+    for target in area_targets(world, storage, center, radius, EntityKind::Npc)? {
+        if can_attack_npc(world, storage, target)? {
+            enemies.push(target);
+        }
+    }
+
+    Ok(enemies)
+}
+
+/// Deals skill damage from a player or npc caster and handles the target dying.
+pub fn skill_damage(
+    world: &mut World,
+    storage: &Storage,
+    caster: GlobalKey,
+    target: GlobalKey,
+    damage: i32,
+) -> Result<()> {
+    match world.get_opt_entity(target) {
+        Some(Entity::Player(p2_data)) => {
+            damage_player(world, storage, caster, target, damage)?;
+
+            let (t_pos, t_vitals) = {
+                let p2_data = p2_data.try_lock()?;
+
+                (p2_data.movement.pos, p2_data.combat.vitals)
+            };
+
+            DataTaskToken::Damage(t_pos.map)
+                .add_task(storage, damage_packet(target, damage as u16, t_pos, true)?)?;
+
+            if t_vitals.vital[VitalTypes::Hp as usize] > 0 {
+                DataTaskToken::Vitals(t_pos.map).add_task(
+                    storage,
+                    vitals_packet(target, t_vitals.vital, t_vitals.vitalmax)?,
+                )?;
+            } else {
+                remove_all_npc_target(world, target)?;
+                kill_player(world, storage, target, Some(caster))?;
+            }
+        }
+        Some(Entity::Npc(n2_data)) => {
+            damage_npc(world, storage, caster, target, damage)?;
+
+            let (t_pos, t_vitals, npc_index, level) = {
+                let n2_data = n2_data.try_lock()?;
+
+                (
+                    n2_data.movement.pos,
+                    n2_data.combat.vitals,
+                    n2_data.index,
+                    n2_data.combat.level,
+                )
+            };
+
+            DataTaskToken::Damage(t_pos.map)
+                .add_task(storage, damage_packet(target, damage as u16, t_pos, true)?)?;
+
+            if t_vitals.vital[VitalTypes::Hp as usize] > 0 {
+                DataTaskToken::Vitals(t_pos.map).add_task(
+                    storage,
+                    vitals_packet(target, t_vitals.vital, t_vitals.vitalmax)?,
+                )?;
+
+                if caster != target {
+                    try_target_entity(world, storage, target, caster)?;
+                }
+            } else if world.get_kind_or_default(caster) == EntityKind::Player {
+                let exp = storage.bases.npcs[npc_index as usize].exp;
+
+                player_earn_exp(world, storage, caster, level, exp, 1.0)?;
+                kill_npc(world, storage, target, Some(caster))?;
+            } else {
+                kill_npc(world, storage, target, None)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+pub fn skill_heal(
+    world: &mut World,
+    storage: &Storage,
+    target: GlobalKey,
+    amount: i32,
+) -> Result<()> {
+    let hp = VitalTypes::Hp as usize;
+
+    let (t_pos, t_vitals) = match world.get_opt_entity(target) {
+        Some(Entity::Player(p2_data)) => {
+            let mut p2_data = p2_data.try_lock()?;
+            let vitals = &mut p2_data.combat.vitals;
+
+            vitals.vital[hp] = vitals.vital[hp]
+                .saturating_add(amount)
+                .min(vitals.vitalmax[hp]);
+
+            (p2_data.movement.pos, p2_data.combat.vitals)
+        }
+        Some(Entity::Npc(n2_data)) => {
+            let mut n2_data = n2_data.try_lock()?;
+            let vitals = &mut n2_data.combat.vitals;
+
+            vitals.vital[hp] = vitals.vital[hp]
+                .saturating_add(amount)
+                .min(vitals.vitalmax[hp]);
+
+            (n2_data.movement.pos, n2_data.combat.vitals)
+        }
+        _ => return Ok(()),
+    };
+
+    DataTaskToken::Damage(t_pos.map)
+        .add_task(storage, damage_packet(target, amount as u16, t_pos, false)?)?;
+    DataTaskToken::Vitals(t_pos.map).add_task(
+        storage,
+        vitals_packet(target, t_vitals.vital, t_vitals.vitalmax)?,
+    )
+}
+
+pub fn skill_apply_effect(
+    world: &mut World,
+    storage: &Storage,
+    caster: GlobalKey,
+    target: GlobalKey,
+    effect: SkillEffect,
+) -> Result<()> {
+    let mut rng = rng();
+
+    match effect {
+        SkillEffect::None => Ok(()),
+        SkillEffect::Damage { min, max } => {
+            let bonus = status_power(world, caster, StatusKind::DamageBuff)?
+                - status_power(world, target, StatusKind::DefenseBuff)?;
+            let damage = (rng.random_range(min..=max).min(u16::MAX as u32) as i32)
+                .saturating_add(bonus)
+                .clamp(1, u16::MAX as i32);
+
+            skill_damage(world, storage, caster, target, damage)
+        }
+        SkillEffect::Heal { min, max } => {
+            let amount = rng.random_range(min..=max).min(u16::MAX as u32) as i32;
+
+            skill_heal(world, storage, target, amount)
+        }
+        SkillEffect::Status {
+            kind,
+            power,
+            duration,
+        } => apply_status_effect(world, storage, caster, target, kind, power, duration),
+    }
+}
+
+pub fn player_set_hotbar(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    slot: usize,
+    skill: Option<u16>,
+) -> Result<()> {
+    if slot >= MAX_HOTBAR || skill.is_some_and(|skill| skill_base(storage, skill).is_none()) {
+        return Ok(());
+    }
+
+    let uid = if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        let mut p_data = p_data.try_lock()?;
+
+        p_data.skills.hotbar[slot] = skill;
+        p_data.account.character
+    } else {
+        return Ok(());
+    };
+
+    storage.saves.save(SaveJob::Hotbar {
+        uid,
+        data: PGHotbarSlot {
+            slot: slot as i16,
+            skill: skill.map(|skill| skill as i16),
+        },
+    })?;
+
+    send_hotbar(world, storage, entity)
+}
+
+/// Casts a skill for the player. target is the chosen entity and ground the chosen tile,
+/// which one is used depends on the skill's cast type.
+pub fn player_cast_skill(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    skill: u16,
+    target: Option<GlobalKey>,
+    ground: Option<Position>,
+) -> Result<()> {
+    let Some(base) = skill_base(storage, skill) else {
+        return Ok(());
+    };
+
+    let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) else {
+        return Ok(());
+    };
+
+    let tick = *storage.gettick.borrow();
+
+    let (caster_pos, socket_id, error) = {
+        let p_data = p_data.try_lock()?;
+        let vitals = &p_data.combat.vitals;
+
+        if !p_data.combat.death_type.is_alive()
+            || p_data.is_using_type.inuse()
+            || p_data.combat.attacking
+            || p_data.combat.stunned
+            || p_data.combat.attack_timer.0 > tick
+        {
+            return Ok(());
+        }
+
+        let error = if p_data.combat.level < base.levelreq {
+            Some("You are not a high enough level to use this skill")
+        } else if p_data
+            .skills
+            .cooldowns
+            .get(&skill)
+            .is_some_and(|cooldown| *cooldown > tick)
+        {
+            Some("That skill is not ready yet")
+        } else if p_data.combat.effects.has(StatusKind::Silence) {
+            Some("You can not cast while silenced")
+        } else if vitals.vital[VitalTypes::Mp as usize] < base.mp_cost {
+            Some("Not enough MP")
+        } else if vitals.vital[VitalTypes::Sp as usize] < base.sp_cost {
+            Some("Not enough SP")
+        } else {
+            None
+        };
+
+        (p_data.movement.pos, p_data.socket.id, error)
+    };
+
+    if let Some(error) = error {
+        return send_fltalert(storage, socket_id, error.into(), FtlType::Error);
+    }
+
+    // Work out where the skill lands and who it was aimed at.
+    let (center, primary) = match base.cast_type {
+        NpcCastType::SelfOnly => (caster_pos, Some(entity)),
+        NpcCastType::Friend => match target {
+            Some(target) if target != entity => {
+                if world.get_kind_or_default(target) != EntityKind::Player
+                    || !try_player_cast(world, storage, entity, target, base.range, false)?
+                {
+                    return Ok(());
+                }
+
+                match entity_state(world, target)? {
+                    Some((pos, _)) => (pos, Some(target)),
+                    None => return Ok(()),
+                }
+            }
+            _ => (caster_pos, Some(entity)),
+        },
+        NpcCastType::Enemy => {
+            let Some(target) = target else {
+                return Ok(());
+            };
+
+            if world.get_kind_or_default(target) == EntityKind::Npc
+                && !can_attack_npc(world, storage, target)?
+            {
+                return Ok(());
+            }
+
+            if !try_player_cast(world, storage, entity, target, base.range, true)? {
+                return Ok(());
+            }
+
+            match entity_state(world, target)? {
+                Some((pos, _)) => (pos, Some(target)),
+                None => return Ok(()),
+            }
+        }
+        NpcCastType::Ground => {
+            let pos = match (ground, target) {
+                (Some(pos), _) => pos,
+                (None, Some(target)) => match entity_state(world, target)? {
+                    Some((pos, _)) => pos,
+                    None => return Ok(()),
+                },
+                (None, None) => return Ok(()),
+            };
+
+            if !can_target(caster_pos, pos, DeathType::Alive, base.range) {
+                return Ok(());
+            }
+
+            (pos, None)
+        }
+    };
+
+    let targets = match (base.area, base.cast_type) {
+        (SkillArea::Single, NpcCastType::Ground) => enemies_in_area(world, storage, center, 0)?,
+        (SkillArea::Single, _) => primary.into_iter().collect(),
+        (SkillArea::Radius(radius), NpcCastType::SelfOnly | NpcCastType::Friend) => {
+            area_targets(world, storage, center, radius, EntityKind::Player)?
+        }
+        (SkillArea::Radius(radius), NpcCastType::Enemy | NpcCastType::Ground) => {
+            // Area damage only hits npcs. Players can still be hit by aiming at them directly.
+            let mut targets = enemies_in_area(world, storage, center, radius)?;
+
+            if let Some(primary) = primary
+                && !targets.contains(&primary)
+            {
+                targets.push(primary);
+            }
+
+            targets
+        }
+    };
+
+    let vitals = {
+        let mut p_data = p_data.try_lock()?;
+
+        p_data.combat.vitals.vital[VitalTypes::Mp as usize] -= base.mp_cost;
+        p_data.combat.vitals.vital[VitalTypes::Sp as usize] -= base.sp_cost;
+        p_data.combat.attack_timer.0 = tick + Duration::try_milliseconds(250).unwrap_or_default();
+        p_data.skills.cooldowns.insert(
+            skill,
+            tick + Duration::try_milliseconds(base.cooldown).unwrap_or_default(),
+        );
+
+        p_data.combat.vitals
+    };
+
+    DataTaskToken::Cast(caster_pos.map).add_task(storage, cast_packet(entity, skill, center)?)?;
+    DataTaskToken::Vitals(caster_pos.map).add_task(
+        storage,
+        vitals_packet(entity, vitals.vital, vitals.vitalmax)?,
+    )?;
+    send_skillcooldown(world, storage, entity, skill, base.cooldown)?;
+
+    for target in targets {
+        skill_apply_effect(world, storage, entity, target, base.effect)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use crate::{
+    containers::{CombatData, Entity, GlobalKey, StatusEffect, Storage, World},
+    gametypes::*,
+    players::*,
+    tasks::{DataTaskToken, status_effects_packet},
+};
+use chrono::Duration;
+
+/// Runs the closure on the combat data of a player or npc along with where they are.
+pub(crate) fn with_combat<T>(
+    world: &mut World,
+    entity: GlobalKey,
+    f: impl FnOnce(&mut CombatData, Position) -> T,
+) -> Result<Option<T>> {
+    match world.get_opt_entity(entity) {
+        Some(Entity::Player(p_data)) => {
+            let mut p_data = p_data.try_lock()?;
+            let pos = p_data.movement.pos;
+
+            Ok(Some(f(&mut p_data.combat, pos)))
+        }
+        Some(Entity::Npc(n_data)) => {
+            let mut n_data = n_data.try_lock()?;
+            let pos = n_data.movement.pos;
+
+            Ok(Some(f(&mut n_data.combat, pos)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Combined power of the effect on a player or npc or 0 if it is not active.
+pub fn status_power(world: &mut World, entity: GlobalKey, kind: StatusKind) -> Result<i32> {
+    Ok(with_combat(world, entity, |combat, _| combat.effects.power(kind))?.unwrap_or_default())
+}
+
+pub fn has_status(world: &mut World, entity: GlobalKey, kind: StatusKind) -> Result<bool> {
+    Ok(with_combat(world, entity, |combat, _| combat.effects.has(kind))?.unwrap_or_default())
+}
+
+/// Broadcasts the entity's active effects to everyone who can see them.
+pub fn send_status_effects(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let tick = *storage.gettick.borrow();
+
+    if let Some((effects, pos)) =
+        with_combat(world, entity, |combat, pos| (combat.effects.clone(), pos))?
+    {
+        DataTaskToken::StatusEffects(pos.map)
+            .add_task(storage, status_effects_packet(entity, &effects, tick)?)?;
+    }
+
+    Ok(())
+}
+
+pub fn apply_status_effect(
+    world: &mut World,
+    storage: &Storage,
+    source: GlobalKey,
+    target: GlobalKey,
+    kind: StatusKind,
+    power: i32,
+    duration: i64,
+) -> Result<()> {
+    let tick = *storage.gettick.borrow();
+
+    let applied = with_combat(world, target, |combat, _| {
+        if !combat.death_type.is_alive() {
+            return false;
+        }
+
+        combat.effects.apply(StatusEffect {
+            kind,
+            power,
+            stacks: 1,
+            expires: tick + Duration::try_milliseconds(duration).unwrap_or_default(),
+            next_tick: tick + Duration::try_milliseconds(STATUS_TICK_MS).unwrap_or_default(),
+            source: Some(source),
+        });
+        combat.stunned = combat.effects.has(StatusKind::Stun);
+        true
+    })?
+    .unwrap_or_default();
+
+    if applied {
+        send_status_effects(world, storage, target)?;
+    }
+
+    Ok(())
+}
+
+/// Removes expired effects and applies poison and regen when their tick comes up.
+/// Called from the player and npc update loops. Effects are cleared on death.
+pub fn update_status_effects(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+) -> Result<()> {
+    let tick = *storage.gettick.borrow();
+
+    let Some((changed, poison, regen)) = with_combat(world, entity, |combat, _| {
+        if combat.effects.0.is_empty() {
+            return None;
+        }
+
+        if !combat.death_type.is_alive() {
+            combat.effects.0.clear();
+            combat.stunned = false;
+            return Some((true, None, 0));
+        }
+
+        let changed = combat.effects.remove_expired(tick);
+        let hurt = combat.vitals.vital[VitalTypes::Hp as usize]
+            < combat.vitals.vitalmax[VitalTypes::Hp as usize];
+        let mut poison = None;
+        let mut regen = 0;
+
+        for effect in combat.effects.0.iter_mut() {
+            if effect.next_tick > tick {
+                continue;
+            }
+
+            effect.next_tick =
+                tick + Duration::try_milliseconds(STATUS_TICK_MS).unwrap_or_default();
+
+            let power = effect.power.saturating_mul(effect.stacks as i32);
+
+            match effect.kind {
+                StatusKind::Poison => poison = Some((power, effect.source)),
+                StatusKind::Regen if hurt => regen = power,
+                _ => {}
+            }
+        }
+
+        combat.stunned = combat.effects.has(StatusKind::Stun);
+        Some((changed, poison, regen))
+    })?
+    .flatten() else {
+        return Ok(());
+    };
+
+    if changed {
+        send_status_effects(world, storage, entity)?;
+    }
+
+    if regen > 0 {
+        skill_heal(world, storage, entity, regen)?;
+    }
+
+    if let Some((damage, source)) = poison
+        && damage > 0
+    {
+        // The poisoner may have logged out or despawned since.
+        let source = source
+            .filter(|source| world.entities.contains_key(*source))
+            .unwrap_or(entity);
+
+        skill_damage(world, storage, source, entity, damage)?;
+    }
+
+    Ok(())
+}
+
+pub fn update_players_status(world: &mut World, storage: &Storage) -> Result<()> {
+    let ids: Vec<GlobalKey> = storage.player_ids.borrow().iter().copied().collect();
+
+    for id in ids {
+        update_status_effects(world, storage, id)?;
+    }
+
+    Ok(())
+}
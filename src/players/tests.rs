@@ -0,0 +1,57 @@
+//! Checks for player logic that runs against a World. These need no database.
+
+use super::*;
+use crate::{
+    containers::{Entity, GlobalKey, Party, Socket, Storage, World},
+    quests::{QuestData, QuestObjective},
+};
+
+fn add_player(world: &mut World, storage: &Storage) -> GlobalKey {
+    storage
+        .add_player_data(world, String::new(), String::new(), Socket::default())
+        .unwrap()
+}
+
+fn levelexp(world: &mut World, entity: GlobalKey) -> u64 {
+    match world.get_opt_entity(entity) {
+        Some(Entity::Player(p_data)) => p_data.try_lock().unwrap().general.levelexp,
+        _ => panic!("player entity is missing"),
+    }
+}
+
+#[test]
+fn quest_exp_is_not_split_with_the_party() {
+    let mut world = World::default();
+    let mut storage = Storage::new_for_tests();
+
+    storage.bases.progression = crate::progression::get_progression().unwrap();
+    storage.bases.quests = vec![QuestData {
+        name: "Rats".into(),
+        objectives: vec![QuestObjective::Kill { npc: 0, count: 1 }],
+        exp: 60,
+        ..Default::default()
+    }];
+
+    let player = add_player(&mut world, &storage);
+    let partner = add_player(&mut world, &storage);
+    let mut party = Party::new(player);
+
+    party.members.insert(partner, None);
+
+    let party = storage.parties.borrow_mut().insert(party);
+
+    for entity in [player, partner] {
+        if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+            p_data.try_lock().unwrap().party.party = Some(party);
+        }
+    }
+
+    if let Some(Entity::Player(p_data)) = world.get_opt_entity(player) {
+        p_data.try_lock().unwrap().quests.active.insert(0, vec![1]);
+    }
+
+    quest_complete(&mut world, &storage, player, 0).unwrap();
+
+    assert_eq!(levelexp(&mut world, player), 60);
+    assert_eq!(levelexp(&mut world, partner), 0);
+}
@@ -0,0 +1,435 @@
+use crate::{
+    containers::{Attributes, CombatData},
+    gametypes::*,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+pub const PROGRESSION_FILE: &str = "./data/progression.toml";
+
+/// Every level up to and including to_level needs the level times exp_per_level
+/// exp to reach the next one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ExpBracket {
+    pub to_level: i32,
+    pub exp_per_level: u64,
+}
+
+/// A stat worth base plus per_level for every level, rounded down.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StatGrowth {
+    pub base: i32,
+    pub per_level: f64,
+}
+
+impl StatGrowth {
+    pub fn at(&self, level: i32) -> i32 {
+        self.base
+            .saturating_add((self.per_level * level.max(0) as f64) as i32)
+    }
+}
+
+/// What a single point spent on an attribute is worth. Totals are rounded down.
+#[derive(Clone, Copy, Debug, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AttributeBonus {
+    pub damage: f64,
+    pub defense: f64,
+    pub hp: f64,
+    pub mp: f64,
+    pub hp_regen: f64,
+    pub mp_regen: f64,
+}
+
+impl AttributeBonus {
+    fn values(&self) -> [(&'static str, f64); 6] {
+        [
+            ("damage", self.damage),
+            ("defense", self.defense),
+            ("hp", self.hp),
+            ("mp", self.mp),
+            ("hp_regen", self.hp_regen),
+            ("mp_regen", self.mp_regen),
+        ]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AttributeRules {
+    /// Unspent points given for every level gained.
+    pub points_per_level: i32,
+    pub strength: AttributeBonus,
+    pub dexterity: AttributeBonus,
+    pub vitality: AttributeBonus,
+    pub intelligence: AttributeBonus,
+}
+
+impl Default for AttributeRules {
+    fn default() -> Self {
+        Self {
+            points_per_level: 3,
+            strength: AttributeBonus {
+                damage: 1.0,
+                ..Default::default()
+            },
+            dexterity: AttributeBonus {
+                damage: 0.5,
+                defense: 0.5,
+                ..Default::default()
+            },
+            vitality: AttributeBonus {
+                defense: 0.5,
+                hp: 10.0,
+                hp_regen: 0.2,
+                ..Default::default()
+            },
+            intelligence: AttributeBonus {
+                mp: 10.0,
+                mp_regen: 0.2,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl AttributeRules {
+    pub fn bonus(&self, attribute: AttributeType) -> AttributeBonus {
+        match attribute {
+            AttributeType::Strength => self.strength,
+            AttributeType::Dexterity => self.dexterity,
+            AttributeType::Vitality => self.vitality,
+            AttributeType::Intelligence => self.intelligence,
+            AttributeType::Count => AttributeBonus::default(),
+        }
+    }
+
+    /// Adds up the bonuses from every point spent.
+    pub fn total(&self, attributes: &Attributes) -> AttributeBonus {
+        let mut total = AttributeBonus::default();
+
+        for attribute in [
+            AttributeType::Strength,
+            AttributeType::Dexterity,
+            AttributeType::Vitality,
+            AttributeType::Intelligence,
+        ] {
+            let points = attributes.get(attribute).max(0) as f64;
+            let bonus = self.bonus(attribute);
+
+            total.damage += bonus.damage * points;
+            total.defense += bonus.defense * points;
+            total.hp += bonus.hp * points;
+            total.mp += bonus.mp * points;
+            total.hp_regen += bonus.hp_regen * points;
+            total.mp_regen += bonus.mp_regen * points;
+        }
+
+        total
+    }
+}
+
+/// What dying costs a player.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DeathRules {
+    /// How long in milliseconds a player stays a spirit before respawning.
+    pub spirit_ms: i64,
+    /// Percent of the exp needed for the next level that is lost. Never drops a level.
+    pub exp_loss_percent: f64,
+    /// Percent of carried money dropped where the player died.
+    pub money_drop_percent: f64,
+    /// How many random inventory stacks are dropped where the player died.
+    pub item_drops: u32,
+    /// How long in milliseconds only the killer can pick the drops up. When an npc
+    /// made the kill the dead player gets them instead.
+    pub owner_ms: i64,
+    /// How long in milliseconds the drops stay on the ground.
+    pub despawn_ms: i64,
+    /// Used in place of the above for player killers, whose pk status ends on death.
+    pub pk_exp_loss_percent: f64,
+    pub pk_money_drop_percent: f64,
+    pub pk_item_drops: u32,
+}
+
+impl Default for DeathRules {
+    fn default() -> Self {
+        Self {
+            spirit_ms: 10000,
+            exp_loss_percent: 5.0,
+            money_drop_percent: 0.0,
+            item_drops: 0,
+            owner_ms: 60000,
+            despawn_ms: 600000,
+            pk_exp_loss_percent: 10.0,
+            pk_money_drop_percent: 10.0,
+            pk_item_drops: 1,
+        }
+    }
+}
+
+/// The exp curve, the stats players get from their level and death penalties. Loaded from
+/// ./data/progression.toml so it can be rebalanced without a rebuild.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Progression {
+    pub max_level: i32,
+    pub exp: Vec<ExpBracket>,
+    pub hp: StatGrowth,
+    pub mp: StatGrowth,
+    pub damage: StatGrowth,
+    pub defense: StatGrowth,
+    /// Vitals restored every regen tick before the out of combat bonus.
+    pub hp_regen: StatGrowth,
+    pub mp_regen: StatGrowth,
+    pub sp_regen: StatGrowth,
+    pub attributes: AttributeRules,
+    pub death: DeathRules,
+    /// Exp needed to leave each level, built from the brackets. Index 0 is level 1.
+    #[serde(skip)]
+    exp_table: Vec<u64>,
+}
+
+impl Default for Progression {
+    fn default() -> Self {
+        let brackets = [
+            (10, 100),
+            (20, 250),
+            (30, 400),
+            (40, 550),
+            (50, 700),
+            (60, 850),
+            (70, 1000),
+            (80, 1150),
+            (90, 1300),
+            (100, 1450),
+            (120, 2000),
+            (150, 3000),
+            (199, 4000),
+        ];
+
+        let mut progression = Self {
+            max_level: 200,
+            exp: brackets
+                .into_iter()
+                .map(|(to_level, exp_per_level)| ExpBracket {
+                    to_level,
+                    exp_per_level,
+                })
+                .collect(),
+            hp: StatGrowth {
+                base: 0,
+                per_level: 25.0,
+            },
+            mp: StatGrowth {
+                base: 0,
+                per_level: 25.0,
+            },
+            damage: StatGrowth::default(),
+            defense: StatGrowth {
+                base: 0,
+                per_level: 0.2,
+            },
+            hp_regen: StatGrowth {
+                base: 1,
+                per_level: 0.1,
+            },
+            mp_regen: StatGrowth {
+                base: 1,
+                per_level: 0.1,
+            },
+            sp_regen: StatGrowth {
+                base: 5,
+                per_level: 0.0,
+            },
+            attributes: AttributeRules::default(),
+            death: DeathRules::default(),
+            exp_table: Vec::new(),
+        };
+
+        progression.exp_table = progression.expand_exp();
+        progression
+    }
+}
+
+impl Progression {
+    /// Exp needed to go from this level to the next. 0 at the max level.
+    pub fn next_level_exp(&self, level: i32) -> u64 {
+        if level < 1 {
+            return 0;
+        }
+
+        self.exp_table
+            .get(level as usize - 1)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Sets the max vitals, regen, damage and defense for the current level and
+    /// attributes. Vitals above their new max are lowered to it. New characters sit at
+    /// level 0 until their first exp so they get level 1 stats.
+    pub fn apply(&self, combat: &mut CombatData, attributes: &Attributes) {
+        let level = combat.level.max(1);
+        let bonus = self.attributes.total(attributes);
+        let vitals = &mut combat.vitals;
+
+        vitals.vitalmax[VitalTypes::Hp as usize] =
+            self.hp.at(level).saturating_add(bonus.hp as i32).max(1);
+        vitals.vitalmax[VitalTypes::Mp as usize] =
+            self.mp.at(level).saturating_add(bonus.mp as i32).max(0);
+        vitals.regens[VitalTypes::Hp as usize] = self
+            .hp_regen
+            .at(level)
+            .saturating_add(bonus.hp_regen as i32)
+            .max(0) as u32;
+        vitals.regens[VitalTypes::Mp as usize] = self
+            .mp_regen
+            .at(level)
+            .saturating_add(bonus.mp_regen as i32)
+            .max(0) as u32;
+        vitals.regens[VitalTypes::Sp as usize] = self.sp_regen.at(level).max(0) as u32;
+
+        for i in [VitalTypes::Hp as usize, VitalTypes::Mp as usize] {
+            vitals.vital[i] =
+                vitals.vital[i].min(vitals.vitalmax[i].saturating_add(vitals.vitalbuffs[i]));
+        }
+
+        combat.physical.damage = self
+            .damage
+            .at(level)
+            .saturating_add(bonus.damage as i32)
+            .max(0) as u32;
+        combat.physical.defense = self
+            .defense
+            .at(level)
+            .saturating_add(bonus.defense as i32)
+            .max(0) as u32;
+    }
+
+    fn expand_exp(&self) -> Vec<u64> {
+        (1..self.max_level)
+            .map(|level| {
+                self.exp
+                    .iter()
+                    .find(|bracket| level <= bracket.to_level)
+                    .map(|bracket| (level as u64).saturating_mul(bracket.exp_per_level))
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    fn validate(&mut self) -> std::result::Result<(), String> {
+        if self.max_level < 2 {
+            return Err("max_level must be at least 2".into());
+        }
+
+        if self
+            .exp
+            .windows(2)
+            .any(|pair| pair[0].to_level >= pair[1].to_level)
+        {
+            return Err("exp brackets must be sorted by to_level".into());
+        }
+
+        if self
+            .exp
+            .last()
+            .is_none_or(|bracket| bracket.to_level < self.max_level - 1)
+        {
+            return Err(format!(
+                "exp brackets must cover every level up to {}",
+                self.max_level - 1
+            ));
+        }
+
+        let table = self.expand_exp();
+
+        if let Some(level) = table.iter().position(|exp| *exp == 0) {
+            return Err(format!("level {} needs 0 exp", level + 1));
+        }
+
+        if let Some(level) = table.windows(2).position(|pair| pair[1] < pair[0]) {
+            return Err(format!(
+                "level {} needs less exp than level {}",
+                level + 2,
+                level + 1
+            ));
+        }
+
+        for (name, stat) in [
+            ("hp", self.hp),
+            ("mp", self.mp),
+            ("damage", self.damage),
+            ("defense", self.defense),
+            ("hp_regen", self.hp_regen),
+            ("mp_regen", self.mp_regen),
+            ("sp_regen", self.sp_regen),
+        ] {
+            if stat.base < 0 || !stat.per_level.is_finite() || stat.per_level < 0.0 {
+                return Err(format!(
+                    "{name} base and per_level must be finite and not negative"
+                ));
+            }
+        }
+
+        if self.attributes.points_per_level < 0 {
+            return Err("attributes.points_per_level must not be negative".into());
+        }
+
+        for (attribute, bonus) in [
+            ("strength", self.attributes.strength),
+            ("dexterity", self.attributes.dexterity),
+            ("vitality", self.attributes.vitality),
+            ("intelligence", self.attributes.intelligence),
+        ] {
+            if let Some((name, _)) = bonus
+                .values()
+                .into_iter()
+                .find(|(_, value)| !value.is_finite() || *value < 0.0)
+            {
+                return Err(format!(
+                    "attributes.{attribute}.{name} must be finite and not negative"
+                ));
+            }
+        }
+
+        let death = &self.death;
+
+        if death.spirit_ms < 0 || death.owner_ms < 0 || death.despawn_ms < 0 {
+            return Err("death timers must not be negative".into());
+        }
+
+        for (name, percent) in [
+            ("exp_loss_percent", death.exp_loss_percent),
+            ("money_drop_percent", death.money_drop_percent),
+            ("pk_exp_loss_percent", death.pk_exp_loss_percent),
+            ("pk_money_drop_percent", death.pk_money_drop_percent),
+        ] {
+            if !(0.0..=100.0).contains(&percent) {
+                return Err(format!("death.{name} must be between 0 and 100"));
+            }
+        }
+
+        self.exp_table = table;
+        Ok(())
+    }
+}
+
+/// Loads and checks the progression file. Without one the built in defaults are used.
+pub fn get_progression() -> Result<Progression> {
+    let Ok(data) = fs::read_to_string(PROGRESSION_FILE) else {
+        return Ok(Progression::default());
+    };
+
+    let invalid = |message: String| AscendingError::InvalidData {
+        file: PROGRESSION_FILE.into(),
+        message,
+    };
+
+    let mut progression: Progression = toml::from_str(&data).map_err(|e| invalid(e.to_string()))?;
+
+    progression.validate().map_err(invalid)?;
+
+    Ok(progression)
+}
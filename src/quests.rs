@@ -0,0 +1,3 @@
+mod questdata;
+
+pub use questdata::*;
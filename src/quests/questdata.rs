@@ -0,0 +1,112 @@
+use crate::gametypes::{AscendingError, MAX_QUESTS, Result};
+use educe::Educe;
+use serde::{Deserialize, Serialize};
+use speedy::{Readable, Writable};
+use std::fs::OpenOptions;
+use std::io::Read;
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Default, Deserialize, Serialize, Readable, Writable,
+)]
+pub enum QuestObjective {
+    #[default]
+    None,
+    /// Kill a number of npcs of this index.
+    Kill { npc: u64, count: u16 },
+    /// Hold a number of items in the inventory. They are taken when the quest completes.
+    Collect { item: u32, count: u16 },
+    /// Hand a number of items over to an npc of this index.
+    Deliver { item: u32, count: u16, npc: u64 },
+    /// Speak with an npc of this index.
+    Talk { npc: u64 },
+}
+
+impl QuestObjective {
+    /// How much progress is needed before the objective is done.
+    pub fn required(&self) -> u16 {
+        match self {
+            QuestObjective::None => 0,
+            QuestObjective::Kill { count, .. }
+            | QuestObjective::Collect { count, .. }
+            | QuestObjective::Deliver { count, .. } => *count,
+            QuestObjective::Talk { .. } => 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Readable, Writable)]
+pub struct QuestRewardItem {
+    pub item: u32,
+    pub amount: u16,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Educe, Readable, Writable)]
+#[educe(Default)]
+pub struct QuestData {
+    pub name: String,
+    pub description: String,
+    pub levelreq: i32,
+    /// Quest that must be completed before this one can be accepted.
+    pub prerequisite: Option<u16>,
+    /// Npc index the quest must be accepted from. None lets it be accepted anywhere.
+    pub giver: Option<u64>,
+    /// Npc index the quest is handed in to. None completes it as soon as the objectives are done.
+    pub turn_in: Option<u64>,
+    pub repeatable: bool,
+    pub objectives: Vec<QuestObjective>,
+    pub exp: i64,
+    pub vals: u64,
+    pub items: Vec<QuestRewardItem>,
+}
+
+impl QuestData {
+    /// Unused slots in Bases are padded with defaults which have no objectives.
+    pub fn is_valid(&self) -> bool {
+        !self.objectives.is_empty()
+    }
+
+    /// Checks the progress list against each objective.
+    pub fn is_done(&self, progress: &[u16]) -> bool {
+        self.objectives
+            .iter()
+            .enumerate()
+            .all(|(id, objective)| progress.get(id).copied().unwrap_or(0) >= objective.required())
+    }
+}
+
+pub fn get_quest() -> Result<Vec<QuestData>> {
+    let mut quest_data: Vec<QuestData> = Vec::new();
+
+    while let Some(data) = load_file(quest_data.len())? {
+        if quest_data.len() >= MAX_QUESTS {
+            return Err(AscendingError::InvalidData {
+                file: "./data/quests/".into(),
+                message: format!("more than {MAX_QUESTS} quests"),
+            });
+        }
+
+        quest_data.push(data);
+    }
+
+    Ok(quest_data)
+}
+
+fn load_file(id: usize) -> Result<Option<QuestData>> {
+    let name = format!("./data/quests/{id}.bin");
+
+    match OpenOptions::new().read(true).open(&name) {
+        Ok(mut file) => {
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+
+            match QuestData::read_from_buffer(&bytes) {
+                Ok(data) => Ok(Some(data)),
+                Err(e) => Err(AscendingError::InvalidData {
+                    file: name,
+                    message: e.to_string(),
+                }),
+            }
+        }
+        Err(_) => Ok(None),
+    }
+}
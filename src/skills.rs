@@ -0,0 +1,3 @@
+mod skilldata;
+
+pub use skilldata::*;
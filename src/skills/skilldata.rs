@@ -0,0 +1,100 @@
+use crate::gametypes::{AscendingError, MAX_SKILLS, NpcCastType, Result, StatusKind};
+use educe::Educe;
+use serde::{Deserialize, Serialize};
+use speedy::{Readable, Writable};
+use std::fs::OpenOptions;
+use std::io::Read;
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Default, Deserialize, Serialize, Readable, Writable,
+)]
+pub enum SkillArea {
+    /// Only the chosen target is affected.
+    #[default]
+    Single,
+    /// Everything valid within this many tiles of the target or ground position.
+    Radius(i32),
+}
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Default, Deserialize, Serialize, Readable, Writable,
+)]
+pub enum SkillEffect {
+    #[default]
+    None,
+    Damage {
+        min: u32,
+        max: u32,
+    },
+    Heal {
+        min: u32,
+        max: u32,
+    },
+    /// Applies a timed status effect. Duration is in milliseconds.
+    Status {
+        kind: StatusKind,
+        power: i32,
+        duration: i64,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Educe, Readable, Writable)]
+#[educe(Default)]
+pub struct SkillData {
+    pub name: String,
+    pub icon: u16,
+    pub animation: Option<u32>,
+    pub levelreq: i32,
+    pub mp_cost: i32,
+    pub sp_cost: i32,
+    /// Milliseconds before the skill can be used again.
+    pub cooldown: i64,
+    pub cast_type: NpcCastType,
+    pub range: i32,
+    pub area: SkillArea,
+    pub effect: SkillEffect,
+}
+
+impl SkillData {
+    /// Unused slots in Bases are padded with defaults which have no effect.
+    pub fn is_valid(&self) -> bool {
+        self.effect != SkillEffect::None
+    }
+}
+
+pub fn get_skill() -> Result<Vec<SkillData>> {
+    let mut skill_data: Vec<SkillData> = Vec::new();
+
+    while let Some(data) = load_file(skill_data.len())? {
+        if skill_data.len() >= MAX_SKILLS {
+            return Err(AscendingError::InvalidData {
+                file: "./data/skills/".into(),
+                message: format!("more than {MAX_SKILLS} skills"),
+            });
+        }
+
+        skill_data.push(data);
+    }
+
+    Ok(skill_data)
+}
+
+fn load_file(id: usize) -> Result<Option<SkillData>> {
+    let name = format!("./data/skills/{id}.bin");
+
+    match OpenOptions::new().read(true).open(&name) {
+        Ok(mut file) => {
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+
+            match SkillData::read_from_buffer(&bytes) {
+                Ok(data) => Ok(Some(data)),
+                Err(e) => Err(AscendingError::InvalidData {
+                    file: name,
+                    message: e.to_string(),
+                }),
+            }
+        }
+        Err(_) => Ok(None),
+    }
+}
@@ -1,9 +1,13 @@
 mod buffer;
 mod client;
 mod packet_ids;
+mod protocol;
+mod rate_limit;
 mod sends;
 mod server;
 mod states;
+#[cfg(test)]
+mod tests;
 
 pub use buffer::*;
 #[allow(unused_imports)]
@@ -12,6 +16,8 @@ pub use client::*;
 #[allow(unused_imports)]
 pub use mmap_bytey::{MByteBuffer, MByteBufferError, MByteBufferRead, MByteBufferWrite};
 pub use packet_ids::*;
+pub use protocol::*;
+pub use rate_limit::*;
 pub use sends::*;
 pub use server::*;
 pub use states::*;
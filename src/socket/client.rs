@@ -31,6 +31,9 @@ pub struct Client {
     pub tls: Option<rustls::ServerConnection>,
     pub buffer: Arc<Mutex<ByteBuffer>>,
     pub addr: Arc<String>,
+    pub rate_limiter: RateLimiter,
+    // Set once the client finishes ProtocolHello.
+    pub protocol: Option<Protocol>,
 }
 
 impl Client {
@@ -51,6 +54,8 @@ impl Client {
             tls,
             buffer: Arc::new(Mutex::new(ByteBuffer::with_capacity(8192)?)),
             addr: Arc::new(addr),
+            rate_limiter: RateLimiter::default(),
+            protocol: None,
         })
     }
 
@@ -112,6 +117,10 @@ impl Client {
                 self.deregister(&storage.poll.borrow_mut())?;
                 self.state = ClientState::Closed;
                 let _ = self.stream.shutdown(std::net::Shutdown::Both);
+                let _ = storage
+                    .character_select
+                    .borrow_mut()
+                    .remove(&(self.token.0 - CLIENT_OFFSET));
 
                 let mut remove_entity = false;
 
@@ -139,7 +148,7 @@ impl Client {
 
                         info!(
                             "Added player on disconnected list : {}",
-                            &data.account.username
+                            data.account.username
                         );
 
                         data.connection.disconnect_timer = *storage.gettick.borrow()
@@ -434,6 +443,7 @@ impl Client {
 #[inline]
 pub fn disconnect(playerid: GlobalKey, world: &mut World, storage: &Storage) -> Result<()> {
     left_game(world, storage, playerid)?;
+    party_leave(world, storage, playerid)?;
 
     let _ = storage
         .disconnected_player
@@ -445,7 +455,7 @@ pub fn disconnect(playerid: GlobalKey, world: &mut World, storage: &Storage) ->
         let pos = {
             let player = player.try_lock()?;
 
-            trace!("Players Disconnected IP: {} ", &player.socket.addr);
+            trace!("Players Disconnected IP: {} ", player.socket.addr);
 
             player.movement.pos
         };
@@ -597,6 +607,34 @@ pub fn set_client_as_closed(storage: &Storage, token: usize) {
 
 pub const MAX_PROCESSED_PACKETS: i32 = 25;
 
+/// Charges the packet against the client's rate limits. The packet's cursor is left at
+/// the start so it can still be handled.
+fn check_rate_limit(
+    storage: &Storage,
+    token: usize,
+    packet: &mut MByteBuffer,
+) -> Result<RateVerdict> {
+    let Some(id) = packet.read::<u16>().ok().and_then(ClientPacket::from_id) else {
+        // Unknown packets are left for handle_data to reject.
+        packet.move_cursor_to_start();
+        return Ok(RateVerdict::Allow);
+    };
+
+    packet.move_cursor_to_start();
+
+    let tick = *storage.gettick.borrow();
+
+    Ok(match storage.server.borrow().clients.get(token) {
+        Some(client) => {
+            client
+                .borrow_mut()
+                .rate_limiter
+                .check(&storage.config.rate_limits, id, tick)
+        }
+        None => RateVerdict::Allow,
+    })
+}
+
 pub fn process_packets(world: &mut World, storage: &Storage) -> Result<()> {
     let mut packet = MByteBuffer::new()?;
     let mut rerun: Vec<usize> = Vec::with_capacity(64);
@@ -667,6 +705,30 @@ pub fn process_packets(world: &mut World, storage: &Storage) -> Result<()> {
 
                     let socketid = SocketID { id: token, is_tls };
 
+                    match check_rate_limit(storage, token, &mut packet)? {
+                        RateVerdict::Allow => {}
+                        RateVerdict::Throttle => {
+                            info!("IP: {address} went over its packet rate limits.");
+                            continue;
+                        }
+                        RateVerdict::Drop => continue,
+                        RateVerdict::Warn => {
+                            warn!("IP: {address} is flooding packets and was warned.");
+                            send_infomsg(
+                                storage,
+                                token,
+                                "You are sending too much too fast. Slow down or you will be disconnected.".into(),
+                                0,
+                            )?;
+                            continue;
+                        }
+                        RateVerdict::Disconnect => {
+                            warn!("IP: {address} was disconnected for flooding packets.");
+                            set_client_as_closed(storage, token);
+                            continue 'user_loop;
+                        }
+                    }
+
                     if handle_data(world, storage, &mut packet, entity, socketid).is_err() {
                         warn!("IP: {address} was disconnected due to invalid packets");
                         set_client_as_closed(storage, token);
@@ -1,113 +1,220 @@
-use bytey::{ByteBufferRead, ByteBufferWrite};
-use mmap_bytey::{MByteBufferRead, MByteBufferWrite};
+use bytey::{ByteBuffer, ByteBufferWrite};
+use mmap_bytey::{MByteBuffer, MByteBufferWrite};
 use serde::{Deserialize, Serialize};
 
-#[derive(
-    Copy,
-    Clone,
-    Debug,
-    PartialEq,
-    Eq,
-    Serialize,
-    Deserialize,
-    ByteBufferRead,
-    ByteBufferWrite,
-    MByteBufferRead,
-    MByteBufferWrite,
-    Hash,
-)]
-pub enum ServerPackets {
-    OnlineCheck,
-    AlertMsg,
-    FltAlert,
-    HandShake,
-    LoginOk,
-    MapItems,
-    MyIndex,
-    Move,
-    Warp,
-    Dir,
-    Vitals,
-    Attack,
-    Death,
-    PlayerData,
-    PlayerSpawn,
-    PlayerInv,
-    PlayerInvSlot,
-    PlayerStorage,
-    PlayerStorageSlot,
-    PlayerEquipment,
-    PlayerLevel,
-    PlayerMoney,
-    PlayerPk,
-    NpcData,
-    ChatMsg,
-    EntityUnload,
-    OpenStorage,
-    OpenShop,
-    ClearIsUsingType,
-    UpdateTradeItem,
-    UpdateTradeMoney,
-    InitTrade,
-    TradeStatus,
-    TradeRequest,
-    PlayItemSfx,
-    Damage,
-    Ping,
-    TlsHandShake,
-    ClearData,
+/// Declares a packet enum whose variants are sent as their fixed u16 id rather than
+/// their position, so moving or adding variants never changes what a client receives.
+/// Ids are never reused. New packets take the next free id and old ones keep theirs
+/// even once removed.
+macro_rules! packet_ids {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($variant:ident = $id:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr(u16)]
+        pub enum $name {
+            $($variant = $id,)*
+        }
+
+        impl $name {
+            #[inline]
+            pub fn id(self) -> u16 {
+                self as u16
+            }
+
+            /// The packet for an id read off the wire or None when the id is unknown.
+            pub fn from_id(id: u16) -> Option<Self> {
+                match id {
+                    $($id => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl ByteBufferWrite for $name {
+            fn write_to_bytey_buffer(&self, buffer: &mut ByteBuffer) -> bytey::Result<()> {
+                self.id().write_to_bytey_buffer(buffer)
+            }
+
+            fn write_to_bytey_buffer_le(&self, buffer: &mut ByteBuffer) -> bytey::Result<()> {
+                self.id().write_to_bytey_buffer_le(buffer)
+            }
+
+            fn write_to_bytey_buffer_be(&self, buffer: &mut ByteBuffer) -> bytey::Result<()> {
+                self.id().write_to_bytey_buffer_be(buffer)
+            }
+        }
+
+        impl MByteBufferWrite for $name {
+            fn write_to_mbuffer(&self, buffer: &mut MByteBuffer) -> mmap_bytey::Result<()> {
+                self.id().write_to_mbuffer(buffer)
+            }
+
+            fn write_to_mbuffer_le(&self, buffer: &mut MByteBuffer) -> mmap_bytey::Result<()> {
+                self.id().write_to_mbuffer_le(buffer)
+            }
+
+            fn write_to_mbuffer_be(&self, buffer: &mut MByteBuffer) -> mmap_bytey::Result<()> {
+                self.id().write_to_mbuffer_be(buffer)
+            }
+        }
+    };
+}
+
+packet_ids! {
+    #[derive(
+        Copy,
+        Clone,
+        Debug,
+        PartialEq,
+        Eq,
+        Serialize,
+        Deserialize,
+        Hash,
+    )]
+    pub enum ServerPackets {
+        OnlineCheck = 0,
+        AlertMsg = 1,
+        FltAlert = 2,
+        HandShake = 3,
+        LoginOk = 4,
+        MapItems = 5,
+        MyIndex = 6,
+        Move = 7,
+        Warp = 8,
+        Dir = 9,
+        Vitals = 10,
+        Attack = 11,
+        Death = 12,
+        PlayerData = 13,
+        PlayerSpawn = 14,
+        PlayerInv = 15,
+        PlayerInvSlot = 16,
+        PlayerStorage = 17,
+        PlayerStorageSlot = 18,
+        PlayerEquipment = 19,
+        PlayerLevel = 20,
+        PlayerMoney = 21,
+        PlayerPk = 22,
+        NpcData = 23,
+        ChatMsg = 24,
+        EntityUnload = 25,
+        OpenStorage = 26,
+        OpenShop = 27,
+        ClearIsUsingType = 28,
+        UpdateTradeItem = 29,
+        UpdateTradeMoney = 30,
+        InitTrade = 31,
+        TradeStatus = 32,
+        TradeRequest = 33,
+        PlayItemSfx = 34,
+        Damage = 35,
+        Ping = 36,
+        TlsHandShake = 37,
+        ClearData = 38,
+        PartyInvite = 39,
+        PartyData = 40,
+        PartyVitals = 41,
+        PartyLeave = 42,
+        GuildData = 43,
+        GuildInvite = 44,
+        GuildMotd = 45,
+        GuildLeave = 46,
+        QuestList = 47,
+        QuestProgress = 48,
+        QuestComplete = 49,
+        QuestAbandon = 50,
+        Hotbar = 51,
+        SkillCooldown = 52,
+        Cast = 53,
+        StatusEffects = 54,
+        CharacterList = 55,
+        Attributes = 56,
+        PlayerPvp = 57,
+        ProtocolAccept = 58,
+        ProtocolReject = 59,
+    }
 }
 
-#[derive(
-    Copy,
-    Clone,
-    Debug,
-    PartialEq,
-    Eq,
-    Serialize,
-    Deserialize,
-    ByteBufferRead,
-    ByteBufferWrite,
-    Hash,
-    MByteBufferRead,
-    MByteBufferWrite,
-)]
-pub enum ClientPacket {
-    OnlineCheck,
-    Register,
-    Login,
-    HandShake,
-    Move,
-    Dir,
-    Attack,
-    UseItem,
-    Unequip,
-    SwitchInvSlot,
-    PickUp,
-    DropItem,
-    DeleteItem,
-    SwitchStorageSlot,
-    DeleteStorageItem,
-    DepositItem,
-    WithdrawItem,
-    Message,
-    Command,
-    SetTarget,
-    CloseStorage,
-    CloseShop,
-    CloseTrade,
-    BuyItem,
-    SellItem,
-    AddTradeItem,
-    RemoveTradeItem,
-    UpdateTradeMoney,
-    SubmitTrade,
-    AcceptTrade,
-    DeclineTrade,
-    Ping,
-    TlsReconnect,
-    TlsHandShake,
-    Reconnect,
-    Disconnect,
-    LoginOk,
+packet_ids! {
+    #[derive(
+        Copy,
+        Clone,
+        Debug,
+        PartialEq,
+        Eq,
+        Serialize,
+        Deserialize,
+        Hash,
+    )]
+    pub enum ClientPacket {
+        OnlineCheck = 0,
+        Register = 1,
+        Login = 2,
+        HandShake = 3,
+        Move = 4,
+        Dir = 5,
+        Attack = 6,
+        UseItem = 7,
+        Unequip = 8,
+        SwitchInvSlot = 9,
+        PickUp = 10,
+        DropItem = 11,
+        DeleteItem = 12,
+        SwitchStorageSlot = 13,
+        DeleteStorageItem = 14,
+        DepositItem = 15,
+        WithdrawItem = 16,
+        Message = 17,
+        Command = 18,
+        SetTarget = 19,
+        CloseStorage = 20,
+        CloseShop = 21,
+        CloseTrade = 22,
+        BuyItem = 23,
+        SellItem = 24,
+        AddTradeItem = 25,
+        RemoveTradeItem = 26,
+        UpdateTradeMoney = 27,
+        SubmitTrade = 28,
+        AcceptTrade = 29,
+        DeclineTrade = 30,
+        Ping = 31,
+        TlsReconnect = 32,
+        TlsHandShake = 33,
+        Reconnect = 34,
+        Disconnect = 35,
+        LoginOk = 36,
+        PartyInvite = 37,
+        PartyAccept = 38,
+        PartyDecline = 39,
+        PartyLeave = 40,
+        PartyKick = 41,
+        PartyLeader = 42,
+        GuildCreate = 43,
+        GuildInvite = 44,
+        GuildAccept = 45,
+        GuildDecline = 46,
+        GuildLeave = 47,
+        GuildKick = 48,
+        GuildSetRank = 49,
+        GuildSetMotd = 50,
+        GuildDisband = 51,
+        QuestAccept = 52,
+        QuestAbandon = 53,
+        QuestTalk = 54,
+        CastSkill = 55,
+        SetHotbar = 56,
+        RequestPasswordReset = 57,
+        ResetPassword = 58,
+        CreateCharacter = 59,
+        DeleteCharacter = 60,
+        SelectCharacter = 61,
+        AllocatePoints = 62,
+        SetPvp = 63,
+        ProtocolHello = 64,
+    }
 }
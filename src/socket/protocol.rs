@@ -0,0 +1,68 @@
+use crate::{containers::Storage, gametypes::*, socket::*};
+
+/// Raised whenever a packet's layout changes or a packet id is added.
+pub const PROTOCOL_VERSION: u16 = 1;
+/// The oldest protocol this server still speaks. Older clients are rejected in ProtocolHello.
+pub const MIN_PROTOCOL_VERSION: u16 = 1;
+
+// Optional features a client can ask for in ProtocolHello. Only the bits both sides
+// set are enabled for that client. Add a bit only along with the code it gates.
+pub const FEATURE_ATTRIBUTES: u32 = 1 << 1;
+pub const SERVER_FEATURES: u32 = FEATURE_ATTRIBUTES;
+
+/// What a client and the server agreed on in ProtocolHello.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Protocol {
+    pub version: u16,
+    pub features: u32,
+}
+
+impl Protocol {
+    /// Settles on the older of the two versions and the features both sides know.
+    pub fn negotiate(version: u16, features: u32) -> Self {
+        Self {
+            version: version.min(PROTOCOL_VERSION),
+            features: features & SERVER_FEATURES,
+        }
+    }
+
+    #[inline]
+    pub fn has(&self, feature: u32) -> bool {
+        self.features & feature == feature
+    }
+}
+
+/// What the client negotiated or None if it has not sent ProtocolHello yet.
+pub fn client_protocol(storage: &Storage, socket_id: usize) -> Option<Protocol> {
+    storage
+        .server
+        .borrow()
+        .clients
+        .get(socket_id)
+        .and_then(|client| client.borrow().protocol)
+}
+
+pub fn send_protocol_accept(storage: &Storage, socket_id: usize, protocol: Protocol) -> Result<()> {
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::ProtocolAccept)?;
+    buf.write(protocol.version)?;
+    buf.write(protocol.features)?;
+    buf.finish()?;
+
+    send_to(storage, socket_id, buf)
+}
+
+/// Tells the client why it was turned away along with the versions the server accepts
+/// so it can show the message and disconnect.
+pub fn send_protocol_reject(storage: &Storage, socket_id: usize, message: String) -> Result<()> {
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::ProtocolReject)?;
+    buf.write(MIN_PROTOCOL_VERSION)?;
+    buf.write(PROTOCOL_VERSION)?;
+    buf.write(message)?;
+    buf.finish()?;
+
+    send_to(storage, socket_id, buf)
+}
@@ -0,0 +1,169 @@
+use crate::{containers::HashMap, gametypes::*, socket::ClientPacket, sql::get_time_left};
+use serde::Deserialize;
+use time::Instant;
+
+/// A token bucket. A packet costs one token, the bucket holds at most burst tokens
+/// and refills at per_second tokens a second.
+#[derive(Copy, Clone, Debug, Deserialize)]
+pub struct RateLimit {
+    pub burst: f64,
+    pub per_second: f64,
+}
+
+/// The [rate_limits] section of settings.toml.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RateLimits {
+    /// Used for any packet without its own entry.
+    pub default: RateLimit,
+    /// Limits for single packets keyed by their ClientPacket name, such as Move or Message.
+    pub packets: HashMap<ClientPacket, RateLimit>,
+    /// Dropped packets a client gets away with before it is warned.
+    pub warn_after: u32,
+    /// Dropped packets before the client is disconnected.
+    pub disconnect_after: u32,
+    /// Milliseconds without a dropped packet before the count starts over.
+    pub forgive_ms: i64,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        let packets = [
+            (ClientPacket::Move, 20.0, 10.0),
+            (ClientPacket::Dir, 20.0, 10.0),
+            (ClientPacket::Attack, 10.0, 5.0),
+            (ClientPacket::CastSkill, 10.0, 5.0),
+            (ClientPacket::Message, 5.0, 1.0),
+            (ClientPacket::Login, 3.0, 0.2),
+            (ClientPacket::Register, 3.0, 0.2),
+            (ClientPacket::ProtocolHello, 3.0, 0.2),
+            (ClientPacket::RequestPasswordReset, 2.0, 0.1),
+        ];
+
+        Self {
+            default: RateLimit {
+                burst: 30.0,
+                per_second: 15.0,
+            },
+            packets: packets
+                .into_iter()
+                .map(|(packet, burst, per_second)| (packet, RateLimit { burst, per_second }))
+                .collect(),
+            warn_after: 10,
+            disconnect_after: 50,
+            forgive_ms: 10000,
+        }
+    }
+}
+
+impl RateLimits {
+    pub fn get(&self, packet: ClientPacket) -> RateLimit {
+        self.packets.get(&packet).copied().unwrap_or(self.default)
+    }
+
+    /// Rejects limits that could never let a packet through or never warn before
+    /// disconnecting. file is only used in the error.
+    pub fn validate(&self, file: &str) -> Result<()> {
+        let invalid = |message: String| {
+            Err(AscendingError::InvalidData {
+                file: file.into(),
+                message,
+            })
+        };
+
+        let limits = std::iter::once(("default".to_string(), self.default)).chain(
+            self.packets
+                .iter()
+                .map(|(packet, limit)| (format!("{packet:?}"), *limit)),
+        );
+
+        for (name, limit) in limits {
+            if limit.burst.is_nan() || limit.burst < 1.0 {
+                return invalid(format!("rate_limits {name} burst must be at least 1"));
+            }
+
+            if limit.per_second.is_nan() || limit.per_second <= 0.0 {
+                return invalid(format!("rate_limits {name} per_second must be above 0"));
+            }
+        }
+
+        if self.warn_after >= self.disconnect_after {
+            return invalid("rate_limits warn_after must be below disconnect_after".into());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RateVerdict {
+    Allow,
+    /// The first packet dropped since the client last went over its limits.
+    Throttle,
+    Drop,
+    /// Dropped and the client should be told to slow down. Only given once per flood.
+    Warn,
+    Disconnect,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Each client's buckets and how many packets have been dropped in the current flood.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimiter {
+    buckets: HashMap<ClientPacket, Bucket>,
+    dropped: u32,
+    warned: bool,
+    last_drop: Option<Instant>,
+}
+
+impl RateLimiter {
+    /// Takes a token for the packet and decides what to do with it.
+    pub fn check(
+        &mut self,
+        limits: &RateLimits,
+        packet: ClientPacket,
+        tick: Instant,
+    ) -> RateVerdict {
+        let limit = limits.get(packet);
+        let bucket = self.buckets.entry(packet).or_insert(Bucket {
+            tokens: limit.burst,
+            updated: tick,
+        });
+        let elapsed = get_time_left(tick, bucket.updated) as f64 / 1000.0;
+
+        bucket.tokens = (bucket.tokens + elapsed * limit.per_second).min(limit.burst);
+        bucket.updated = tick;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return RateVerdict::Allow;
+        }
+
+        if self
+            .last_drop
+            .is_some_and(|last| get_time_left(tick, last) > limits.forgive_ms)
+        {
+            self.dropped = 0;
+            self.warned = false;
+        }
+
+        self.dropped += 1;
+        self.last_drop = Some(tick);
+
+        if self.dropped >= limits.disconnect_after {
+            RateVerdict::Disconnect
+        } else if self.dropped >= limits.warn_after && !self.warned {
+            self.warned = true;
+            RateVerdict::Warn
+        } else if self.dropped == 1 {
+            RateVerdict::Throttle
+        } else {
+            RateVerdict::Drop
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use crate::{
-    containers::{Entity, GlobalKey, Storage, TradeStatus, World},
+    containers::{Entity, GlobalKey, PartyKey, Storage, TradeStatus, UserAccess, Vitals, World},
     gametypes::*,
     socket::*,
+    sql::PGCharacter,
     tasks::*,
 };
 use std::ops::Range;
+use uuid::Uuid;
 
 #[inline]
 pub fn send_infomsg(
@@ -243,6 +245,32 @@ pub fn send_level(world: &mut World, storage: &Storage, entity: GlobalKey) -> Re
         buf.write(ServerPackets::PlayerLevel)?;
         buf.write(data.combat.level)?;
         buf.write(data.general.levelexp)?;
+        buf.write(storage.bases.progression.next_level_exp(data.combat.level))?;
+        buf.finish()?;
+
+        send_to(storage, data.socket.id, buf)?;
+    }
+    Ok(())
+}
+
+/// Sends the points spent on each attribute, the unspent points and the damage and
+/// defense they add up to.
+#[inline]
+pub fn send_attributes(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    if let Some(Entity::Player(data)) = world.get_opt_entity(entity) {
+        let data = data.try_lock()?;
+
+        if !client_protocol(storage, data.socket.id).is_some_and(|p| p.has(FEATURE_ATTRIBUTES)) {
+            return Ok(());
+        }
+
+        let mut buf = MByteBuffer::new_packet()?;
+
+        buf.write(ServerPackets::Attributes)?;
+        buf.write(data.attributes.values)?;
+        buf.write(data.attributes.points)?;
+        buf.write(data.combat.physical.damage)?;
+        buf.write(data.combat.physical.defense)?;
         buf.finish()?;
 
         send_to(storage, data.socket.id, buf)?;
@@ -294,6 +322,34 @@ pub fn send_pk(
     Ok(())
 }
 
+#[inline]
+pub fn send_pvp(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    toself: bool,
+) -> Result<()> {
+    if let Some(Entity::Player(data)) = world.get_opt_entity(entity) {
+        let data = data.try_lock()?;
+
+        let mut buf = MByteBuffer::new_packet()?;
+        let closure = |toself, id| if toself { Some(id) } else { None };
+
+        buf.write(ServerPackets::PlayerPvp)?;
+        buf.write(data.general.pvpon)?;
+        buf.finish()?;
+
+        send_to_maps(
+            world,
+            storage,
+            data.movement.pos.map,
+            buf,
+            closure(toself, entity),
+        )?;
+    }
+    Ok(())
+}
+
 #[inline]
 pub fn send_message(
     world: &mut World,
@@ -314,7 +370,24 @@ pub fn send_message(
                 .add_task(storage, message_packet(chan, head, msg, Some(access))?)?,
             MessageChannel::Global => DataTaskToken::GlobalChat
                 .add_task(storage, message_packet(chan, head, msg, Some(access))?)?,
-            MessageChannel::Party | MessageChannel::Trade | MessageChannel::Help => {}
+            MessageChannel::Party | MessageChannel::Guild => {
+                let (party, guild) = (data.party.party, data.guild.guild_id());
+
+                // Unlock so our own socket can be looked up along with everyone else's.
+                drop(data);
+
+                let recipients = match chan {
+                    MessageChannel::Party => party.map(|party| party_recipients(storage, party)),
+                    _ => guild
+                        .map(|guild| guild_recipients(world, storage, guild))
+                        .transpose()?,
+                };
+
+                if let Some(recipients) = recipients {
+                    send_chat_to(world, storage, &recipients, chan, head, msg, access)?;
+                }
+            }
+            MessageChannel::Trade | MessageChannel::Help => {}
             MessageChannel::Private => {
                 let mut buf = MByteBuffer::new_packet()?;
                 buf.write(ServerPackets::ChatMsg)?;
@@ -330,7 +403,6 @@ pub fn send_message(
                 }
                 send_to(storage, data.socket.id, buf)?;
             }
-            MessageChannel::Guild => {}
             MessageChannel::Quest | MessageChannel::Npc => {
                 let mut buf = MByteBuffer::new_packet()?;
 
@@ -349,6 +421,29 @@ pub fn send_message(
     Ok(())
 }
 
+/// Sends a chat line to each of the recipients that is online.
+fn send_chat_to(
+    world: &mut World,
+    storage: &Storage,
+    recipients: &[GlobalKey],
+    chan: MessageChannel,
+    head: String,
+    msg: String,
+    access: UserAccess,
+) -> Result<()> {
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::ChatMsg)?;
+    buf.write(1_u32)?;
+    buf.write(chan)?;
+    buf.write(head)?;
+    buf.write(msg)?;
+    buf.write(Some(access))?;
+    buf.finish()?;
+
+    send_to_entities(world, storage, recipients, buf)
+}
+
 #[inline]
 pub fn send_openstorage(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
     let socket_id = if let Some(Entity::Player(data)) = world.get_opt_entity(entity) {
@@ -606,3 +701,458 @@ pub fn send_clear_data(world: &mut World, storage: &Storage, entity: GlobalKey)
 
     send_to(storage, socket_id, buf)
 }
+
+/// Sends a packet to every online member of a party.
+pub fn send_to_party(
+    world: &mut World,
+    storage: &Storage,
+    party: PartyKey,
+    buf: MByteBuffer,
+) -> Result<()> {
+    let members = party_recipients(storage, party);
+
+    send_to_entities(world, storage, &members, buf)
+}
+
+fn party_recipients(storage: &Storage, party: PartyKey) -> Vec<GlobalKey> {
+    storage
+        .parties
+        .borrow()
+        .get(party)
+        .map(|party| party.member_list())
+        .unwrap_or_default()
+}
+
+#[inline]
+pub fn send_partyinvite(
+    world: &mut World,
+    storage: &Storage,
+    target_entity: GlobalKey,
+    entity: GlobalKey,
+    name: String,
+) -> Result<()> {
+    let socket_id = if let Some(Entity::Player(data)) = world.get_opt_entity(target_entity) {
+        data.try_lock()?.socket.id
+    } else {
+        return Ok(());
+    };
+
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::PartyInvite)?;
+    buf.write(entity)?;
+    buf.write(name)?;
+    buf.finish()?;
+
+    send_to(storage, socket_id, buf)
+}
+
+/// Sends the leader and full member list to everyone in the party.
+pub fn send_partydata(world: &mut World, storage: &Storage, party: PartyKey) -> Result<()> {
+    let (leader, members) = match storage.parties.borrow().get(party) {
+        Some(party) => (party.leader, party.member_list()),
+        None => return Ok(()),
+    };
+
+    let mut member_data = Vec::with_capacity(members.len());
+
+    for member in members {
+        if let Some(Entity::Player(data)) = world.get_opt_entity(member) {
+            let data = data.try_lock()?;
+
+            member_data.push((
+                member,
+                data.account.username.clone(),
+                data.combat.level,
+                data.combat.vitals,
+            ));
+        }
+    }
+
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::PartyData)?;
+    buf.write(leader)?;
+    buf.write(member_data.len() as u32)?;
+
+    for (member, name, level, vitals) in member_data {
+        buf.write(member)?;
+        buf.write(name)?;
+        buf.write(level)?;
+        buf.write(vitals.vital)?;
+        buf.write(vitals.vitalmax)?;
+    }
+
+    buf.finish()?;
+
+    send_to_party(world, storage, party, buf)
+}
+
+#[inline]
+pub fn send_partyvitals(
+    world: &mut World,
+    storage: &Storage,
+    party: PartyKey,
+    entity: GlobalKey,
+    vitals: Vitals,
+) -> Result<()> {
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::PartyVitals)?;
+    buf.write(entity)?;
+    buf.write(vitals.vital)?;
+    buf.write(vitals.vitalmax)?;
+    buf.finish()?;
+
+    send_to_party(world, storage, party, buf)
+}
+
+#[inline]
+pub fn send_partyleave(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let socket_id = if let Some(Entity::Player(data)) = world.get_opt_entity(entity) {
+        data.try_lock()?.socket.id
+    } else {
+        return Ok(());
+    };
+
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::PartyLeave)?;
+    buf.write(1_u32)?;
+    buf.finish()?;
+
+    send_to(storage, socket_id, buf)
+}
+
+/// Sends a packet to every online member of a guild.
+pub fn send_to_guild(
+    world: &mut World,
+    storage: &Storage,
+    guild: Uuid,
+    buf: MByteBuffer,
+) -> Result<()> {
+    let members = guild_recipients(world, storage, guild)?;
+
+    send_to_entities(world, storage, &members, buf)
+}
+
+// Guild members are not tracked while online so every player is checked.
+fn guild_recipients(world: &mut World, storage: &Storage, guild: Uuid) -> Result<Vec<GlobalKey>> {
+    let players: Vec<GlobalKey> = storage.player_ids.borrow().iter().copied().collect();
+    let mut members = Vec::new();
+
+    for player in players {
+        if let Some(Entity::Player(data)) = world.get_opt_entity(player)
+            && data.try_lock()?.guild.guild_id() == Some(guild)
+        {
+            members.push(player);
+        }
+    }
+
+    Ok(members)
+}
+
+/// Sends the player their guild, rank list and message of the day.
+/// Players without a guild are told to clear it instead.
+pub fn send_guilddata(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let (socket_id, membership) = if let Some(Entity::Player(data)) = world.get_opt_entity(entity) {
+        let data = data.try_lock()?;
+
+        (data.socket.id, data.guild.membership.clone())
+    } else {
+        return Ok(());
+    };
+
+    let guild = membership
+        .as_ref()
+        .and_then(|membership| storage.guilds.borrow().get(&membership.id).cloned());
+
+    let (Some(membership), Some(guild)) = (membership, guild) else {
+        return send_guildleave(world, storage, entity);
+    };
+
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::GuildData)?;
+    buf.write(guild.name)?;
+    buf.write(guild.tag)?;
+    buf.write(guild.motd)?;
+    buf.write(membership.rank)?;
+    buf.write(guild.ranks.len() as u32)?;
+
+    for rank in guild.ranks {
+        buf.write(rank.rank)?;
+        buf.write(rank.name)?;
+        buf.write(rank.permissions.0)?;
+    }
+
+    buf.finish()?;
+
+    send_to(storage, socket_id, buf)
+}
+
+#[inline]
+pub fn send_guildinvite(
+    world: &mut World,
+    storage: &Storage,
+    target_entity: GlobalKey,
+    name: String,
+    guild_name: String,
+) -> Result<()> {
+    let socket_id = if let Some(Entity::Player(data)) = world.get_opt_entity(target_entity) {
+        data.try_lock()?.socket.id
+    } else {
+        return Ok(());
+    };
+
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::GuildInvite)?;
+    buf.write(name)?;
+    buf.write(guild_name)?;
+    buf.finish()?;
+
+    send_to(storage, socket_id, buf)
+}
+
+#[inline]
+pub fn send_guildmotd(
+    world: &mut World,
+    storage: &Storage,
+    guild: Uuid,
+    motd: String,
+) -> Result<()> {
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::GuildMotd)?;
+    buf.write(motd)?;
+    buf.finish()?;
+
+    send_to_guild(world, storage, guild, buf)
+}
+
+/// Sends a system message to everyone online in the guild.
+pub fn send_guildnotice(
+    world: &mut World,
+    storage: &Storage,
+    guild: Uuid,
+    msg: String,
+) -> Result<()> {
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::ChatMsg)?;
+    buf.write(1_u32)?;
+    buf.write(MessageChannel::Guild)?;
+    buf.write(String::from("[Guild]"))?;
+    buf.write(msg)?;
+    buf.write(None::<UserAccess>)?;
+    buf.finish()?;
+
+    send_to_guild(world, storage, guild, buf)
+}
+
+#[inline]
+pub fn send_guildleave(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let socket_id = if let Some(Entity::Player(data)) = world.get_opt_entity(entity) {
+        data.try_lock()?.socket.id
+    } else {
+        return Ok(());
+    };
+
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::GuildLeave)?;
+    buf.write(1_u32)?;
+    buf.finish()?;
+
+    send_to(storage, socket_id, buf)
+}
+
+#[inline]
+pub fn send_questlist(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let (socket_id, quests) = if let Some(Entity::Player(data)) = world.get_opt_entity(entity) {
+        let data = data.try_lock()?;
+
+        (data.socket.id, data.quests.clone())
+    } else {
+        return Ok(());
+    };
+
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::QuestList)?;
+    buf.write(quests.active.len() as u32)?;
+
+    for (quest, progress) in quests.active {
+        buf.write(quest)?;
+        buf.write(progress.len() as u32)?;
+
+        for val in progress {
+            buf.write(val)?;
+        }
+    }
+
+    buf.write(quests.completed.len() as u32)?;
+
+    for (quest, times) in quests.completed {
+        buf.write(quest)?;
+        buf.write(times)?;
+    }
+
+    buf.finish()?;
+
+    send_to(storage, socket_id, buf)
+}
+
+#[inline]
+pub fn send_questprogress(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    quest: u16,
+) -> Result<()> {
+    let (socket_id, progress) = if let Some(Entity::Player(data)) = world.get_opt_entity(entity) {
+        let data = data.try_lock()?;
+
+        (data.socket.id, data.quests.active.get(&quest).cloned())
+    } else {
+        return Ok(());
+    };
+
+    let Some(progress) = progress else {
+        return Ok(());
+    };
+
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::QuestProgress)?;
+    buf.write(quest)?;
+    buf.write(progress.len() as u32)?;
+
+    for val in progress {
+        buf.write(val)?;
+    }
+
+    buf.finish()?;
+
+    send_to(storage, socket_id, buf)
+}
+
+#[inline]
+pub fn send_questcomplete(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    quest: u16,
+) -> Result<()> {
+    let (socket_id, times) = if let Some(Entity::Player(data)) = world.get_opt_entity(entity) {
+        let data = data.try_lock()?;
+
+        (data.socket.id, data.quests.times_completed(quest))
+    } else {
+        return Ok(());
+    };
+
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::QuestComplete)?;
+    buf.write(quest)?;
+    buf.write(times)?;
+    buf.finish()?;
+
+    send_to(storage, socket_id, buf)
+}
+
+#[inline]
+pub fn send_questabandon(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    quest: u16,
+) -> Result<()> {
+    let socket_id = if let Some(Entity::Player(data)) = world.get_opt_entity(entity) {
+        data.try_lock()?.socket.id
+    } else {
+        return Ok(());
+    };
+
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::QuestAbandon)?;
+    buf.write(quest)?;
+    buf.finish()?;
+
+    send_to(storage, socket_id, buf)
+}
+
+#[inline]
+pub fn send_hotbar(world: &mut World, storage: &Storage, entity: GlobalKey) -> Result<()> {
+    let (socket_id, hotbar) = if let Some(Entity::Player(data)) = world.get_opt_entity(entity) {
+        let data = data.try_lock()?;
+
+        (data.socket.id, data.skills.hotbar)
+    } else {
+        return Ok(());
+    };
+
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::Hotbar)?;
+    buf.write(hotbar.len() as u32)?;
+
+    for skill in hotbar {
+        buf.write(skill)?;
+    }
+
+    buf.finish()?;
+
+    send_to(storage, socket_id, buf)
+}
+
+#[inline]
+pub fn send_skillcooldown(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    skill: u16,
+    cooldown: i64,
+) -> Result<()> {
+    let socket_id = if let Some(Entity::Player(data)) = world.get_opt_entity(entity) {
+        data.try_lock()?.socket.id
+    } else {
+        return Ok(());
+    };
+
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::SkillCooldown)?;
+    buf.write(skill)?;
+    buf.write(cooldown)?;
+    buf.finish()?;
+
+    send_to(storage, socket_id, buf)
+}
+
+#[inline]
+pub fn send_characterlist(
+    storage: &Storage,
+    socket_id: usize,
+    characters: &[PGCharacter],
+) -> Result<()> {
+    let mut buf = MByteBuffer::new_packet()?;
+
+    buf.write(ServerPackets::CharacterList)?;
+    buf.write(MAX_CHARACTERS as u8)?;
+    buf.write(characters.len() as u8)?;
+
+    for character in characters {
+        buf.write(character.slot as u8)?;
+        buf.write(&character.name)?;
+        buf.write(character.sprite())?;
+        buf.write(character.level)?;
+    }
+
+    buf.finish()?;
+
+    send_to(storage, socket_id, buf)
+}
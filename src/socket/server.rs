@@ -77,6 +77,12 @@ impl Server {
                 }
             };
 
+            if storage.ip_bans.borrow().is_banned(addr.ip().to_canonical()) {
+                trace!("Refused connection from banned IP: {addr}");
+                drop(stream);
+                continue;
+            }
+
             if !is_tls {
                 stream.set_nodelay(true)?;
             }
@@ -114,6 +120,13 @@ impl Server {
         Ok(())
     }
 
+    /// Stops both listeners from receiving any new connections.
+    pub fn stop_listening(&mut self, poll: &Poll) -> Result<()> {
+        poll.registry().deregister(&mut self.listener)?;
+        poll.registry().deregister(&mut self.tls_listener)?;
+        Ok(())
+    }
+
     #[inline]
     pub fn remove(&mut self, token: mio::Token) {
         let key = token.0 - CLIENT_OFFSET;
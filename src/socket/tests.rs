@@ -0,0 +1,184 @@
+//! Checks for the per client packet rate limiting. These need no connection.
+
+use super::*;
+use crate::containers::HashMap;
+use chrono::Duration;
+use time::Instant;
+
+fn limits() -> RateLimits {
+    RateLimits {
+        default: RateLimit {
+            burst: 2.0,
+            per_second: 1.0,
+        },
+        packets: HashMap::default(),
+        warn_after: 3,
+        disconnect_after: 5,
+        forgive_ms: 1000,
+    }
+}
+
+fn after(tick: Instant, ms: i64) -> Instant {
+    tick + Duration::try_milliseconds(ms).unwrap_or_default()
+}
+
+#[test]
+fn rate_limit_refills_up_to_burst() {
+    let limits = limits();
+    let mut limiter = RateLimiter::default();
+    let tick = Instant::recent();
+
+    assert_eq!(
+        limiter.check(&limits, ClientPacket::Move, tick),
+        RateVerdict::Allow
+    );
+    assert_eq!(
+        limiter.check(&limits, ClientPacket::Move, tick),
+        RateVerdict::Allow
+    );
+    assert_eq!(
+        limiter.check(&limits, ClientPacket::Move, tick),
+        RateVerdict::Throttle
+    );
+
+    // Each packet has its own bucket.
+    assert_eq!(
+        limiter.check(&limits, ClientPacket::Dir, tick),
+        RateVerdict::Allow
+    );
+
+    // One second at one token a second buys a single packet.
+    let tick = after(tick, 1000);
+
+    assert_eq!(
+        limiter.check(&limits, ClientPacket::Move, tick),
+        RateVerdict::Allow
+    );
+    assert_eq!(
+        limiter.check(&limits, ClientPacket::Move, tick),
+        RateVerdict::Drop
+    );
+
+    // A long wait only fills the bucket back up to burst.
+    let tick = after(tick, 60000);
+
+    assert_eq!(
+        limiter.check(&limits, ClientPacket::Move, tick),
+        RateVerdict::Allow
+    );
+    assert_eq!(
+        limiter.check(&limits, ClientPacket::Move, tick),
+        RateVerdict::Allow
+    );
+    assert_ne!(
+        limiter.check(&limits, ClientPacket::Move, tick),
+        RateVerdict::Allow
+    );
+}
+
+#[test]
+fn rate_limit_escalates_then_forgives() {
+    let limits = limits();
+    let mut limiter = RateLimiter::default();
+    let tick = Instant::recent();
+
+    for _ in 0..2 {
+        assert_eq!(
+            limiter.check(&limits, ClientPacket::Move, tick),
+            RateVerdict::Allow
+        );
+    }
+
+    let flood = [
+        RateVerdict::Throttle,
+        RateVerdict::Drop,
+        RateVerdict::Warn,
+        RateVerdict::Drop,
+    ];
+
+    for verdict in flood {
+        assert_eq!(limiter.check(&limits, ClientPacket::Move, tick), verdict);
+    }
+
+    // Going quiet for longer than forgive_ms starts the count and the warning over.
+    let tick = after(tick, 2000);
+
+    for _ in 0..2 {
+        assert_eq!(
+            limiter.check(&limits, ClientPacket::Move, tick),
+            RateVerdict::Allow
+        );
+    }
+
+    for verdict in flood {
+        assert_eq!(limiter.check(&limits, ClientPacket::Move, tick), verdict);
+    }
+
+    assert_eq!(
+        limiter.check(&limits, ClientPacket::Move, tick),
+        RateVerdict::Disconnect
+    );
+}
+
+#[test]
+fn rate_limit_warns_when_warn_after_is_first_drop() {
+    let limits = RateLimits {
+        warn_after: 0,
+        ..limits()
+    };
+    let mut limiter = RateLimiter::default();
+    let tick = Instant::recent();
+
+    for _ in 0..2 {
+        assert_eq!(
+            limiter.check(&limits, ClientPacket::Move, tick),
+            RateVerdict::Allow
+        );
+    }
+
+    assert_eq!(
+        limiter.check(&limits, ClientPacket::Move, tick),
+        RateVerdict::Warn
+    );
+    assert_eq!(
+        limiter.check(&limits, ClientPacket::Move, tick),
+        RateVerdict::Drop
+    );
+}
+
+#[test]
+fn rate_limits_reject_bad_config() {
+    assert!(RateLimits::default().validate("settings.toml").is_ok());
+    assert!(limits().validate("settings.toml").is_ok());
+
+    let bad_limit = |limit: RateLimit| {
+        let mut limits = limits();
+
+        limits.packets.insert(ClientPacket::Move, limit);
+        limits.validate("settings.toml").is_err()
+    };
+
+    assert!(bad_limit(RateLimit {
+        burst: 0.5,
+        per_second: 1.0,
+    }));
+    assert!(bad_limit(RateLimit {
+        burst: 2.0,
+        per_second: 0.0,
+    }));
+    assert!(bad_limit(RateLimit {
+        burst: 2.0,
+        per_second: -1.0,
+    }));
+    assert!(bad_limit(RateLimit {
+        burst: f64::NAN,
+        per_second: 1.0,
+    }));
+
+    let equal = RateLimits {
+        warn_after: 5,
+        ..limits()
+    };
+
+    assert!(equal.validate("settings.toml").is_err());
+}
@@ -1,13 +1,24 @@
+mod audit;
 mod integers;
+mod login;
 mod logstruct;
+mod lookup;
+mod migrations;
 mod queries;
 mod schema;
 mod schema_enums;
 mod schema_structs;
+#[cfg(test)]
+mod tests;
 mod updater;
+mod worker;
 
+pub use audit::*;
+pub use login::*;
 #[allow(unused_imports)]
 pub use logstruct::PGLog;
+pub use lookup::*;
+pub use migrations::*;
 pub use queries::*;
 #[allow(unused_imports)]
 pub use schema::*;
@@ -16,3 +27,4 @@ pub use schema_enums::*;
 #[allow(unused_imports)]
 pub use schema_structs::*;
 pub use updater::*;
+pub use worker::*;
@@ -0,0 +1,78 @@
+use crate::{
+    containers::{Entity, GlobalKey, Storage, World},
+    gametypes::*,
+    sql::{PGLog, SaveJob},
+};
+
+/// Queues a row for the logs table. The save worker writes queued rows in batches.
+pub fn add_log(storage: &Storage, log: PGLog) -> Result<()> {
+    storage.saves.save(SaveJob::Logs { logs: vec![log] })
+}
+
+/// Builds a log row for something the player did, tagged with their account and address.
+pub fn player_log_entry(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    logtype: LogType,
+    message: String,
+) -> Result<Option<PGLog>> {
+    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        let p_data = p_data.try_lock()?;
+
+        Ok(Some(PGLog::new(
+            storage.config.server_id,
+            p_data.account.id,
+            logtype,
+            format!("{}: {message}", p_data.account.username),
+            p_data.socket.addr.to_string(),
+        )))
+    } else {
+        Ok(None)
+    }
+}
+
+pub fn player_log(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    logtype: LogType,
+    message: String,
+) -> Result<()> {
+    match player_log_entry(world, storage, entity, logtype, message)? {
+        Some(log) => add_log(storage, log),
+        None => Ok(()),
+    }
+}
+
+/// Describes an amount of an item for log messages, e.g. "5 x Potion (#3)".
+pub fn item_log_text(storage: &Storage, num: u32, amount: u64) -> String {
+    let name = storage
+        .bases
+        .items
+        .get(num as usize)
+        .map(|item| item.name.as_str())
+        .unwrap_or_default();
+
+    format!("{amount} x {name} (#{num})")
+}
+
+/// Logs an item leaving or entering the player's hands, such as a drop or deposit.
+pub fn player_item_log(
+    world: &mut World,
+    storage: &Storage,
+    entity: GlobalKey,
+    action: &str,
+    num: u32,
+    amount: u64,
+) -> Result<()> {
+    let item = item_log_text(storage, num, amount);
+
+    player_log(
+        world,
+        storage,
+        entity,
+        LogType::Item,
+        format!("{action} {item}"),
+    )
+}
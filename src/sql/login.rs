@@ -0,0 +1,270 @@
+use super::{
+    hash_password, sql_active_sanction, sql_find_login, sql_login_failed, sql_login_succeeded,
+    sql_reset_password, sql_set_reset_code,
+};
+use crate::{containers::socket_ip, gametypes::SanctionKind};
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::sync::{
+    Arc,
+    mpsc::{self, Receiver, Sender},
+};
+use tokio::runtime::{Handle, Runtime};
+use uuid::Uuid;
+
+/// A login waiting on the password check. addr is the client's own address so a
+/// result for a socket id that has since been reused can be told apart.
+pub struct LoginRequest {
+    pub socket_id: usize,
+    pub addr: Arc<String>,
+    pub username: String,
+    pub password: String,
+    pub reconnect_code: String,
+}
+
+#[derive(Debug)]
+pub enum LoginOutcome {
+    Success(Uuid),
+    /// Unknown account or wrong password. uid is set when the account exists.
+    Failed(Option<Uuid>),
+    /// newly is set when this attempt is the one that locked the account.
+    Locked {
+        uid: Uuid,
+        until: DateTime<Utc>,
+        newly: bool,
+    },
+    /// The password was right but the account or address is banned. None is permanent.
+    Banned {
+        uid: Uuid,
+        reason: String,
+        until: Option<DateTime<Utc>>,
+    },
+    Error(sqlx::Error),
+}
+
+pub struct LoginResult {
+    pub socket_id: usize,
+    pub addr: Arc<String>,
+    pub reconnect_code: String,
+    pub outcome: LoginOutcome,
+}
+
+pub enum ResetAction {
+    /// Stores a new code for the account using the email so it can be mailed out.
+    Request { email: String, code: String },
+    /// Sets the password if the code is right.
+    Reset {
+        email: String,
+        code: String,
+        password: String,
+    },
+}
+
+/// A password reset step waiting on the database, with the same socket_id and addr as
+/// a LoginRequest.
+pub struct ResetRequest {
+    pub socket_id: usize,
+    pub addr: Arc<String>,
+    pub action: ResetAction,
+}
+
+#[derive(Debug)]
+pub enum ResetOutcome {
+    /// account is the uid and username when an account uses the email.
+    Requested {
+        email: String,
+        code: String,
+        account: Option<(Uuid, String)>,
+    },
+    /// The changed account or None if the code was wrong or had expired.
+    Reset(Option<Uuid>),
+    Error(sqlx::Error),
+}
+
+pub struct ResetResult {
+    pub socket_id: usize,
+    pub addr: Arc<String>,
+    pub outcome: ResetOutcome,
+}
+
+/// Checks logins and password resets on the tokio runtime so argon2 and the account
+/// lookups never stall the game loop. Results are picked up with next_result and next_reset.
+pub struct LoginWorker {
+    handle: Handle,
+    pool: PgPool,
+    sender: Sender<LoginResult>,
+    results: Receiver<LoginResult>,
+    reset_sender: Sender<ResetResult>,
+    resets: Receiver<ResetResult>,
+}
+
+impl LoginWorker {
+    pub fn spawn(rt: &Runtime, pool: PgPool) -> Self {
+        let (sender, results) = mpsc::channel();
+        let (reset_sender, resets) = mpsc::channel();
+
+        Self {
+            handle: rt.handle().clone(),
+            pool,
+            sender,
+            results,
+            reset_sender,
+            resets,
+        }
+    }
+
+    pub fn check(&self, request: LoginRequest) {
+        let pool = self.pool.clone();
+        let sender = self.sender.clone();
+
+        self.handle.spawn(async move {
+            let LoginRequest {
+                socket_id,
+                addr,
+                username,
+                password,
+                reconnect_code,
+            } = request;
+
+            let outcome = check_login(&pool, &username, password, &addr)
+                .await
+                .unwrap_or_else(LoginOutcome::Error);
+
+            let _ = sender.send(LoginResult {
+                socket_id,
+                addr,
+                reconnect_code,
+                outcome,
+            });
+        });
+    }
+
+    pub fn next_result(&self) -> Option<LoginResult> {
+        self.results.try_recv().ok()
+    }
+
+    pub fn reset(&self, request: ResetRequest) {
+        let pool = self.pool.clone();
+        let sender = self.reset_sender.clone();
+
+        self.handle.spawn(async move {
+            let ResetRequest {
+                socket_id,
+                addr,
+                action,
+            } = request;
+
+            let outcome = run_reset(&pool, action)
+                .await
+                .unwrap_or_else(ResetOutcome::Error);
+
+            let _ = sender.send(ResetResult {
+                socket_id,
+                addr,
+                outcome,
+            });
+        });
+    }
+
+    pub fn next_reset(&self) -> Option<ResetResult> {
+        self.resets.try_recv().ok()
+    }
+}
+
+/// Reset codes are stored hashed like passwords so reading the database does not hand
+/// out working codes. hash_password uses a fixed salt so the hash can be matched in SQL.
+async fn run_reset(pool: &PgPool, action: ResetAction) -> sqlx::Result<ResetOutcome> {
+    match action {
+        ResetAction::Request { email, code } => {
+            let hashed = code.clone();
+            let hashed = tokio::task::spawn_blocking(move || hash_password(&hashed))
+                .await
+                .map_err(|e| sqlx::Error::Io(std::io::Error::other(e)))?;
+            let account = sql_set_reset_code(pool, &email, &hashed).await?;
+
+            Ok(ResetOutcome::Requested {
+                email,
+                code,
+                account,
+            })
+        }
+        ResetAction::Reset {
+            email,
+            code,
+            password,
+        } => {
+            let (code, password) = tokio::task::spawn_blocking(move || {
+                (hash_password(&code), hash_password(&password))
+            })
+            .await
+            .map_err(|e| sqlx::Error::Io(std::io::Error::other(e)))?;
+
+            Ok(ResetOutcome::Reset(
+                sql_reset_password(pool, &email, &code, &password).await?,
+            ))
+        }
+    }
+}
+
+async fn check_login(
+    pool: &PgPool,
+    username: &str,
+    password: String,
+    addr: &str,
+) -> sqlx::Result<LoginOutcome> {
+    let Some(account) = sql_find_login(pool, username).await? else {
+        return Ok(LoginOutcome::Failed(None));
+    };
+
+    // A locked account is refused without checking the password at all.
+    if let Some(until) = account.locked_until
+        && until > Utc::now()
+    {
+        return Ok(LoginOutcome::Locked {
+            uid: account.uid,
+            until,
+            newly: false,
+        });
+    }
+
+    let hash = account.password;
+    let valid = tokio::task::spawn_blocking(move || {
+        PasswordHash::new(&hash).is_ok_and(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+    })
+    .await
+    .unwrap_or_default();
+
+    if valid {
+        sql_login_succeeded(pool, account.uid).await?;
+
+        let ip = socket_ip(addr).map(|ip| ip.to_string());
+
+        if let Some(ban) =
+            sql_active_sanction(pool, SanctionKind::Ban, Some(account.uid), ip.as_deref()).await?
+        {
+            return Ok(LoginOutcome::Banned {
+                uid: account.uid,
+                reason: ban.reason,
+                until: ban.expires_on,
+            });
+        }
+
+        return Ok(LoginOutcome::Success(account.uid));
+    }
+
+    let (_, locked_until) = sql_login_failed(pool, account.uid).await?;
+
+    Ok(match locked_until {
+        Some(until) if until > Utc::now() => LoginOutcome::Locked {
+            uid: account.uid,
+            until,
+            newly: true,
+        },
+        _ => LoginOutcome::Failed(Some(account.uid)),
+    })
+}
@@ -1,20 +1,24 @@
+use chrono::{DateTime, Utc};
 use sqlx::FromRow;
+use uuid::Uuid;
 
 use crate::gametypes::*;
 
 #[derive(Debug, FromRow)]
 pub struct PGLog {
-    serverid: i16,
-    userid: i64,
-    logtype: LogType,
-    message: String,
-    ipaddress: String,
+    pub serverid: i16,
+    pub userid: Uuid,
+    pub logtype: LogType,
+    pub message: String,
+    pub ipaddress: String,
+    /// When it happened rather than when the batch it was in got written.
+    pub logged_on: DateTime<Utc>,
 }
 
 impl PGLog {
     pub fn new(
         serverid: i16,
-        userid: i64,
+        userid: Uuid,
         logtype: LogType,
         message: String,
         ipaddress: String,
@@ -25,6 +29,7 @@ impl PGLog {
             logtype,
             message,
             ipaddress,
+            logged_on: Utc::now(),
         }
     }
 }
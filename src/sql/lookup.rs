@@ -0,0 +1,63 @@
+use crate::{
+    containers::{Storage, World},
+    gametypes::*,
+};
+use log::error;
+use sqlx::PgPool;
+use std::{
+    future::Future,
+    sync::mpsc::{self, Receiver, Sender},
+};
+use tokio::runtime::{Handle, Runtime};
+
+/// What to do with a lookup's result. Runs on the game loop once the query is back.
+pub type LookupReply = Box<dyn FnOnce(&mut World, &Storage) -> Result<()> + Send>;
+
+/// Runs reads the game loop has to answer from, like staff commands looking up offline
+/// accounts, on the tokio runtime. Replies are picked up with next_reply.
+pub struct LookupWorker {
+    handle: Handle,
+    pool: PgPool,
+    sender: Sender<LookupReply>,
+    replies: Receiver<LookupReply>,
+}
+
+impl LookupWorker {
+    pub fn spawn(rt: &Runtime, pool: PgPool) -> Self {
+        let (sender, replies) = mpsc::channel();
+
+        Self {
+            handle: rt.handle().clone(),
+            pool,
+            sender,
+            replies,
+        }
+    }
+
+    /// query gets its own handle to the pool and returns the reply to run with what it read.
+    pub fn lookup<F, Fut>(&self, query: F)
+    where
+        F: FnOnce(PgPool) -> Fut,
+        Fut: Future<Output = LookupReply> + Send + 'static,
+    {
+        let sender = self.sender.clone();
+        let future = query(self.pool.clone());
+
+        self.handle.spawn(async move {
+            let _ = sender.send(future.await);
+        });
+    }
+
+    pub fn next_reply(&self) -> Option<LookupReply> {
+        self.replies.try_recv().ok()
+    }
+}
+
+/// Runs the replies of finished lookups. Called from the game loop.
+pub fn process_lookups(world: &mut World, storage: &Storage) {
+    while let Some(reply) = storage.lookups.next_reply() {
+        if let Err(e) = reply(world, storage) {
+            error!("Failed to handle lookup: {e}");
+        }
+    }
+}
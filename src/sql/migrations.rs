@@ -0,0 +1,377 @@
+use crate::{gametypes::*, sql::*};
+use log::info;
+use sqlx::PgPool;
+use tokio::{runtime::Runtime, task};
+
+/// Key for the advisory lock held while migrating so servers sharing a
+/// database do not apply the same migration at once.
+const MIGRATION_LOCK: i64 = 0x4153_4344_4d49_4752;
+
+#[rustfmt::skip]
+const SCHEMA_VERSION_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS public.schema_version
+(
+    version integer NOT NULL,
+    name text COLLATE pg_catalog.\"default\" NOT NULL,
+    applied_on timestamp with time zone NOT NULL DEFAULT now(),
+    CONSTRAINT schema_version_pkey PRIMARY KEY (version)
+)
+
+TABLESPACE pg_default;
+";
+
+#[rustfmt::skip]
+const SCHEMA_VERSION_SCHEMA_ALTER: &str = "
+ALTER TABLE IF EXISTS public.schema_version
+    OWNER to server;
+";
+
+/// A forward only change to the database. Each statement may hold several SQL commands.
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub statements: &'static [&'static str],
+    /// False for statements Postgres refuses inside a transaction block, such as
+    /// ALTER TYPE ... ADD VALUE before Postgres 12. They must be safe to run twice.
+    pub transaction: bool,
+}
+
+/// Every migration in the order they are applied. Versions go up by one and an
+/// applied migration must never be edited, add a new one to change the schema instead.
+pub const MIGRATIONS: &[Migration] = &[
+    // Everything created before migrations existed. It only uses IF NOT EXISTS so
+    // databases made by older servers are brought up to date without losing data.
+    Migration {
+        version: 1,
+        name: "baseline",
+        transaction: true,
+        statements: &[
+            PG_CRYPTO_EXTENSION,
+            PG_UUID,
+            LOGTYPE_SCHEMA,
+            LOGTYPE_SCHEMA_ALTER,
+            USERACCESS_SCHEMA,
+            USERACCESS_SCHEMA_ALTER,
+            STATUSKIND_SCHEMA,
+            STATUSKIND_SCHEMA_ALTER,
+            MAP_POSITION_SCHEMA,
+            MAP_POSITION_SCHEMA_ALTER,
+            POSITION_SCHEMA,
+            POSITION_SCHEMA_ALTER,
+            LOGS_SCHEMA,
+            LOGS_SCHEMA_ALTER,
+            ACCOUNT_SCHEMA,
+            ACCOUNT_SCHEMA_ALTER,
+            GENERAL_SCHEMA,
+            GENERAL_SCHEMA_ALTER,
+            LOCATION_SCHEMA,
+            LOCATION_SCHEMA_ALTER,
+            COMBAT_SCHEMA,
+            COMBAT_SCHEMA_ALTER,
+            EQUIPMENT_SCHEMA,
+            EQUIPMENT_SCHEMA_ALTER,
+            INVENTORY_SCHEMA,
+            INVENTORY_SCHEMA_ALTER,
+            STORAGE_SCHEMA,
+            STORAGE_SCHEMA_ALTER,
+            GUILD_SCHEMA,
+            GUILD_SCHEMA_ALTER,
+            GUILD_RANK_SCHEMA,
+            GUILD_RANK_SCHEMA_ALTER,
+            GUILD_MEMBER_SCHEMA,
+            GUILD_MEMBER_SCHEMA_ALTER,
+            QUEST_SCHEMA,
+            QUEST_SCHEMA_ALTER,
+            HOTBAR_SCHEMA,
+            HOTBAR_SCHEMA_ALTER,
+            STATUS_EFFECT_SCHEMA,
+            STATUS_EFFECT_SCHEMA_ALTER,
+        ],
+    },
+    Migration {
+        version: 2,
+        name: "logs_timestamp",
+        transaction: true,
+        statements: &["
+ALTER TABLE public.logs
+    ADD COLUMN IF NOT EXISTS logged_on timestamp with time zone NOT NULL DEFAULT now();
+
+CREATE INDEX IF NOT EXISTS logs_userid_logged_on
+    ON public.logs (userid, logged_on DESC);
+"],
+    },
+    Migration {
+        version: 3,
+        name: "login_lockout",
+        transaction: true,
+        statements: &["
+ALTER TABLE public.account
+    ADD COLUMN IF NOT EXISTS failed_logins integer NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS locked_until timestamp with time zone;
+"],
+    },
+    Migration {
+        version: 4,
+        name: "password_reset_expiry",
+        transaction: true,
+        statements: &["
+ALTER TABLE public.account
+    ADD COLUMN IF NOT EXISTS passresetexpires timestamp with time zone;
+"],
+    },
+    Migration {
+        version: 5,
+        name: "sanctions",
+        transaction: true,
+        statements: &["
+DO $$ BEGIN
+    CREATE TYPE public.\"sanction_kind\" AS ENUM
+        ('Ban', 'Mute');
+EXCEPTION
+    WHEN duplicate_object THEN null;
+END $$;
+
+CREATE TABLE IF NOT EXISTS public.sanctions
+(
+    id bigserial PRIMARY KEY,
+    kind sanction_kind NOT NULL,
+    uid uuid REFERENCES public.account (uid) ON DELETE CASCADE,
+    ipaddress inet,
+    reason text COLLATE pg_catalog.\"default\" NOT NULL,
+    issuer uuid,
+    created_on timestamp with time zone NOT NULL DEFAULT now(),
+    expires_on timestamp with time zone,
+    CHECK (uid IS NOT NULL OR ipaddress IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS sanctions_uid ON public.sanctions (uid);
+CREATE INDEX IF NOT EXISTS sanctions_ipaddress ON public.sanctions USING gist (ipaddress inet_ops);
+"],
+    },
+    // Splits characters out of accounts. Every account that has player data becomes its
+    // first character, reusing the account's uid as the character id so its rows stay put.
+    // The bank stays per account so it is shared by all of its characters.
+    Migration {
+        version: 6,
+        name: "characters",
+        transaction: true,
+        statements: &["
+CREATE TABLE IF NOT EXISTS public.characters
+(
+    cid uuid NOT NULL DEFAULT uuid_generate_v7(),
+    uid uuid NOT NULL REFERENCES public.account (uid) ON DELETE CASCADE,
+    slot smallint NOT NULL,
+    name text COLLATE pg_catalog.\"default\" NOT NULL,
+    created_on timestamp with time zone NOT NULL DEFAULT now(),
+    CONSTRAINT character_pkey PRIMARY KEY (cid),
+    CONSTRAINT character_slot UNIQUE (uid, slot),
+    CONSTRAINT character_name UNIQUE (name)
+);
+
+INSERT INTO public.characters (cid, uid, slot, name)
+SELECT a.uid, a.uid, 0, a.username
+FROM public.account a
+WHERE EXISTS (SELECT 1 FROM public.general g WHERE g.uid = a.uid)
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE public.general RENAME COLUMN uid TO cid;
+ALTER TABLE public.locations RENAME COLUMN uid TO cid;
+ALTER TABLE public.combat RENAME COLUMN uid TO cid;
+ALTER TABLE public.equipment RENAME COLUMN uid TO cid;
+ALTER TABLE public.inventory RENAME COLUMN uid TO cid;
+ALTER TABLE public.quests RENAME COLUMN uid TO cid;
+ALTER TABLE public.hotbar RENAME COLUMN uid TO cid;
+ALTER TABLE public.status_effects RENAME COLUMN uid TO cid;
+ALTER TABLE public.guild_members RENAME COLUMN uid TO cid;
+
+ALTER TABLE public.quests DROP CONSTRAINT IF EXISTS quests_uid_fkey;
+ALTER TABLE public.hotbar DROP CONSTRAINT IF EXISTS hotbar_uid_fkey;
+ALTER TABLE public.status_effects DROP CONSTRAINT IF EXISTS status_effects_uid_fkey;
+ALTER TABLE public.guild_members DROP CONSTRAINT IF EXISTS guild_members_uid_fkey;
+
+-- NOT VALID skips checking rows written before this so leftovers can not fail the
+-- migration, while deleting a character still removes everything it owns.
+ALTER TABLE public.general ADD CONSTRAINT general_cid_fkey
+    FOREIGN KEY (cid) REFERENCES public.characters (cid) ON DELETE CASCADE NOT VALID;
+ALTER TABLE public.locations ADD CONSTRAINT locations_cid_fkey
+    FOREIGN KEY (cid) REFERENCES public.characters (cid) ON DELETE CASCADE NOT VALID;
+ALTER TABLE public.combat ADD CONSTRAINT combat_cid_fkey
+    FOREIGN KEY (cid) REFERENCES public.characters (cid) ON DELETE CASCADE NOT VALID;
+ALTER TABLE public.equipment ADD CONSTRAINT equipment_cid_fkey
+    FOREIGN KEY (cid) REFERENCES public.characters (cid) ON DELETE CASCADE NOT VALID;
+ALTER TABLE public.inventory ADD CONSTRAINT inventory_cid_fkey
+    FOREIGN KEY (cid) REFERENCES public.characters (cid) ON DELETE CASCADE NOT VALID;
+ALTER TABLE public.quests ADD CONSTRAINT quests_cid_fkey
+    FOREIGN KEY (cid) REFERENCES public.characters (cid) ON DELETE CASCADE NOT VALID;
+ALTER TABLE public.hotbar ADD CONSTRAINT hotbar_cid_fkey
+    FOREIGN KEY (cid) REFERENCES public.characters (cid) ON DELETE CASCADE NOT VALID;
+ALTER TABLE public.status_effects ADD CONSTRAINT status_effects_cid_fkey
+    FOREIGN KEY (cid) REFERENCES public.characters (cid) ON DELETE CASCADE NOT VALID;
+ALTER TABLE public.guild_members ADD CONSTRAINT guild_members_cid_fkey
+    FOREIGN KEY (cid) REFERENCES public.characters (cid) ON DELETE CASCADE NOT VALID;
+"],
+    },
+    // Existing characters are given the points they would have earned levelling,
+    // using the default of 3 per level.
+    Migration {
+        version: 7,
+        name: "attributes",
+        transaction: true,
+        statements: &["
+ALTER TABLE public.combat
+    ADD COLUMN IF NOT EXISTS attributes integer[] NOT NULL DEFAULT '{0, 0, 0, 0}',
+    ADD COLUMN IF NOT EXISTS stat_points integer NOT NULL DEFAULT 0;
+
+UPDATE public.combat SET stat_points = GREATEST(level - 1, 0) * 3;
+"],
+    },
+    // log_type made before the Command value existed. New databases already have it.
+    Migration {
+        version: 8,
+        name: "log_type_command",
+        transaction: false,
+        statements: &[LOGTYPE_SCHEMA_COMMAND],
+    },
+    // Reset codes are now stored hashed. Codes sent before that would never match again.
+    Migration {
+        version: 9,
+        name: "hash_reset_codes",
+        transaction: true,
+        statements: &["
+UPDATE public.account
+SET passresetcode = NULL, passresetexpires = NULL
+WHERE passresetcode IS NOT NULL;
+"],
+    },
+];
+
+async fn schema_version(pool: &PgPool) -> sqlx::Result<i32> {
+    let version: Option<i32> =
+        sqlx::query_scalar("SELECT MAX(version) FROM public.schema_version;")
+            .fetch_one(pool)
+            .await?;
+
+    Ok(version.unwrap_or_default())
+}
+
+/// Applies one migration in its own transaction. Returns false if another server got to it first.
+async fn apply_migration(pool: &PgPool, migration: &Migration) -> sqlx::Result<bool> {
+    if !migration.transaction {
+        return apply_migration_unwrapped(pool, migration).await;
+    }
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("SELECT pg_advisory_xact_lock($1);")
+        .bind(MIGRATION_LOCK)
+        .execute(&mut *tx)
+        .await?;
+
+    let applied: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM public.schema_version WHERE version = $1);",
+    )
+    .bind(migration.version)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if applied {
+        return Ok(false);
+    }
+
+    for statement in migration.statements {
+        sqlx::raw_sql(statement).execute(&mut *tx).await?;
+    }
+
+    sqlx::query("INSERT INTO public.schema_version(version, name) VALUES ($1, $2);")
+        .bind(migration.version)
+        .bind(migration.name)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// Applies a migration which can not run in a transaction, holding the lock on the
+/// connection instead. A failure part way leaves it unrecorded so it is run again.
+async fn apply_migration_unwrapped(pool: &PgPool, migration: &Migration) -> sqlx::Result<bool> {
+    let mut conn = pool.acquire().await?;
+
+    sqlx::query("SELECT pg_advisory_lock($1);")
+        .bind(MIGRATION_LOCK)
+        .execute(&mut *conn)
+        .await?;
+
+    let result: sqlx::Result<bool> = async {
+        let applied: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM public.schema_version WHERE version = $1);",
+        )
+        .bind(migration.version)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        if applied {
+            return Ok(false);
+        }
+
+        for statement in migration.statements {
+            sqlx::raw_sql(statement).execute(&mut *conn).await?;
+        }
+
+        sqlx::query("INSERT INTO public.schema_version(version, name) VALUES ($1, $2);")
+            .bind(migration.version)
+            .bind(migration.name)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(true)
+    }
+    .await;
+
+    sqlx::query("SELECT pg_advisory_unlock($1);")
+        .bind(MIGRATION_LOCK)
+        .execute(&mut *conn)
+        .await?;
+
+    result
+}
+
+async fn migrate(pool: &PgPool) -> Result<()> {
+    sqlx::raw_sql(SCHEMA_VERSION_SCHEMA).execute(pool).await?;
+    sqlx::raw_sql(SCHEMA_VERSION_SCHEMA_ALTER)
+        .execute(pool)
+        .await?;
+
+    let latest = MIGRATIONS
+        .last()
+        .map(|migration| migration.version)
+        .unwrap_or_default();
+    let current = schema_version(pool).await?;
+
+    if current > latest {
+        return Err(AscendingError::SchemaTooNew {
+            database: current,
+            server: latest,
+        });
+    }
+
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > current)
+    {
+        if apply_migration(pool, migration).await? {
+            info!(
+                "Applied database migration {} {}",
+                migration.version, migration.name
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Brings the database up to the newest schema version. Called once at startup.
+pub fn initiate(conn: &PgPool, rt: &mut Runtime, local: &task::LocalSet) -> Result<()> {
+    local.block_on(rt, migrate(conn))
+}
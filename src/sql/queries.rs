@@ -1,124 +1,52 @@
 use std::sync::{Arc, Mutex};
 
 use crate::{containers::*, gametypes::*, sql::*};
-use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use chrono::Duration;
 use sqlx::{FromRow, PgPool};
-use tokio::{runtime::Runtime, task};
 use uuid::Uuid;
 
 mod account;
+mod character;
 mod combat;
 mod equipment;
 mod general;
+mod guild;
+mod hotbar;
 mod inventory;
 mod location;
+mod logs;
+mod quest;
+mod sanction;
+mod status;
 mod storage;
 
 pub use account::*;
+pub use character::*;
 pub use combat::*;
 pub use equipment::*;
 pub use general::*;
+pub use guild::*;
+pub use hotbar::*;
 pub use inventory::*;
 pub use location::*;
+pub use logs::*;
+pub use quest::*;
+pub use sanction::*;
+pub use status::*;
 pub use storage::*;
 
 use super::integers::Shifting;
 
-#[derive(Debug, PartialEq, Eq, FromRow)]
-pub struct PlayerWithPassword {
-    pub uid: Uuid,
-    pub password: String,
-}
-
 #[derive(Debug, PartialEq, Eq, FromRow)]
 pub struct Check {
     pub check: bool,
 }
 
-pub fn initiate(conn: &PgPool, rt: &mut Runtime, local: &task::LocalSet) -> Result<()> {
-    let queries = [
-        PG_CRYPTO_EXTENSION,
-        PG_UUID,
-        LOGTYPE_SCHEMA,
-        LOGTYPE_SCHEMA_ALTER,
-        USERACCESS_SCHEMA,
-        USERACCESS_SCHEMA_ALTER,
-        MAP_POSITION_SCHEMA,
-        MAP_POSITION_SCHEMA_ALTER,
-        POSITION_SCHEMA,
-        POSITION_SCHEMA_ALTER,
-        LOGS_SCHEMA,
-        LOGS_SCHEMA_ALTER,
-        ACCOUNT_SCHEMA,
-        ACCOUNT_SCHEMA_ALTER,
-        GENERAL_SCHEMA,
-        GENERAL_SCHEMA_ALTER,
-        LOCATION_SCHEMA,
-        LOCATION_SCHEMA_ALTER,
-        COMBAT_SCHEMA,
-        COMBAT_SCHEMA_ALTER,
-        EQUIPMENT_SCHEMA,
-        EQUIPMENT_SCHEMA_ALTER,
-        INVENTORY_SCHEMA,
-        INVENTORY_SCHEMA_ALTER,
-        STORAGE_SCHEMA,
-        STORAGE_SCHEMA_ALTER,
-    ];
-
-    for quere in queries {
-        local.block_on(rt, sqlx::query(quere).execute(conn))?;
-    }
-
-    Ok(())
-}
-
-pub fn find_player(storage: &Storage, email: &str, password: &str) -> Result<Option<Uuid>> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-    let userdata: Option<PlayerWithPassword> = local.block_on(
-        &rt,
-        sqlx::query_as(
-            r#"
-                SELECT uid, password FROM public.account
-                WHERE email = $1
-            "#,
-        )
-        .bind(email)
-        .fetch_optional(&storage.pgconn),
-    )?;
-
-    if let Some(userdata) = userdata {
-        let hash = match PasswordHash::new(&userdata.password[..]) {
-            Ok(v) => v,
-            Err(_) => return Err(AscendingError::IncorrectPassword),
-        };
-
-        if Argon2::default()
-            .verify_password(password.as_bytes(), &hash)
-            .is_ok()
-        {
-            Ok(Some(userdata.uid))
-        } else {
-            Err(AscendingError::IncorrectPassword)
-        }
-    } else {
-        Ok(None)
-    }
-}
-
 pub fn check_existance(storage: &Storage, username: &str, email: &str) -> Result<i64> {
     let rt = storage.rt.borrow_mut();
     let local = storage.local.borrow();
 
-    let check: Check = local.block_on(
-        &rt,
-        sqlx::query_as(r#"SELECT EXISTS(SELECT 1 FROM public.account WHERE username=$1) as check"#)
-            .bind(username)
-            .fetch_one(&storage.pgconn),
-    )?;
-
-    if check.check {
+    if local.block_on(&rt, sql_name_taken(&storage.pgconn, username))? {
         return Ok(1);
     };
 
@@ -136,39 +64,250 @@ pub fn check_existance(storage: &Storage, username: &str, email: &str) -> Result
     Ok(0)
 }
 
+/// Creates the account, its bank and its first character, which takes the account's name.
+/// Returns the account and character ids.
 pub fn new_player(
     storage: &Storage,
     username: String,
     email: String,
     password: String,
+    sprite: u16,
     socket: &Socket,
-) -> Result<Uuid> {
-    let uid: Uuid = sql_new_account(storage, &username, &socket.addr, &password, &email)?;
+) -> Result<(Uuid, Uuid)> {
+    let rt = storage.rt.borrow_mut();
+    let local = storage.local.borrow();
+    let pool = &storage.pgconn;
+
+    let ids = local.block_on(&rt, async {
+        let uid = sql_new_account(pool, &username, &socket.addr, &password, &email).await?;
+
+        sql_new_storage(pool, uid).await?;
+        let cid = sql_new_character(pool, uid, 0, &username, sprite).await?;
+
+        Ok::<(Uuid, Uuid), sqlx::Error>((uid, cid))
+    })?;
+
+    Ok(ids)
+}
+
+/// What came of adding a character on the character select screen.
+pub enum NewCharacter {
+    /// The account's characters with the new one among them.
+    Created(Vec<PGCharacter>),
+    NameTaken,
+    SlotsFull,
+}
+
+/// Adds a character to the account in the first free slot.
+pub async fn sql_add_character(
+    pool: &PgPool,
+    uid: Uuid,
+    name: &str,
+    sprite: u16,
+) -> sqlx::Result<NewCharacter> {
+    if sql_name_taken(pool, name).await? {
+        return Ok(NewCharacter::NameTaken);
+    }
+
+    let used: Vec<i16> = sql_load_characters(pool, uid)
+        .await?
+        .into_iter()
+        .map(|character| character.slot)
+        .collect();
+
+    let Some(slot) = (0..MAX_CHARACTERS as i16).find(|slot| !used.contains(slot)) else {
+        return Ok(NewCharacter::SlotsFull);
+    };
+
+    sql_new_character(pool, uid, slot, name, sprite).await?;
 
-    sql_new_general(storage, uid)?;
-    sql_new_equipment(storage, uid)?;
-    sql_new_inventory(storage, uid)?;
-    sql_new_storage(storage, uid)?;
-    sql_new_combat(storage, uid)?;
-    sql_new_location(storage, uid)?;
+    Ok(NewCharacter::Created(sql_load_characters(pool, uid).await?))
+}
 
-    Ok(uid)
+/// What came of deleting a character on the character select screen.
+pub enum RemovedCharacter {
+    /// The deleted character's name, None if it was already gone, and the characters left.
+    Deleted(Option<String>, Vec<PGCharacter>),
+    Missing,
+    LoggedIn,
+    InGuild,
 }
 
-pub fn load_player(storage: &Storage, entity: &mut PlayerEntity, account_id: Uuid) -> Result<()> {
+/// Deletes the character in the account's slot. online is the account's character that
+/// is still in the world, which can not be deleted.
+pub async fn sql_remove_character(
+    pool: &PgPool,
+    uid: Uuid,
+    slot: i16,
+    online: Option<Uuid>,
+) -> sqlx::Result<RemovedCharacter> {
+    let Some(character) = sql_load_characters(pool, uid)
+        .await?
+        .into_iter()
+        .find(|character| character.slot == slot)
+    else {
+        return Ok(RemovedCharacter::Missing);
+    };
+
+    if online == Some(character.cid) {
+        return Ok(RemovedCharacter::LoggedIn);
+    }
+
+    if sql_fetch_guild_member(pool, character.cid).await?.is_some() {
+        return Ok(RemovedCharacter::InGuild);
+    }
+
+    let deleted = sql_delete_character(pool, uid, character.cid)
+        .await?
+        .then_some(character.name);
+
+    Ok(RemovedCharacter::Deleted(
+        deleted,
+        sql_load_characters(pool, uid).await?,
+    ))
+}
+
+/// The rows of one character read by sql_load_player.
+pub struct PlayerLoad {
+    pub account: PGAccount,
+    pub characters: Vec<PGCharacter>,
+    pub general: PGGeneral,
+    pub equipment: PGEquipment,
+    pub inventory: PGInventory,
+    pub storage: PGStorage,
+    pub combat: PGCombat,
+    pub location: PGLocation,
+    pub quests: Vec<PGQuest>,
+    pub hotbar: Vec<PGHotbarSlot>,
+    pub effects: Vec<PGStatusEffect>,
+    pub mute: Option<PGSanction>,
+    pub guild: Option<(PGGuildMember, Option<Guild>)>,
+}
+
+/// Reads the character cid of the account uid. The bank is read from the account.
+/// Runs on the save worker once the character's queued writes are done.
+pub async fn sql_load_player(
+    pool: &PgPool,
+    uid: Uuid,
+    cid: Uuid,
+    ip: Option<String>,
+) -> sqlx::Result<PlayerLoad> {
+    let guild = match sql_fetch_guild_member(pool, cid).await? {
+        Some(member) => {
+            let guild = sql_fetch_guild(pool, member.guild).await?;
+
+            Some((member, guild))
+        }
+        None => None,
+    };
+
+    Ok(PlayerLoad {
+        account: sql_load_account(pool, uid).await?,
+        characters: sql_load_characters(pool, uid).await?,
+        general: sql_load_general(pool, cid).await?,
+        equipment: sql_load_equipment(pool, cid).await?,
+        inventory: sql_load_inventory(pool, cid).await?,
+        storage: sql_load_storage(pool, uid).await?,
+        combat: sql_load_combat(pool, cid).await?,
+        location: sql_load_location(pool, cid).await?,
+        quests: sql_load_quests(pool, cid).await?,
+        hotbar: sql_load_hotbar(pool, cid).await?,
+        effects: sql_load_status_effects(pool, cid).await?,
+        mute: sql_active_sanction(pool, SanctionKind::Mute, Some(uid), ip.as_deref()).await?,
+        guild,
+    })
+}
+
+/// Fills the player from the character cid of the account uid as read by sql_load_player.
+pub fn load_player(
+    storage: &Storage,
+    entity: &mut PlayerEntity,
+    uid: Uuid,
+    cid: Uuid,
+    data: PlayerLoad,
+) -> Result<()> {
     let tick = *storage.gettick.borrow();
 
-    let account_data = sql_load_account(storage, account_id)?;
-    let general_data = sql_load_general(storage, account_id)?;
-    let equipment_data = sql_load_equipment(storage, account_id)?;
-    let inventory_data = sql_load_inventory(storage, account_id)?;
-    let storage_data = sql_load_storage(storage, account_id)?;
-    let combat_data = sql_load_combat(storage, account_id)?;
-    let location_data = sql_load_location(storage, account_id)?;
+    let PlayerLoad {
+        account: account_data,
+        characters,
+        general: general_data,
+        equipment: equipment_data,
+        inventory: inventory_data,
+        storage: storage_data,
+        combat: combat_data,
+        location: location_data,
+        quests,
+        hotbar: hotbar_data,
+        effects,
+        mute,
+        guild,
+    } = data;
+
+    if let Some((member, loaded)) = guild {
+        // The cached guild is newer than the database when one of its members is online.
+        let cached = storage.guilds.borrow().get(&member.guild).cloned();
+
+        if let Some(guild) = cached.or(loaded) {
+            entity.guild.membership = Some(GuildMembership {
+                id: guild.id,
+                tag: guild.tag.clone(),
+                rank: member.rank,
+            });
+
+            storage.guilds.borrow_mut().entry(guild.id).or_insert(guild);
+        }
+    }
+
+    for quest in quests {
+        let id = quest.quest as u16;
+
+        if quest.active {
+            entity
+                .quests
+                .active
+                .insert(id, quest.progress.iter().map(|val| *val as u16).collect());
+        }
+
+        if quest.completed > 0 {
+            entity.quests.completed.insert(id, quest.completed as u32);
+        }
+    }
+
+    for hotbar in hotbar_data {
+        if let Some(slot) = entity.skills.hotbar.get_mut(hotbar.slot as usize) {
+            *slot = hotbar.skill.map(|skill| skill as u16);
+        }
+    }
+
+    for effect in effects {
+        entity.combat.effects.0.push(StatusEffect {
+            kind: effect.kind,
+            power: effect.power,
+            stacks: (effect.stacks as u8).clamp(1, effect.kind.max_stacks()),
+            expires: tick + Duration::try_milliseconds(effect.remaining).unwrap_or_default(),
+            next_tick: tick + Duration::try_milliseconds(STATUS_TICK_MS).unwrap_or_default(),
+            source: None,
+        });
+    }
+
+    entity.combat.stunned = entity.combat.effects.has(StatusKind::Stun);
+
+    if let Some(mute) = mute {
+        entity.mute_timer.set(mute.expires_on, tick);
+    }
+
+    let Some(character) = characters
+        .into_iter()
+        .find(|character| character.cid == cid)
+    else {
+        return Err(AscendingError::UserNotFound);
+    };
 
     entity.user_access = account_data.useraccess;
-    entity.account.id = account_id;
-    entity.account.username.clone_from(&account_data.username);
+    entity.account.id = uid;
+    entity.account.character = cid;
+    entity.account.username = character.name;
     entity
         .account
         .passresetcode
@@ -210,60 +349,99 @@ pub fn load_player(storage: &Storage, entity: &mut PlayerEntity, account_id: Uui
     }
 
     entity.general.pk = combat_data.pk;
+    // Pk status carried over a logout lasts a full timer from the login.
+    entity.pvp_timer.pk_timer =
+        *storage.gettick.borrow() + Duration::try_milliseconds(PK_DURATION_MS).unwrap_or_default();
     entity.general.levelexp = combat_data.levelexp.shift_signed();
     entity.combat.level = combat_data.level;
     entity.combat.vitals.vital = combat_data.vital;
     entity.combat.vitals.vitalmax = combat_data.vital_max;
 
+    // Players who logged out dead wait out a fresh death timer before respawning.
+    if combat_data.indeath {
+        entity.combat.death_type = DeathType::Spirit;
+        entity.combat.death_timer.0 = *storage.gettick.borrow()
+            + Duration::try_milliseconds(storage.bases.progression.death.spirit_ms)
+                .unwrap_or_default();
+    }
+    entity.attributes.values = combat_data.attributes;
+    entity.attributes.points = combat_data.stat_points;
+
     entity.movement.pos = location_data.pos;
     entity.movement.spawn.pos = location_data.spawn;
     entity.movement.dir = location_data.dir as u8;
 
+    // Stats come from the progression data so they follow any rebalancing.
+    storage
+        .bases
+        .progression
+        .apply(&mut entity.combat, &entity.attributes);
+
     Ok(())
 }
 
+/// Queues everything not saved per change. This does not wait for the writes to finish.
 pub fn save_player(storage: &Storage, player: Arc<Mutex<PlayerEntity>>) -> Result<()> {
     let tick = *storage.gettick.borrow();
     let p_data = player.try_lock()?;
-    let accountid = p_data.account.id;
-
-    sql_update_account(storage, accountid, p_data.user_access)?;
-    sql_update_general(
-        storage,
-        accountid,
-        PGGeneral {
+    let uid = p_data.account.character;
+
+    storage.saves.save(SaveJob::Account {
+        uid: p_data.account.id,
+        user_access: p_data.user_access,
+    })?;
+    storage.saves.save(SaveJob::General {
+        uid,
+        data: PGGeneral {
             sprite: i16::unshift_signed(&p_data.sprite.id),
             money: i64::unshift_signed(&p_data.money.vals),
             resetcount: p_data.general.resetcount,
             itemtimer: get_time_left(p_data.item_timer.itemtimer, tick),
             deathtimer: get_time_left(p_data.combat.death_timer.0, tick),
         },
-    )?;
-    sql_update_combat(
-        storage,
-        accountid,
-        PGCombat {
+    })?;
+    storage.saves.save(SaveJob::Combat {
+        uid,
+        data: PGCombat {
             indeath: p_data.combat.death_type.is_dead(),
             level: p_data.combat.level,
             levelexp: i64::unshift_signed(&p_data.general.levelexp),
             pk: p_data.general.pk,
             vital: p_data.combat.vitals.vital,
             vital_max: p_data.combat.vitals.vitalmax,
+            attributes: p_data.attributes.values,
+            stat_points: p_data.attributes.points,
         },
-    )?;
-    sql_update_location(
-        storage,
-        accountid,
-        PGLocation {
+    })?;
+    storage.saves.save(SaveJob::Location {
+        uid,
+        data: PGLocation {
             spawn: p_data.movement.spawn.pos,
             pos: p_data.movement.pos,
             dir: p_data.movement.dir as i16,
         },
-    )?;
+    })?;
+    storage.saves.save(SaveJob::StatusEffects {
+        uid,
+        effects: p_data
+            .combat
+            .effects
+            .0
+            .iter()
+            .map(|effect| PGStatusEffect {
+                kind: effect.kind,
+                power: effect.power,
+                stacks: effect.stacks as i16,
+                remaining: get_time_left(effect.expires, tick),
+            })
+            .collect(),
+    })?;
 
     // Inventory Not needed since its saved per change.
     // Equipment Not needed since its saved per change.
     // Storage Not needed since its saved per change.
+    // Quests Not needed since its saved per change.
+    // Hotbar Not needed since its saved per change.
 
     Ok(())
 }
@@ -1,15 +1,16 @@
 use argon2::{Argon2, PasswordHasher};
+use chrono::{DateTime, Utc};
 use password_hash::SaltString;
 use uuid::Uuid;
 
 use crate::{
-    containers::{SALT, Storage, UserAccess},
+    containers::{SALT, UserAccess},
     gametypes::*,
 };
 
-use sqlx::FromRow;
+use sqlx::{FromRow, PgPool};
 
-#[derive(Debug, FromRow, Default)]
+#[derive(Debug, PartialEq, FromRow, Default)]
 pub struct PGAccount {
     pub username: String,
     pub email: String,
@@ -17,27 +18,19 @@ pub struct PGAccount {
     pub passresetcode: Option<String>,
 }
 
-impl PGAccount {
-    pub fn into_empty() -> String {
-        r#"
-            INSERT INTO public.account(username, address, password, email, passresetcode, useraccess)
-            VALUES ($1, $2, $3, $4, null, 'None') RETURNING uid;
-            "#.to_string()
-    }
+/// What is needed to check a login. The password is the argon2 hash.
+#[derive(Debug, PartialEq, FromRow)]
+pub struct PGLoginAccount {
+    pub uid: Uuid,
+    pub password: String,
+    pub failed_logins: i32,
+    pub locked_until: Option<DateTime<Utc>>,
 }
 
-pub fn sql_new_account(
-    storage: &Storage,
-    username: &str,
-    address: &str,
-    password: &str,
-    email: &str,
-) -> Result<Uuid> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
+pub fn hash_password(password: &str) -> String {
     let argon = Argon2::default();
-    let hashed_password = if let Ok(salt) = SaltString::encode_b64(SALT) {
+
+    if let Ok(salt) = SaltString::encode_b64(SALT) {
         if let Ok(hash) = argon.hash_password(password.as_bytes(), &salt) {
             hash.to_string()
         } else {
@@ -45,56 +38,185 @@ pub fn sql_new_account(
         }
     } else {
         String::from("FailedPasswordHash")
-    };
-
-    let query = PGAccount::into_empty();
-    let result: (Uuid,) = local.block_on(
-        &rt,
-        sqlx::query_as(&query)
-            .bind(username)
-            .bind(address)
-            .bind(hashed_password)
-            .bind(email)
-            .fetch_one(&storage.pgconn),
-    )?;
+    }
+}
+
+pub async fn sql_new_account(
+    pool: &PgPool,
+    username: &str,
+    address: &str,
+    password: &str,
+    email: &str,
+) -> sqlx::Result<Uuid> {
+    let hashed_password = hash_password(password);
+
+    let result: (Uuid,) = sqlx::query_as(
+        r#"
+        INSERT INTO public.account(username, address, password, email, passresetcode, useraccess)
+        VALUES ($1, $2, $3, $4, null, 'None') RETURNING uid;
+        "#,
+    )
+    .bind(username)
+    .bind(address)
+    .bind(hashed_password)
+    .bind(email)
+    .fetch_one(pool)
+    .await?;
 
     Ok(result.0)
 }
 
-pub fn sql_load_account(storage: &Storage, account_id: Uuid) -> Result<PGAccount> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let query = format!(
+pub async fn sql_load_account(pool: &PgPool, account_id: Uuid) -> sqlx::Result<PGAccount> {
+    sqlx::query_as(
         r#"
         SELECT username, email, passresetcode, useraccess
         FROM public.account
-        WHERE uid = '{account_id}';
+        WHERE uid = $1;
+        "#,
+    )
+    .bind(account_id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Looks up an account and its access by name so offline players can be looked at by staff.
+pub async fn sql_find_account(
+    pool: &PgPool,
+    username: &str,
+) -> sqlx::Result<Option<(Uuid, UserAccess)>> {
+    sqlx::query_as(
+        r#"
+        SELECT uid, useraccess
+        FROM public.account
+        WHERE username = $1;
+        "#,
+    )
+    .bind(username)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn sql_update_account(
+    pool: &PgPool,
+    uid: Uuid,
+    user_access: UserAccess,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE public.account
+        SET useraccess = $2
+        WHERE uid = $1;
         "#,
-    );
-    let data: PGAccount = local.block_on(&rt, sqlx::query_as(&query).fetch_one(&storage.pgconn))?;
+    )
+    .bind(uid)
+    .bind(user_access)
+    .execute(pool)
+    .await?;
 
-    Ok(data)
+    Ok(())
+}
+
+pub async fn sql_find_login(pool: &PgPool, email: &str) -> sqlx::Result<Option<PGLoginAccount>> {
+    sqlx::query_as(
+        r#"
+        SELECT uid, password, failed_logins, locked_until
+        FROM public.account
+        WHERE email = $1;
+        "#,
+    )
+    .bind(email)
+    .fetch_optional(pool)
+    .await
 }
 
-pub fn sql_update_account(storage: &Storage, uid: Uuid, user_access: UserAccess) -> Result<()> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
+/// Counts a wrong password against the account. Once ACCOUNT_LOCK_ATTEMPTS is reached
+/// the account is locked, doubling the lock on every further failure up to the max.
+/// Returns the new failure count and lock.
+pub async fn sql_login_failed(
+    pool: &PgPool,
+    uid: Uuid,
+) -> sqlx::Result<(i32, Option<DateTime<Utc>>)> {
+    sqlx::query_as(
+        r#"
+        UPDATE public.account
+        SET failed_logins = failed_logins + 1,
+            locked_until = CASE
+                WHEN failed_logins + 1 >= $2 THEN now() + make_interval(secs => LEAST(
+                    $3 * power(2, LEAST(failed_logins + 1 - $2, 16)),
+                    $4
+                ))
+                ELSE locked_until
+            END
+        WHERE uid = $1
+        RETURNING failed_logins, locked_until;
+        "#,
+    )
+    .bind(uid)
+    .bind(ACCOUNT_LOCK_ATTEMPTS)
+    .bind(ACCOUNT_LOCK_SECS)
+    .bind(ACCOUNT_LOCK_MAX_SECS)
+    .fetch_one(pool)
+    .await
+}
 
-    let query_text = format!(
+pub async fn sql_login_succeeded(pool: &PgPool, uid: Uuid) -> sqlx::Result<()> {
+    sqlx::query(
         r#"
         UPDATE public.account
-        SET useraccess=$1
-        WHERE uid = '{uid}';
-        "#
-    );
-
-    local.block_on(
-        &rt,
-        sqlx::query(&query_text)
-            .bind(user_access)
-            .execute(&storage.pgconn),
-    )?;
+        SET failed_logins = 0, locked_until = NULL
+        WHERE uid = $1 AND (failed_logins <> 0 OR locked_until IS NOT NULL);
+        "#,
+    )
+    .bind(uid)
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
+
+/// Stores a reset code for the account with this email, replacing any older one.
+/// code is the hash_password of the code that is mailed out. Returns the account's uid and username or None if no account uses the email.
+pub async fn sql_set_reset_code(
+    pool: &PgPool,
+    email: &str,
+    code: &str,
+) -> sqlx::Result<Option<(Uuid, String)>> {
+    sqlx::query_as(
+        r#"
+        UPDATE public.account
+        SET passresetcode = $2, passresetexpires = now() + make_interval(secs => $3)
+        WHERE email = $1
+        RETURNING uid, username;
+        "#,
+    )
+    .bind(email)
+    .bind(code)
+    .bind(PASSWORD_RESET_EXPIRE_SECS)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Sets the new password if the code matches and has not expired. The code is used up
+/// and any login lockout is lifted. code and password are already hashed so argon2 can
+/// be run off the async workers. Returns the uid of the changed account.
+pub async fn sql_reset_password(
+    pool: &PgPool,
+    email: &str,
+    code: &str,
+    password: &str,
+) -> sqlx::Result<Option<Uuid>> {
+    sqlx::query_scalar(
+        r#"
+        UPDATE public.account
+        SET password = $3, passresetcode = NULL, passresetexpires = NULL,
+            failed_logins = 0, locked_until = NULL
+        WHERE email = $1 AND passresetcode = $2 AND passresetexpires > now()
+        RETURNING uid;
+        "#,
+    )
+    .bind(email)
+    .bind(code)
+    .bind(password)
+    .fetch_optional(pool)
+    .await
+}
@@ -0,0 +1,100 @@
+use uuid::Uuid;
+
+use crate::sql::{
+    integers::Shifting, sql_new_combat, sql_new_equipment, sql_new_general, sql_new_inventory,
+    sql_new_location,
+};
+
+use sqlx::{FromRow, PgPool};
+
+/// A character as shown on the character select screen.
+#[derive(Debug, PartialEq, FromRow)]
+pub struct PGCharacter {
+    pub cid: Uuid,
+    pub slot: i16,
+    pub name: String,
+    pub sprite: i16,
+    pub level: i32,
+}
+
+impl PGCharacter {
+    pub fn sprite(&self) -> u16 {
+        self.sprite.shift_signed()
+    }
+}
+
+/// Creates a character with all of its default rows. Returns the new character's id.
+pub async fn sql_new_character(
+    pool: &PgPool,
+    uid: Uuid,
+    slot: i16,
+    name: &str,
+    sprite: u16,
+) -> sqlx::Result<Uuid> {
+    let (cid,): (Uuid,) = sqlx::query_as(
+        r#"
+        INSERT INTO public.characters(uid, slot, name)
+        VALUES ($1, $2, $3) RETURNING cid;
+        "#,
+    )
+    .bind(uid)
+    .bind(slot)
+    .bind(name)
+    .fetch_one(pool)
+    .await?;
+
+    sql_new_general(pool, cid, sprite).await?;
+    sql_new_equipment(pool, cid).await?;
+    sql_new_inventory(pool, cid).await?;
+    sql_new_combat(pool, cid).await?;
+    sql_new_location(pool, cid).await?;
+
+    Ok(cid)
+}
+
+pub async fn sql_load_characters(pool: &PgPool, uid: Uuid) -> sqlx::Result<Vec<PGCharacter>> {
+    sqlx::query_as(
+        r#"
+        SELECT c.cid, c.slot, c.name, g.sprite, b.level
+        FROM public.characters c
+        INNER JOIN public.general g ON g.cid = c.cid
+        INNER JOIN public.combat b ON b.cid = c.cid
+        WHERE c.uid = $1
+        ORDER BY c.slot;
+        "#,
+    )
+    .bind(uid)
+    .fetch_all(pool)
+    .await
+}
+
+/// Deletes the character and everything it owns. Returns false if the account does
+/// not own it.
+pub async fn sql_delete_character(pool: &PgPool, uid: Uuid, cid: Uuid) -> sqlx::Result<bool> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM public.characters
+        WHERE uid = $1 AND cid = $2;
+        "#,
+    )
+    .bind(uid)
+    .bind(cid)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Character names share one namespace with account names so a new account's
+/// first character can always take the account's name.
+pub async fn sql_name_taken(pool: &PgPool, name: &str) -> sqlx::Result<bool> {
+    sqlx::query_scalar(
+        r#"
+        SELECT EXISTS(SELECT 1 FROM public.characters WHERE name = $1)
+            OR EXISTS(SELECT 1 FROM public.account WHERE username = $1);
+        "#,
+    )
+    .bind(name)
+    .fetch_one(pool)
+    .await
+}
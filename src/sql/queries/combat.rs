@@ -1,12 +1,11 @@
-use crate::{containers::Storage, sql::integers::Shifting};
-use itertools::Itertools;
+use crate::sql::integers::Shifting;
 use uuid::Uuid;
 
 use crate::gametypes::*;
 
-use sqlx::FromRow;
+use sqlx::{FromRow, PgPool};
 
-#[derive(Debug, FromRow, Default)]
+#[derive(Debug, PartialEq, FromRow, Default)]
 pub struct PGCombat {
     pub indeath: bool,
     pub level: i32,
@@ -14,109 +13,109 @@ pub struct PGCombat {
     pub pk: bool,
     pub vital: [i32; VITALS_MAX],
     pub vital_max: [i32; VITALS_MAX],
+    pub attributes: [i32; ATTRIBUTES_MAX],
+    pub stat_points: i32,
 }
 
-impl PGCombat {
-    pub fn into_empty(uid: Uuid) -> String {
-        format!(
-            r#"
-            INSERT INTO public.combat(uid, indeath, level, levelexp, pk, vital, vital_max)
-            VALUES ('{0}', false, 0, {1}, false, '{{25, 2, 100}}', '{{25, 2, 100}}');
-            "#,
-            uid,
-            i64::unshift_signed(&0),
-        )
-    }
-}
-
-pub fn sql_new_combat(storage: &Storage, uid: Uuid) -> Result<()> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let query = PGCombat::into_empty(uid);
-    local.block_on(&rt, sqlx::query(&query).execute(&storage.pgconn))?;
+pub async fn sql_new_combat(pool: &PgPool, cid: Uuid) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO public.combat(cid, indeath, level, levelexp, pk, vital, vital_max)
+        VALUES ($1, false, 0, $2, false, '{25, 2, 100}', '{25, 2, 100}');
+        "#,
+    )
+    .bind(cid)
+    .bind(i64::unshift_signed(&0))
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
 
-pub fn sql_load_combat(storage: &Storage, account_id: Uuid) -> Result<PGCombat> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let query = format!(
+pub async fn sql_load_combat(pool: &PgPool, cid: Uuid) -> sqlx::Result<PGCombat> {
+    sqlx::query_as(
         r#"
-        SELECT indeath, level, levelexp, pk, vital, vital_max
+        SELECT indeath, level, levelexp, pk, vital, vital_max, attributes, stat_points
         FROM public.combat
-        WHERE uid = '{account_id}';
+        WHERE cid = $1;
         "#,
-    );
-    let data: PGCombat = local.block_on(&rt, sqlx::query_as(&query).fetch_one(&storage.pgconn))?;
-
-    Ok(data)
+    )
+    .bind(cid)
+    .fetch_one(pool)
+    .await
 }
 
-pub fn sql_update_combat(storage: &Storage, uid: Uuid, data: PGCombat) -> Result<()> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let vital = data
-        .vital
-        .iter()
-        .format_with(", ", |elt, f| f(&format_args!("{elt}")))
-        .to_string();
-    let vitalmax = data
-        .vital_max
-        .iter()
-        .format_with(", ", |elt, f| f(&format_args!("{elt}")))
-        .to_string();
-
-    let query_text = format!(
+pub async fn sql_update_combat(pool: &PgPool, cid: Uuid, data: &PGCombat) -> sqlx::Result<()> {
+    sqlx::query(
         r#"
         UPDATE public.combat
-        SET indeath = {1},
-            level = {2},
-            levelexp = {3},
-            pk = {4},
-            vital = '{{{5}}}',
-            vital_max = '{{{6}}}'
-        WHERE uid = '{0}';
+        SET indeath = $2,
+            level = $3,
+            levelexp = $4,
+            pk = $5,
+            vital = $6,
+            vital_max = $7,
+            attributes = $8,
+            stat_points = $9
+        WHERE cid = $1;
         "#,
-        uid, data.indeath, data.level, data.levelexp, data.pk, vital, vitalmax
-    );
-
-    local.block_on(&rt, sqlx::query(&query_text).execute(&storage.pgconn))?;
+    )
+    .bind(cid)
+    .bind(data.indeath)
+    .bind(data.level)
+    .bind(data.levelexp)
+    .bind(data.pk)
+    .bind(&data.vital[..])
+    .bind(&data.vital_max[..])
+    .bind(&data.attributes[..])
+    .bind(data.stat_points)
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
 
-pub fn sql_update_level(storage: &Storage, uid: Uuid, data: PGCombat) -> Result<()> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
+pub async fn sql_update_level(pool: &PgPool, cid: Uuid, data: &PGCombat) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE public.combat
+        SET level = $2,
+            levelexp = $3,
+            vital = $4,
+            vital_max = $5
+        WHERE cid = $1;
+        "#,
+    )
+    .bind(cid)
+    .bind(data.level)
+    .bind(data.levelexp)
+    .bind(&data.vital[..])
+    .bind(&data.vital_max[..])
+    .execute(pool)
+    .await?;
 
-    let vital = data
-        .vital
-        .iter()
-        .format_with(", ", |elt, f| f(&format_args!("{elt}")))
-        .to_string();
-    let vitalmax = data
-        .vital_max
-        .iter()
-        .format_with(", ", |elt, f| f(&format_args!("{elt}")))
-        .to_string();
+    Ok(())
+}
 
-    let query_text = format!(
+pub async fn sql_update_attributes(
+    pool: &PgPool,
+    cid: Uuid,
+    attributes: &[i32; ATTRIBUTES_MAX],
+    stat_points: i32,
+) -> sqlx::Result<()> {
+    sqlx::query(
         r#"
         UPDATE public.combat
-        SET level = {1},
-            levelexp = {2},
-            vital = '{{{3}}}',
-            vital_max = '{{{4}}}'
-        WHERE uid = '{0}';
+        SET attributes = $2,
+            stat_points = $3
+        WHERE cid = $1;
         "#,
-        uid, data.level, data.levelexp, vital, vitalmax
-    );
-
-    local.block_on(&rt, sqlx::query(&query_text).execute(&storage.pgconn))?;
+    )
+    .bind(cid)
+    .bind(&attributes[..])
+    .bind(stat_points)
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
@@ -1,11 +1,9 @@
-use crate::{containers::Storage, sql::integers::Shifting};
+use crate::sql::integers::Shifting;
 use uuid::Uuid;
 
-use crate::gametypes::*;
+use sqlx::{FromRow, PgPool};
 
-use sqlx::FromRow;
-
-#[derive(Debug, FromRow)]
+#[derive(Debug, PartialEq, FromRow)]
 pub struct PGGeneral {
     pub sprite: i16,
     pub money: i64,
@@ -14,98 +12,87 @@ pub struct PGGeneral {
     pub deathtimer: i64,
 }
 
-impl PGGeneral {
-    pub fn into_empty(uid: Uuid) -> String {
-        format!(
-            r#"
-            INSERT INTO public.general(uid, sprite, money, resetcount, itemtimer, deathtimer)
-            VALUES ('{0}', {1}, {2}, 0, 0, 0);
-            "#,
-            uid,
-            i16::unshift_signed(&0),
-            i64::unshift_signed(&0),
-        )
-    }
-}
-
-pub fn sql_new_general(storage: &Storage, uid: Uuid) -> Result<()> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let query = PGGeneral::into_empty(uid);
-    local.block_on(&rt, sqlx::query(&query).execute(&storage.pgconn))?;
+pub async fn sql_new_general(pool: &PgPool, cid: Uuid, sprite: u16) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO public.general(cid, sprite, money, resetcount, itemtimer, deathtimer)
+        VALUES ($1, $2, $3, 0, 0, 0);
+        "#,
+    )
+    .bind(cid)
+    .bind(i16::unshift_signed(&sprite))
+    .bind(i64::unshift_signed(&0))
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
 
-pub fn sql_load_general(storage: &Storage, account_id: Uuid) -> Result<PGGeneral> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let query = format!(
+pub async fn sql_load_general(pool: &PgPool, cid: Uuid) -> sqlx::Result<PGGeneral> {
+    sqlx::query_as(
         r#"
         SELECT sprite, money, resetcount, itemtimer, deathtimer
         FROM public.general
-        WHERE uid = '{account_id}';
+        WHERE cid = $1;
         "#,
-    );
-    let data: PGGeneral = local.block_on(&rt, sqlx::query_as(&query).fetch_one(&storage.pgconn))?;
-
-    Ok(data)
+    )
+    .bind(cid)
+    .fetch_one(pool)
+    .await
 }
 
-pub fn sql_update_general(storage: &Storage, uid: Uuid, data: PGGeneral) -> Result<()> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let query_text = format!(
+pub async fn sql_update_general(pool: &PgPool, cid: Uuid, data: &PGGeneral) -> sqlx::Result<()> {
+    sqlx::query(
         r#"
         UPDATE public.general
-        SET sprite = {1},
-            money = {2},
-            resetcount = {3},
-            itemtimer = {4},
-            deathtimer = {5}
-        WHERE uid = '{0}';
+        SET sprite = $2,
+            money = $3,
+            resetcount = $4,
+            itemtimer = $5,
+            deathtimer = $6
+        WHERE cid = $1;
         "#,
-        uid, data.sprite, data.money, data.resetcount, data.itemtimer, data.deathtimer,
-    );
-
-    local.block_on(&rt, sqlx::query(&query_text).execute(&storage.pgconn))?;
+    )
+    .bind(cid)
+    .bind(data.sprite)
+    .bind(data.money)
+    .bind(data.resetcount)
+    .bind(data.itemtimer)
+    .bind(data.deathtimer)
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
 
-pub fn sql_update_resetcount(storage: &Storage, uid: Uuid, resetcount: i16) -> Result<()> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let query_text = format!(
+pub async fn sql_update_resetcount(pool: &PgPool, cid: Uuid, resetcount: i16) -> sqlx::Result<()> {
+    sqlx::query(
         r#"
         UPDATE public.general
-        SET resetcount = {resetcount}
-        WHERE uid = '{uid}';
-        "#
-    );
-
-    local.block_on(&rt, sqlx::query(&query_text).execute(&storage.pgconn))?;
+        SET resetcount = $2
+        WHERE cid = $1;
+        "#,
+    )
+    .bind(cid)
+    .bind(resetcount)
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
 
-pub fn sql_update_money(storage: &Storage, uid: Uuid, money: i64) -> Result<()> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let query_text = format!(
+pub async fn sql_update_money(pool: &PgPool, cid: Uuid, money: i64) -> sqlx::Result<()> {
+    sqlx::query(
         r#"
         UPDATE public.general
-        SET money = {money}
-        WHERE uid = '{uid}';
+        SET money = $2
+        WHERE cid = $1;
         "#,
-    );
-
-    local.block_on(&rt, sqlx::query(&query_text).execute(&storage.pgconn))?;
+    )
+    .bind(cid)
+    .bind(money)
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
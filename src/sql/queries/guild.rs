@@ -0,0 +1,246 @@
+use uuid::Uuid;
+
+use crate::{
+    containers::{Guild, GuildPermissions, GuildRank, default_guild_ranks},
+    sql::Check,
+};
+
+use sqlx::{FromRow, PgPool};
+
+#[derive(Debug, FromRow)]
+pub struct PGGuild {
+    pub uid: Uuid,
+    pub name: String,
+    pub tag: String,
+    pub motd: String,
+}
+
+#[derive(Debug, FromRow)]
+pub struct PGGuildRank {
+    pub rank: i16,
+    pub name: String,
+    pub permissions: i32,
+}
+
+#[derive(Debug, FromRow)]
+pub struct PGGuildMember {
+    pub cid: Uuid,
+    /// The account the character belongs to.
+    pub uid: Uuid,
+    pub guild: Uuid,
+    pub rank: i16,
+}
+
+/// Checks if a guild name or tag is already in use.
+pub async fn sql_guild_exists(pool: &PgPool, name: &str, tag: &str) -> sqlx::Result<bool> {
+    let check: Check = sqlx::query_as(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM public.guilds WHERE lower(name) = lower($1) OR lower(tag) = lower($2)
+        ) as check
+        "#,
+    )
+    .bind(name)
+    .bind(tag)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(check.check)
+}
+
+/// Creates the guild uid with the default ranks. The leader is added as a member separately.
+pub async fn sql_new_guild(pool: &PgPool, uid: Uuid, name: &str, tag: &str) -> sqlx::Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO public.guilds(uid, name, tag, motd)
+        VALUES ($1, $2, $3, '');
+        "#,
+    )
+    .bind(uid)
+    .bind(name)
+    .bind(tag)
+    .execute(&mut *tx)
+    .await?;
+
+    for rank in default_guild_ranks() {
+        sqlx::query(
+            r#"
+            INSERT INTO public.guild_ranks(guild, rank, name, permissions)
+            VALUES ($1, $2, $3, $4);
+            "#,
+        )
+        .bind(uid)
+        .bind(rank.rank)
+        .bind(&rank.name)
+        .bind(rank.permissions.0)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await
+}
+
+/// Reads a guild and its ranks.
+pub async fn sql_fetch_guild(pool: &PgPool, guild_id: Uuid) -> sqlx::Result<Option<Guild>> {
+    let guild: Option<PGGuild> = sqlx::query_as(
+        r#"
+        SELECT uid, name, tag, motd
+        FROM public.guilds
+        WHERE uid = $1;
+        "#,
+    )
+    .bind(guild_id)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(guild) = guild else {
+        return Ok(None);
+    };
+
+    let ranks: Vec<PGGuildRank> = sqlx::query_as(
+        r#"
+        SELECT rank, name, permissions
+        FROM public.guild_ranks
+        WHERE guild = $1
+        ORDER BY rank;
+        "#,
+    )
+    .bind(guild_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Some(Guild {
+        id: guild.uid,
+        name: guild.name,
+        tag: guild.tag,
+        motd: guild.motd,
+        ranks: ranks
+            .into_iter()
+            .map(|rank| GuildRank {
+                rank: rank.rank,
+                name: rank.name,
+                permissions: GuildPermissions(rank.permissions),
+            })
+            .collect(),
+    }))
+}
+
+pub async fn sql_fetch_guild_member(
+    pool: &PgPool,
+    cid: Uuid,
+) -> sqlx::Result<Option<PGGuildMember>> {
+    sqlx::query_as(
+        r#"
+        SELECT m.cid, c.uid, m.guild, m.rank
+        FROM public.guild_members m
+        INNER JOIN public.characters c ON c.cid = m.cid
+        WHERE m.cid = $1;
+        "#,
+    )
+    .bind(cid)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Finds a guild member by their character name so offline members can be managed.
+pub async fn sql_find_guild_member(
+    pool: &PgPool,
+    guild_id: Uuid,
+    username: &str,
+) -> sqlx::Result<Option<PGGuildMember>> {
+    sqlx::query_as(
+        r#"
+        SELECT m.cid, c.uid, m.guild, m.rank
+        FROM public.guild_members m
+        INNER JOIN public.characters c ON c.cid = m.cid
+        WHERE m.guild = $1 AND c.name = $2;
+        "#,
+    )
+    .bind(guild_id)
+    .bind(username)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn sql_set_guild_member(
+    pool: &PgPool,
+    cid: Uuid,
+    guild_id: Uuid,
+    rank: i16,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO public.guild_members(cid, guild, rank)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (cid) DO UPDATE SET guild = $2, rank = $3;
+        "#,
+    )
+    .bind(cid)
+    .bind(guild_id)
+    .bind(rank)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn sql_remove_guild_member(pool: &PgPool, cid: Uuid) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+        DELETE FROM public.guild_members
+        WHERE cid = $1;
+        "#,
+    )
+    .bind(cid)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn sql_count_guild_members(pool: &PgPool, guild_id: Uuid) -> sqlx::Result<i64> {
+    let (count,): (i64,) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*) FROM public.guild_members
+        WHERE guild = $1;
+        "#,
+    )
+    .bind(guild_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+pub async fn sql_update_guild_motd(pool: &PgPool, guild_id: Uuid, motd: &str) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE public.guilds
+        SET motd = $2
+        WHERE uid = $1;
+        "#,
+    )
+    .bind(guild_id)
+    .bind(motd)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Deletes the guild. Ranks and members are removed along with it.
+pub async fn sql_delete_guild(pool: &PgPool, guild_id: Uuid) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+        DELETE FROM public.guilds
+        WHERE uid = $1;
+        "#,
+    )
+    .bind(guild_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use uuid::Uuid;
+
+use sqlx::{FromRow, PgPool};
+
+#[derive(Debug, PartialEq, FromRow)]
+pub struct PGHotbarSlot {
+    pub slot: i16,
+    pub skill: Option<i16>,
+}
+
+pub async fn sql_load_hotbar(pool: &PgPool, cid: Uuid) -> sqlx::Result<Vec<PGHotbarSlot>> {
+    sqlx::query_as(
+        r#"
+        SELECT slot, skill
+        FROM public.hotbar
+        WHERE cid = $1
+        ORDER BY slot ASC;
+        "#,
+    )
+    .bind(cid)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn sql_set_hotbar_slot(
+    pool: &PgPool,
+    cid: Uuid,
+    data: &PGHotbarSlot,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO public.hotbar(cid, slot, skill)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (cid, slot) DO UPDATE SET skill = $3;
+        "#,
+    )
+    .bind(cid)
+    .bind(data.slot)
+    .bind(data.skill)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -1,12 +1,11 @@
-use crate::{containers::Storage, sql::integers::Shifting};
-use itertools::{Itertools, join};
+use crate::sql::integers::Shifting;
 use uuid::Uuid;
 
 use crate::gametypes::*;
 
-use sqlx::FromRow;
+use sqlx::{FromRow, PgPool};
 
-#[derive(Debug, FromRow)]
+#[derive(Debug, PartialEq, FromRow)]
 pub struct PGInventorySlot {
     pub id: i16,
     pub num: i32,
@@ -15,85 +14,66 @@ pub struct PGInventorySlot {
     pub data: [i16; 5],
 }
 
-#[derive(Debug, FromRow)]
+#[derive(Debug, PartialEq, FromRow)]
 pub struct PGInventory {
     pub slot: Vec<PGInventorySlot>,
 }
 
-impl PGInventory {
-    pub fn into_empty(uid: Uuid) -> String {
-        let default_i32 = i32::unshift_signed(&0);
-        let default_i16 = i16::unshift_signed(&0);
-
-        let value_text = join(
-            (0..MAX_INV).map(|index| {
-                format!("('{uid}', {index}, {default_i32}, {default_i16}, 0, '{{0, 0, 0, 0, 0}}')")
-            }),
-            ", ",
-        );
-
-        format!(
-            r#"
-            INSERT INTO public.inventory(uid, id, num, val, level, data)
-            VALUES {value_text};
-            "#
-        )
-    }
-}
-
-pub fn sql_new_inventory(storage: &Storage, uid: Uuid) -> Result<()> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let query = PGInventory::into_empty(uid);
-    local.block_on(&rt, sqlx::query(&query).execute(&storage.pgconn))?;
+/// Creates an empty row for every slot.
+pub async fn sql_new_inventory(pool: &PgPool, cid: Uuid) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO public.inventory(cid, id, num, val, level, data)
+        SELECT $1, id, $2, $3, 0, '{0, 0, 0, 0, 0}'
+        FROM generate_series(0, $4 - 1) AS id;
+        "#,
+    )
+    .bind(cid)
+    .bind(i32::unshift_signed(&0))
+    .bind(i16::unshift_signed(&0))
+    .bind(MAX_INV as i32)
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
 
-pub fn sql_load_inventory(storage: &Storage, account_id: Uuid) -> Result<PGInventory> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let query = format!(
+pub async fn sql_load_inventory(pool: &PgPool, cid: Uuid) -> sqlx::Result<PGInventory> {
+    let slot = sqlx::query_as(
         r#"
         SELECT id, num, val, level, data
         FROM public.inventory
-        WHERE uid = '{account_id}'
+        WHERE cid = $1
         ORDER BY id ASC;
         "#,
-    );
-    let data = PGInventory {
-        slot: local.block_on(&rt, sqlx::query_as(&query).fetch_all(&storage.pgconn))?,
-    };
+    )
+    .bind(cid)
+    .fetch_all(pool)
+    .await?;
 
-    Ok(data)
+    Ok(PGInventory { slot })
 }
 
-pub fn sql_update_inventory_slot(
-    storage: &Storage,
-    uid: Uuid,
-    data: PGInventorySlot,
-) -> Result<()> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let data_str = data
-        .data
-        .iter()
-        .format_with(", ", |elt, f| f(&format_args!("{elt}")))
-        .to_string();
-
-    let query_text = format!(
+pub async fn sql_update_inventory_slot(
+    pool: &PgPool,
+    cid: Uuid,
+    data: &PGInventorySlot,
+) -> sqlx::Result<()> {
+    sqlx::query(
         r#"
         UPDATE public.inventory
-        SET num = {2}, val = {3}, level = {4}, data = '{{{5}}}'
-        WHERE uid = '{0}' AND id = {1};
+        SET num = $3, val = $4, level = $5, data = $6
+        WHERE cid = $1 AND id = $2;
         "#,
-        uid, data.id, data.num, data.val, data.level, data_str
-    );
-
-    local.block_on(&rt, sqlx::query(&query_text).execute(&storage.pgconn))?;
+    )
+    .bind(cid)
+    .bind(data.id)
+    .bind(data.num)
+    .bind(data.val)
+    .bind(data.level)
+    .bind(&data.data[..])
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
@@ -1,80 +1,59 @@
 use uuid::Uuid;
 
-use crate::{containers::Storage, gametypes::*};
+use crate::gametypes::*;
 
-use sqlx::FromRow;
+use sqlx::{FromRow, PgPool};
 
-#[derive(Debug, FromRow)]
+#[derive(Debug, PartialEq, FromRow)]
 pub struct PGLocation {
     pub spawn: Position,
     pub pos: Position,
     pub dir: i16,
 }
 
-impl PGLocation {
-    pub fn into_empty(uid: Uuid) -> String {
-        format!(
-            r#"
-            INSERT INTO public.locations(uid, spawn, pos, dir)
-            VALUES ('{uid}', $1, $2, 0);
-            "#,
-        )
-    }
-}
-
-pub fn sql_new_location(storage: &Storage, uid: Uuid) -> Result<()> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let query = PGLocation::into_empty(uid);
-    local.block_on(
-        &rt,
-        sqlx::query(&query)
-            .bind(Position::default())
-            .bind(Position::default())
-            .execute(&storage.pgconn),
-    )?;
+pub async fn sql_new_location(pool: &PgPool, cid: Uuid) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO public.locations(cid, spawn, pos, dir)
+        VALUES ($1, $2, $3, 0);
+        "#,
+    )
+    .bind(cid)
+    .bind(Position::default())
+    .bind(Position::default())
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
 
-pub fn sql_load_location(storage: &Storage, account_id: Uuid) -> Result<PGLocation> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let query = format!(
+pub async fn sql_load_location(pool: &PgPool, cid: Uuid) -> sqlx::Result<PGLocation> {
+    sqlx::query_as(
         r#"
         SELECT spawn, pos, dir
         FROM public.locations
-        WHERE uid = '{account_id}';
+        WHERE cid = $1;
         "#,
-    );
-    let data: PGLocation =
-        local.block_on(&rt, sqlx::query_as(&query).fetch_one(&storage.pgconn))?;
-
-    Ok(data)
+    )
+    .bind(cid)
+    .fetch_one(pool)
+    .await
 }
 
-pub fn sql_update_location(storage: &Storage, uid: Uuid, data: PGLocation) -> Result<()> {
-    let rt = storage.rt.borrow_mut();
-    let local = storage.local.borrow();
-
-    let query_text = format!(
+pub async fn sql_update_location(pool: &PgPool, cid: Uuid, data: &PGLocation) -> sqlx::Result<()> {
+    sqlx::query(
         r#"
         UPDATE public.locations
-        SET spawn = $1, pos = $2, dir = {1}
-        WHERE uid = '{0}';
+        SET spawn = $2, pos = $3, dir = $4
+        WHERE cid = $1;
         "#,
-        uid, data.dir
-    );
-
-    local.block_on(
-        &rt,
-        sqlx::query(&query_text)
-            .bind(data.spawn)
-            .bind(data.pos)
-            .execute(&storage.pgconn),
-    )?;
+    )
+    .bind(cid)
+    .bind(data.spawn)
+    .bind(data.pos)
+    .bind(data.dir)
+    .execute(pool)
+    .await?;
 
     Ok(())
 }
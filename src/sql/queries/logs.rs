@@ -0,0 +1,45 @@
+use crate::sql::PGLog;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Writes a batch of log rows with a single insert.
+pub async fn sql_insert_logs(pool: &PgPool, logs: &[PGLog]) -> sqlx::Result<()> {
+    if logs.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO public.logs(serverid, userid, logtype, message, ipaddress, logged_on)
+        SELECT * FROM UNNEST($1::smallint[], $2::uuid[], $3::log_type[], $4::text[], $5::text[], $6::timestamptz[]);
+        "#,
+    )
+    .bind(logs.iter().map(|log| log.serverid).collect::<Vec<_>>())
+    .bind(logs.iter().map(|log| log.userid).collect::<Vec<_>>())
+    .bind(logs.iter().map(|log| log.logtype).collect::<Vec<_>>())
+    .bind(logs.iter().map(|log| log.message.as_str()).collect::<Vec<_>>())
+    .bind(logs.iter().map(|log| log.ipaddress.as_str()).collect::<Vec<_>>())
+    .bind(logs.iter().map(|log| log.logged_on).collect::<Vec<_>>())
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Returns the account's newest log rows first. Rows still waiting in the
+/// save worker are not included.
+pub async fn sql_load_logs(pool: &PgPool, userid: Uuid, limit: i64) -> sqlx::Result<Vec<PGLog>> {
+    sqlx::query_as(
+        r#"
+        SELECT serverid, userid, logtype, message, ipaddress, logged_on
+        FROM public.logs
+        WHERE userid = $1
+        ORDER BY logged_on DESC
+        LIMIT $2;
+        "#,
+    )
+    .bind(userid)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
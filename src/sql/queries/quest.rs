@@ -0,0 +1,45 @@
+use uuid::Uuid;
+
+use sqlx::{FromRow, PgPool};
+
+#[derive(Debug, PartialEq, FromRow)]
+pub struct PGQuest {
+    pub quest: i16,
+    pub active: bool,
+    pub progress: Vec<i16>,
+    pub completed: i32,
+}
+
+pub async fn sql_load_quests(pool: &PgPool, cid: Uuid) -> sqlx::Result<Vec<PGQuest>> {
+    sqlx::query_as(
+        r#"
+        SELECT quest, active, progress, completed
+        FROM public.quests
+        WHERE cid = $1
+        ORDER BY quest ASC;
+        "#,
+    )
+    .bind(cid)
+    .fetch_all(pool)
+    .await
+}
+
+/// Saves the quest state, creating the row the first time a quest is accepted.
+pub async fn sql_set_quest(pool: &PgPool, cid: Uuid, data: &PGQuest) -> sqlx::Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO public.quests(cid, quest, active, progress, completed)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (cid, quest) DO UPDATE SET active = $3, progress = $4, completed = $5;
+        "#,
+    )
+    .bind(cid)
+    .bind(data.quest)
+    .bind(data.active)
+    .bind(&data.progress)
+    .bind(data.completed)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use log::error;
+use uuid::Uuid;
+
+use crate::{
+    containers::{IpBans, IpRange, Storage},
+    gametypes::*,
+};
+
+use sqlx::{FromRow, PgPool};
+
+#[derive(Debug, PartialEq, FromRow)]
+pub struct PGSanction {
+    pub id: i64,
+    pub kind: SanctionKind,
+    pub uid: Option<Uuid>,
+    pub ipaddress: Option<String>,
+    pub reason: String,
+    pub issuer: Option<Uuid>,
+    pub created_on: DateTime<Utc>,
+    pub expires_on: Option<DateTime<Utc>>,
+}
+
+/// Who a ban or mute applies to.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub enum SanctionTarget {
+    Account(Uuid),
+    Address(IpRange),
+}
+
+impl SanctionTarget {
+    fn binds(&self) -> (Option<Uuid>, Option<String>) {
+        match self {
+            SanctionTarget::Account(uid) => (Some(*uid), None),
+            SanctionTarget::Address(range) => (None, Some(range.to_string())),
+        }
+    }
+}
+
+/// A ban or mute to add once the target's older ones of the same kind are lifted.
+#[derive(Debug)]
+pub struct NewSanction {
+    pub reason: String,
+    pub issuer: Option<Uuid>,
+    pub expires_on: Option<DateTime<Utc>>,
+}
+
+/// Adds a sanction. An expiry of None makes it permanent. Returns the new row's id.
+pub async fn sql_add_sanction(
+    pool: &PgPool,
+    kind: SanctionKind,
+    target: SanctionTarget,
+    reason: &str,
+    issuer: Option<Uuid>,
+    expires_on: Option<DateTime<Utc>>,
+) -> sqlx::Result<i64> {
+    let (uid, ipaddress) = target.binds();
+
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO public.sanctions(kind, uid, ipaddress, reason, issuer, expires_on)
+        VALUES ($1, $2, $3::inet, $4, $5, $6)
+        RETURNING id;
+        "#,
+    )
+    .bind(kind)
+    .bind(uid)
+    .bind(ipaddress)
+    .bind(reason)
+    .bind(issuer)
+    .bind(expires_on)
+    .fetch_one(pool)
+    .await
+}
+
+/// Ends every active sanction of this kind on the target. The rows are kept as history.
+/// Returns how many were lifted.
+pub async fn sql_lift_sanctions(
+    pool: &PgPool,
+    kind: SanctionKind,
+    target: SanctionTarget,
+) -> sqlx::Result<u64> {
+    let (uid, ipaddress) = target.binds();
+
+    let result = sqlx::query(
+        r#"
+        UPDATE public.sanctions
+        SET expires_on = now()
+        WHERE kind = $1
+            AND (uid = $2 OR ipaddress = $3::inet)
+            AND (expires_on IS NULL OR expires_on > now());
+        "#,
+    )
+    .bind(kind)
+    .bind(uid)
+    .bind(ipaddress)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Finds the longest running active sanction of this kind on the account or on any
+/// range the ip falls within.
+pub async fn sql_active_sanction(
+    pool: &PgPool,
+    kind: SanctionKind,
+    uid: Option<Uuid>,
+    ip: Option<&str>,
+) -> sqlx::Result<Option<PGSanction>> {
+    sqlx::query_as(
+        r#"
+        SELECT id, kind, uid, ipaddress::text AS ipaddress, reason, issuer, created_on, expires_on
+        FROM public.sanctions
+        WHERE kind = $1
+            AND (uid = $2 OR ipaddress >>= $3::inet)
+            AND (expires_on IS NULL OR expires_on > now())
+        ORDER BY expires_on DESC NULLS FIRST
+        LIMIT 1;
+        "#,
+    )
+    .bind(kind)
+    .bind(uid)
+    .bind(ip)
+    .fetch_optional(pool)
+    .await
+}
+
+/// The newest sanctions given to an account, active or not.
+pub async fn sql_load_sanctions(
+    pool: &PgPool,
+    uid: Uuid,
+    limit: i64,
+) -> sqlx::Result<Vec<PGSanction>> {
+    sqlx::query_as(
+        r#"
+        SELECT id, kind, uid, ipaddress::text AS ipaddress, reason, issuer, created_on, expires_on
+        FROM public.sanctions
+        WHERE uid = $1
+        ORDER BY created_on DESC, id DESC
+        LIMIT $2;
+        "#,
+    )
+    .bind(uid)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn sql_load_ip_bans(pool: &PgPool) -> sqlx::Result<Vec<(String, Option<DateTime<Utc>>)>> {
+    sqlx::query_as(
+        r#"
+        SELECT ipaddress::text, expires_on
+        FROM public.sanctions
+        WHERE kind = 'Ban'
+            AND ipaddress IS NOT NULL
+            AND (expires_on IS NULL OR expires_on > now());
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Asks the save worker to reload the ip ban cache once everything queued so far is
+/// written. Done after ip bans change and every minute to pick up bans made by other servers.
+pub fn refresh_ip_bans(storage: &Storage) -> Result<()> {
+    storage.saves.refresh_ip_bans()
+}
+
+/// Swaps in the newest ip ban list the save worker loaded. Called from the game loop.
+pub fn process_ip_bans(storage: &Storage) {
+    let mut newest = None;
+
+    while let Some(result) = storage.saves.next_ip_bans() {
+        match result {
+            Ok(bans) => newest = Some(bans),
+            Err(e) => error!("Failed to refresh ip bans: {e}"),
+        }
+    }
+
+    if let Some(bans) = newest {
+        *storage.ip_bans.borrow_mut() = IpBans(
+            bans.into_iter()
+                .filter_map(|(range, expires)| Some((range.parse().ok()?, expires)))
+                .collect(),
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use crate::gametypes::*;
+use uuid::Uuid;
+
+use sqlx::{FromRow, PgPool};
+
+#[derive(Debug, PartialEq, FromRow)]
+pub struct PGStatusEffect {
+    pub kind: StatusKind,
+    pub power: i32,
+    pub stacks: i16,
+    /// Milliseconds left on the effect when it was saved.
+    pub remaining: i64,
+}
+
+pub async fn sql_load_status_effects(
+    pool: &PgPool,
+    cid: Uuid,
+) -> sqlx::Result<Vec<PGStatusEffect>> {
+    sqlx::query_as(
+        r#"
+        SELECT kind, power, stacks, remaining
+        FROM public.status_effects
+        WHERE cid = $1;
+        "#,
+    )
+    .bind(cid)
+    .fetch_all(pool)
+    .await
+}
+
+/// Replaces the saved effects with the ones still active.
+pub async fn sql_set_status_effects(
+    pool: &PgPool,
+    cid: Uuid,
+    effects: &[PGStatusEffect],
+) -> sqlx::Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        DELETE FROM public.status_effects
+        WHERE cid = $1;
+        "#,
+    )
+    .bind(cid)
+    .execute(&mut *tx)
+    .await?;
+
+    for effect in effects.iter().filter(|effect| effect.remaining > 0) {
+        sqlx::query(
+            r#"
+            INSERT INTO public.status_effects(cid, kind, power, stacks, remaining)
+            VALUES ($1, $2, $3, $4, $5);
+            "#,
+        )
+        .bind(cid)
+        .bind(effect.kind)
+        .bind(effect.power)
+        .bind(effect.stacks)
+        .bind(effect.remaining)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await
+}
@@ -1,3 +1,6 @@
+// These make up the baseline migration in migrations.rs and must not be changed.
+// Schema changes go in a new migration there instead.
+
 #[rustfmt::skip]
 pub const PG_UUID: &str = "
 CREATE OR REPLACE FUNCTION
@@ -224,3 +227,144 @@ pub const STORAGE_SCHEMA_ALTER: &str = "
 ALTER TABLE IF EXISTS public.storage
     OWNER to postgres;
 ";
+
+#[rustfmt::skip]
+pub const GUILD_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS public.guilds
+(
+    uid uuid NOT NULL DEFAULT uuid_generate_v7(),
+    name text COLLATE pg_catalog.\"default\" NOT NULL,
+    tag text COLLATE pg_catalog.\"default\" NOT NULL,
+    motd text COLLATE pg_catalog.\"default\" NOT NULL,
+    created_on timestamp with time zone NOT NULL DEFAULT now(),
+    CONSTRAINT guild_pkey PRIMARY KEY (uid),
+    CONSTRAINT guild_name UNIQUE (name),
+    CONSTRAINT guild_tag UNIQUE (tag)
+)
+
+WITH (
+    FILLFACTOR = 70
+)
+TABLESPACE pg_default;
+";
+
+#[rustfmt::skip]
+pub const GUILD_SCHEMA_ALTER: &str = "
+ALTER TABLE IF EXISTS public.guilds
+    OWNER to server;
+";
+
+#[rustfmt::skip]
+pub const GUILD_RANK_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS public.guild_ranks
+(
+    guild uuid NOT NULL REFERENCES public.guilds (uid) ON DELETE CASCADE,
+    rank smallint NOT NULL,
+    name text COLLATE pg_catalog.\"default\" NOT NULL,
+    permissions integer NOT NULL,
+    CONSTRAINT guild_rank_pkey PRIMARY KEY (guild, rank)
+)
+
+WITH (
+    FILLFACTOR = 70
+)
+TABLESPACE pg_default;
+";
+
+#[rustfmt::skip]
+pub const GUILD_RANK_SCHEMA_ALTER: &str = "
+ALTER TABLE IF EXISTS public.guild_ranks
+    OWNER to server;
+";
+
+#[rustfmt::skip]
+pub const GUILD_MEMBER_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS public.guild_members
+(
+    uid uuid NOT NULL REFERENCES public.account (uid) ON DELETE CASCADE,
+    guild uuid NOT NULL REFERENCES public.guilds (uid) ON DELETE CASCADE,
+    rank smallint NOT NULL,
+    CONSTRAINT guild_member_pkey PRIMARY KEY (uid)
+)
+
+WITH (
+    FILLFACTOR = 70
+)
+TABLESPACE pg_default;
+";
+
+#[rustfmt::skip]
+pub const GUILD_MEMBER_SCHEMA_ALTER: &str = "
+ALTER TABLE IF EXISTS public.guild_members
+    OWNER to server;
+";
+
+#[rustfmt::skip]
+pub const QUEST_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS public.quests
+(
+    uid uuid NOT NULL REFERENCES public.account (uid) ON DELETE CASCADE,
+    quest smallint NOT NULL,
+    active boolean NOT NULL,
+    progress smallint[] NOT NULL,
+    completed integer NOT NULL,
+    CONSTRAINT quest_pkey PRIMARY KEY (uid, quest)
+)
+
+WITH (
+    FILLFACTOR = 70
+)
+TABLESPACE pg_default;
+";
+
+#[rustfmt::skip]
+pub const QUEST_SCHEMA_ALTER: &str = "
+ALTER TABLE IF EXISTS public.quests
+    OWNER to server;
+";
+
+#[rustfmt::skip]
+pub const HOTBAR_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS public.hotbar
+(
+    uid uuid NOT NULL REFERENCES public.account (uid) ON DELETE CASCADE,
+    slot smallint NOT NULL,
+    skill smallint,
+    CONSTRAINT hotbar_pkey PRIMARY KEY (uid, slot)
+)
+
+WITH (
+    FILLFACTOR = 70
+)
+TABLESPACE pg_default;
+";
+
+#[rustfmt::skip]
+pub const HOTBAR_SCHEMA_ALTER: &str = "
+ALTER TABLE IF EXISTS public.hotbar
+    OWNER to server;
+";
+
+#[rustfmt::skip]
+pub const STATUS_EFFECT_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS public.status_effects
+(
+    uid uuid NOT NULL REFERENCES public.account (uid) ON DELETE CASCADE,
+    kind \"status_kind\" NOT NULL,
+    power integer NOT NULL,
+    stacks smallint NOT NULL,
+    remaining bigint NOT NULL,
+    CONSTRAINT status_effects_pkey PRIMARY KEY (uid, kind)
+)
+
+WITH (
+    FILLFACTOR = 70
+)
+TABLESPACE pg_default;
+";
+
+#[rustfmt::skip]
+pub const STATUS_EFFECT_SCHEMA_ALTER: &str = "
+ALTER TABLE IF EXISTS public.status_effects
+    OWNER to server;
+";
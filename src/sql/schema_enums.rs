@@ -2,7 +2,7 @@
 pub const LOGTYPE_SCHEMA: &str = "
 DO $$ BEGIN
     CREATE TYPE public.\"log_type\" AS ENUM
-        ('Login', 'Logout', 'Item', 'Warning', 'Error');
+        ('Login', 'Logout', 'Item', 'Warning', 'Error', 'Command');
 EXCEPTION
     WHEN duplicate_object THEN null;
 END $$;
@@ -14,6 +14,13 @@ ALTER TYPE public.\"log_type\"
     OWNER TO postgres;
 ";
 
+// Added after log_type was first created so existing databases get it too.
+#[rustfmt::skip]
+pub const LOGTYPE_SCHEMA_COMMAND: &str = "
+ALTER TYPE public.\"log_type\"
+    ADD VALUE IF NOT EXISTS 'Command';
+";
+
 #[rustfmt::skip]
 pub const USERACCESS_SCHEMA: &str = "
 DO $$ BEGIN
@@ -29,3 +36,19 @@ pub const USERACCESS_SCHEMA_ALTER: &str = "
 ALTER TYPE public.\"user_access\"
     OWNER TO postgres;
 ";
+
+#[rustfmt::skip]
+pub const STATUSKIND_SCHEMA: &str = "
+DO $$ BEGIN
+    CREATE TYPE public.\"status_kind\" AS ENUM
+        ('Stun', 'Poison', 'Regen', 'Slow', 'DamageBuff', 'DefenseBuff', 'Silence');
+EXCEPTION
+        WHEN duplicate_object THEN null;
+END $$;
+";
+
+#[rustfmt::skip]
+pub const STATUSKIND_SCHEMA_ALTER: &str = "
+ALTER TYPE public.\"status_kind\"
+    OWNER TO postgres;
+";
@@ -1,8 +1,5 @@
 use super::{
-    PGCombat, PGEquipmentSlot, PGGeneral, PGInventorySlot, PGLocation, PGStorageSlot,
-    sql_update_combat, sql_update_equipment_slot, sql_update_general, sql_update_inventory_slot,
-    sql_update_level, sql_update_location, sql_update_money, sql_update_resetcount,
-    sql_update_storage_slot,
+    PGCombat, PGEquipmentSlot, PGGeneral, PGInventorySlot, PGLocation, PGStorageSlot, SaveJob,
 };
 use crate::{
     containers::{Entity, GlobalKey, Storage, World},
@@ -17,46 +14,48 @@ pub fn get_time_left(cur_time: Instant, system_time: Instant) -> i64 {
     cur_timer.saturating_sub(cur_time).max(0)
 }
 
+/// Queues the player's combat, general and location data with the save worker.
 pub fn update_player(storage: &Storage, world: &mut World, entity: GlobalKey) -> Result<()> {
     let tick = *storage.gettick.borrow();
 
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
         let p_data = p_data.try_lock()?;
 
-        sql_update_combat(
-            storage,
-            p_data.account.id,
-            PGCombat {
+        let uid = p_data.account.character;
+
+        storage.saves.save(SaveJob::Combat {
+            uid,
+            data: PGCombat {
                 level: p_data.combat.level,
                 levelexp: i64::unshift_signed(&p_data.general.levelexp),
                 vital: p_data.combat.vitals.vital,
                 vital_max: p_data.combat.vitals.vitalmax,
-                indeath: false,
+                indeath: p_data.combat.death_type.is_dead(),
                 pk: p_data.general.pk,
+                attributes: p_data.attributes.values,
+                stat_points: p_data.attributes.points,
             },
-        )?;
+        })?;
 
-        sql_update_general(
-            storage,
-            p_data.account.id,
-            PGGeneral {
+        storage.saves.save(SaveJob::General {
+            uid,
+            data: PGGeneral {
                 sprite: i16::unshift_signed(&p_data.sprite.id),
                 money: i64::unshift_signed(&p_data.money.vals),
                 resetcount: p_data.general.resetcount,
                 itemtimer: get_time_left(p_data.item_timer.itemtimer, tick),
                 deathtimer: get_time_left(p_data.combat.death_timer.0, tick),
             },
-        )?;
+        })?;
 
-        sql_update_location(
-            storage,
-            p_data.account.id,
-            PGLocation {
+        storage.saves.save(SaveJob::Location {
+            uid,
+            data: PGLocation {
                 spawn: p_data.movement.spawn.pos,
                 pos: p_data.movement.pos,
                 dir: p_data.movement.dir as i16,
             },
-        )?;
+        })?;
     }
 
     Ok(())
@@ -71,20 +70,19 @@ pub fn update_inv(
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
         let p_data = p_data.try_lock()?;
 
-        let uid = p_data.account.id;
+        let uid = p_data.account.character;
 
         if let Some(slot_data) = p_data.inventory.items.get(slot) {
-            sql_update_inventory_slot(
-                storage,
+            storage.saves.save(SaveJob::Inventory {
                 uid,
-                PGInventorySlot {
+                data: PGInventorySlot {
                     id: slot as i16,
                     num: i32::unshift_signed(&slot_data.num),
                     val: i16::unshift_signed(&slot_data.val),
                     level: slot_data.level as i16,
                     data: slot_data.data,
                 },
-            )?;
+            })?;
         }
     }
 
@@ -103,17 +101,16 @@ pub fn update_storage(
         let uid = p_data.account.id;
 
         if let Some(slot_data) = p_data.storage.items.get(slot) {
-            sql_update_storage_slot(
-                storage,
+            storage.saves.save(SaveJob::Storage {
                 uid,
-                PGStorageSlot {
+                data: PGStorageSlot {
                     id: slot as i16,
                     num: i32::unshift_signed(&slot_data.num),
                     val: i16::unshift_signed(&slot_data.val),
                     level: slot_data.level as i16,
                     data: slot_data.data,
                 },
-            )?;
+            })?;
         }
     }
 
@@ -129,20 +126,19 @@ pub fn update_equipment(
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
         let p_data = p_data.try_lock()?;
 
-        let uid = p_data.account.id;
+        let uid = p_data.account.character;
 
         if let Some(slot_data) = p_data.equipment.items.get(slot) {
-            sql_update_equipment_slot(
-                storage,
+            storage.saves.save(SaveJob::Equipment {
                 uid,
-                PGEquipmentSlot {
+                data: PGEquipmentSlot {
                     id: slot as i16,
                     num: i32::unshift_signed(&slot_data.num),
                     val: i16::unshift_signed(&slot_data.val),
                     level: slot_data.level as i16,
                     data: slot_data.data,
                 },
-            )?;
+            })?;
         }
     }
 
@@ -153,15 +149,14 @@ pub fn update_pos(storage: &Storage, world: &mut World, entity: GlobalKey) -> Re
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
         let p_data = p_data.try_lock()?;
 
-        sql_update_location(
-            storage,
-            p_data.account.id,
-            PGLocation {
+        storage.saves.save(SaveJob::Location {
+            uid: p_data.account.character,
+            data: PGLocation {
                 spawn: p_data.movement.spawn.pos,
                 pos: p_data.movement.pos,
                 dir: p_data.movement.dir as i16,
             },
-        )?;
+        })?;
     }
 
     Ok(())
@@ -171,11 +166,10 @@ pub fn update_currency(storage: &Storage, world: &mut World, entity: GlobalKey)
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
         let p_data = p_data.try_lock()?;
 
-        sql_update_money(
-            storage,
-            p_data.account.id,
-            i64::unshift_signed(&p_data.money.vals),
-        )?;
+        storage.saves.save(SaveJob::Money {
+            uid: p_data.account.character,
+            money: i64::unshift_signed(&p_data.money.vals),
+        })?;
     }
 
     Ok(())
@@ -185,17 +179,29 @@ pub fn update_level(storage: &Storage, world: &mut World, entity: GlobalKey) ->
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
         let p_data = p_data.try_lock()?;
 
-        sql_update_level(
-            storage,
-            p_data.account.id,
-            PGCombat {
+        storage.saves.save(SaveJob::Level {
+            uid: p_data.account.character,
+            data: PGCombat {
                 level: p_data.combat.level,
                 levelexp: i64::unshift_signed(&p_data.general.levelexp),
                 vital: p_data.combat.vitals.vital,
                 vital_max: p_data.combat.vitals.vitalmax,
                 ..Default::default()
             },
-        )?;
+        })?;
+    }
+    Ok(())
+}
+
+pub fn update_attributes(storage: &Storage, world: &mut World, entity: GlobalKey) -> Result<()> {
+    if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
+        let p_data = p_data.try_lock()?;
+
+        storage.saves.save(SaveJob::Attributes {
+            uid: p_data.account.character,
+            attributes: p_data.attributes.values,
+            stat_points: p_data.attributes.points,
+        })?;
     }
     Ok(())
 }
@@ -204,7 +210,10 @@ pub fn update_resetcount(storage: &Storage, world: &mut World, entity: GlobalKey
     if let Some(Entity::Player(p_data)) = world.get_opt_entity(entity) {
         let p_data = p_data.try_lock()?;
 
-        sql_update_resetcount(storage, p_data.account.id, p_data.general.resetcount)?;
+        storage.saves.save(SaveJob::ResetCount {
+            uid: p_data.account.character,
+            resetcount: p_data.general.resetcount,
+        })?;
     }
     Ok(())
 }
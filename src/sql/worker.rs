@@ -0,0 +1,510 @@
+use super::{
+    NewSanction, PGCombat, PGEquipmentSlot, PGGeneral, PGHotbarSlot, PGInventorySlot, PGLocation,
+    PGLog, PGQuest, PGStatusEffect, PGStorageSlot, PlayerLoad, SanctionTarget, sql_add_sanction,
+    sql_delete_guild, sql_insert_logs, sql_lift_sanctions, sql_load_ip_bans, sql_load_player,
+    sql_new_guild, sql_remove_guild_member, sql_set_guild_member, sql_set_hotbar_slot,
+    sql_set_quest, sql_set_status_effects, sql_update_account, sql_update_attributes,
+    sql_update_combat, sql_update_equipment_slot, sql_update_general, sql_update_guild_motd,
+    sql_update_inventory_slot, sql_update_level, sql_update_location, sql_update_money,
+    sql_update_resetcount, sql_update_storage_slot,
+};
+use crate::{
+    containers::{IndexMap, Storage, UserAccess, socket_ip},
+    gametypes::*,
+};
+use chrono::{DateTime, Utc};
+use log::{error, warn};
+use sqlx::PgPool;
+use std::{
+    sync::{
+        Arc,
+        mpsc::{self, Receiver, Sender},
+    },
+    time::Duration,
+};
+use tokio::{
+    runtime::Runtime,
+    sync::{mpsc as async_mpsc, oneshot},
+};
+use uuid::Uuid;
+
+/// How many times a write is retried after a connection or deadlock error.
+const SAVE_RETRY_LIMIT: u32 = 5;
+/// Wait before the first retry. Doubled after every failed attempt.
+const SAVE_RETRY_DELAY_MS: u64 = 100;
+
+/// A single write for the save worker. uid is the character the data belongs to
+/// except for Account and Storage where it is the account and GuildMotd, NewGuild and
+/// DeleteGuild where it is the guild.
+#[derive(Debug)]
+pub enum SaveJob {
+    Account {
+        uid: Uuid,
+        user_access: UserAccess,
+    },
+    General {
+        uid: Uuid,
+        data: PGGeneral,
+    },
+    Combat {
+        uid: Uuid,
+        data: PGCombat,
+    },
+    Level {
+        uid: Uuid,
+        data: PGCombat,
+    },
+    Attributes {
+        uid: Uuid,
+        attributes: [i32; ATTRIBUTES_MAX],
+        stat_points: i32,
+    },
+    Money {
+        uid: Uuid,
+        money: i64,
+    },
+    ResetCount {
+        uid: Uuid,
+        resetcount: i16,
+    },
+    Location {
+        uid: Uuid,
+        data: PGLocation,
+    },
+    Inventory {
+        uid: Uuid,
+        data: PGInventorySlot,
+    },
+    Storage {
+        uid: Uuid,
+        data: PGStorageSlot,
+    },
+    Equipment {
+        uid: Uuid,
+        data: PGEquipmentSlot,
+    },
+    Quest {
+        uid: Uuid,
+        data: PGQuest,
+    },
+    Hotbar {
+        uid: Uuid,
+        data: PGHotbarSlot,
+    },
+    StatusEffects {
+        uid: Uuid,
+        effects: Vec<PGStatusEffect>,
+    },
+    GuildMotd {
+        uid: Uuid,
+        motd: String,
+    },
+    /// A new guild with the default ranks. Its leader is added with a GuildMember job.
+    NewGuild {
+        uid: Uuid,
+        name: String,
+        tag: String,
+    },
+    /// Removes the guild along with its ranks and members.
+    DeleteGuild {
+        uid: Uuid,
+    },
+    /// Puts the character in a guild at a rank, or takes them out of theirs with None.
+    GuildMember {
+        uid: Uuid,
+        guild: Option<(Uuid, i16)>,
+    },
+    /// Audit log rows. Queued batches are joined into one insert instead of replaced.
+    Logs {
+        logs: Vec<PGLog>,
+    },
+    /// Lifts the target's active sanctions of this kind then adds sanction if there is one.
+    Sanction {
+        kind: SanctionKind,
+        target: SanctionTarget,
+        sanction: Option<NewSanction>,
+    },
+}
+
+/// Queued jobs with the same key write to the same row so only the newest is kept.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+enum SaveKey {
+    Account(Uuid),
+    General(Uuid),
+    Combat(Uuid),
+    Level(Uuid),
+    Attributes(Uuid),
+    Money(Uuid),
+    ResetCount(Uuid),
+    Location(Uuid),
+    Inventory(Uuid, i16),
+    Storage(Uuid, i16),
+    Equipment(Uuid, i16),
+    Quest(Uuid, i16),
+    Hotbar(Uuid, i16),
+    StatusEffects(Uuid),
+    GuildMotd(Uuid),
+    NewGuild(Uuid),
+    DeleteGuild(Uuid),
+    GuildMember(Uuid),
+    Logs,
+    Sanction(SanctionKind, SanctionTarget),
+}
+
+impl SaveKey {
+    /// The account or character the row belongs to.
+    fn owner(&self) -> Option<Uuid> {
+        match *self {
+            SaveKey::Account(uid)
+            | SaveKey::General(uid)
+            | SaveKey::Combat(uid)
+            | SaveKey::Level(uid)
+            | SaveKey::Attributes(uid)
+            | SaveKey::Money(uid)
+            | SaveKey::ResetCount(uid)
+            | SaveKey::Location(uid)
+            | SaveKey::Inventory(uid, _)
+            | SaveKey::Storage(uid, _)
+            | SaveKey::Equipment(uid, _)
+            | SaveKey::Quest(uid, _)
+            | SaveKey::Hotbar(uid, _)
+            | SaveKey::StatusEffects(uid)
+            | SaveKey::GuildMember(uid)
+            | SaveKey::Sanction(_, SanctionTarget::Account(uid)) => Some(uid),
+            SaveKey::GuildMotd(_)
+            | SaveKey::NewGuild(_)
+            | SaveKey::DeleteGuild(_)
+            | SaveKey::Logs
+            | SaveKey::Sanction(..) => None,
+        }
+    }
+}
+
+impl SaveJob {
+    fn key(&self) -> SaveKey {
+        match self {
+            SaveJob::Account { uid, .. } => SaveKey::Account(*uid),
+            SaveJob::General { uid, .. } => SaveKey::General(*uid),
+            SaveJob::Combat { uid, .. } => SaveKey::Combat(*uid),
+            SaveJob::Level { uid, .. } => SaveKey::Level(*uid),
+            SaveJob::Attributes { uid, .. } => SaveKey::Attributes(*uid),
+            SaveJob::Money { uid, .. } => SaveKey::Money(*uid),
+            SaveJob::ResetCount { uid, .. } => SaveKey::ResetCount(*uid),
+            SaveJob::Location { uid, .. } => SaveKey::Location(*uid),
+            SaveJob::Inventory { uid, data } => SaveKey::Inventory(*uid, data.id),
+            SaveJob::Storage { uid, data } => SaveKey::Storage(*uid, data.id),
+            SaveJob::Equipment { uid, data } => SaveKey::Equipment(*uid, data.id),
+            SaveJob::Quest { uid, data } => SaveKey::Quest(*uid, data.quest),
+            SaveJob::Hotbar { uid, data } => SaveKey::Hotbar(*uid, data.slot),
+            SaveJob::StatusEffects { uid, .. } => SaveKey::StatusEffects(*uid),
+            SaveJob::GuildMotd { uid, .. } => SaveKey::GuildMotd(*uid),
+            SaveJob::NewGuild { uid, .. } => SaveKey::NewGuild(*uid),
+            SaveJob::DeleteGuild { uid } => SaveKey::DeleteGuild(*uid),
+            SaveJob::GuildMember { uid, .. } => SaveKey::GuildMember(*uid),
+            SaveJob::Logs { .. } => SaveKey::Logs,
+            SaveJob::Sanction { kind, target, .. } => SaveKey::Sanction(*kind, *target),
+        }
+    }
+
+    async fn execute(&self, pool: &PgPool) -> sqlx::Result<()> {
+        match self {
+            SaveJob::Account { uid, user_access } => {
+                sql_update_account(pool, *uid, *user_access).await
+            }
+            SaveJob::General { uid, data } => sql_update_general(pool, *uid, data).await,
+            SaveJob::Combat { uid, data } => sql_update_combat(pool, *uid, data).await,
+            SaveJob::Level { uid, data } => sql_update_level(pool, *uid, data).await,
+            SaveJob::Attributes {
+                uid,
+                attributes,
+                stat_points,
+            } => sql_update_attributes(pool, *uid, attributes, *stat_points).await,
+            SaveJob::Money { uid, money } => sql_update_money(pool, *uid, *money).await,
+            SaveJob::ResetCount { uid, resetcount } => {
+                sql_update_resetcount(pool, *uid, *resetcount).await
+            }
+            SaveJob::Location { uid, data } => sql_update_location(pool, *uid, data).await,
+            SaveJob::Inventory { uid, data } => sql_update_inventory_slot(pool, *uid, data).await,
+            SaveJob::Storage { uid, data } => sql_update_storage_slot(pool, *uid, data).await,
+            SaveJob::Equipment { uid, data } => sql_update_equipment_slot(pool, *uid, data).await,
+            SaveJob::Quest { uid, data } => sql_set_quest(pool, *uid, data).await,
+            SaveJob::Hotbar { uid, data } => sql_set_hotbar_slot(pool, *uid, data).await,
+            SaveJob::StatusEffects { uid, effects } => {
+                sql_set_status_effects(pool, *uid, effects).await
+            }
+            SaveJob::GuildMotd { uid, motd } => sql_update_guild_motd(pool, *uid, motd).await,
+            SaveJob::NewGuild { uid, name, tag } => sql_new_guild(pool, *uid, name, tag).await,
+            SaveJob::DeleteGuild { uid } => sql_delete_guild(pool, *uid).await,
+            SaveJob::GuildMember {
+                uid,
+                guild: Some((guild, rank)),
+            } => sql_set_guild_member(pool, *uid, *guild, *rank).await,
+            SaveJob::GuildMember { uid, guild: None } => sql_remove_guild_member(pool, *uid).await,
+            SaveJob::Logs { logs } => sql_insert_logs(pool, logs).await,
+            SaveJob::Sanction {
+                kind,
+                target,
+                sanction,
+            } => {
+                sql_lift_sanctions(pool, *kind, *target).await?;
+
+                if let Some(sanction) = sanction {
+                    sql_add_sanction(
+                        pool,
+                        *kind,
+                        *target,
+                        &sanction.reason,
+                        sanction.issuer,
+                        sanction.expires_on,
+                    )
+                    .await?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+enum SaveMessage {
+    Job(SaveJob),
+    Flush(oneshot::Sender<()>),
+    Load(LoadRequest),
+    RefreshIpBans,
+}
+
+/// Every active ip ban as the range text and when it ends.
+pub type IpBanRows = Vec<(String, Option<DateTime<Utc>>)>;
+
+/// A character to read back once its queued writes are saved. addr is the client's own
+/// address so a result for a socket id that has since been reused can be told apart.
+pub struct LoadRequest {
+    pub socket_id: usize,
+    pub addr: Arc<String>,
+    pub uid: Uuid,
+    pub cid: Uuid,
+}
+
+pub struct LoadResult {
+    pub socket_id: usize,
+    pub addr: Arc<String>,
+    pub uid: Uuid,
+    pub cid: Uuid,
+    pub data: sqlx::Result<PlayerLoad>,
+}
+
+/// A write which could not be saved even after retrying.
+#[derive(Debug)]
+pub struct SaveFailure {
+    pub job: SaveJob,
+    pub error: sqlx::Error,
+}
+
+/// Sends writes to a task on the tokio runtime so the game loop never waits on Postgres.
+pub struct SaveWorker {
+    sender: async_mpsc::UnboundedSender<SaveMessage>,
+    failures: Receiver<SaveFailure>,
+    loads: Receiver<LoadResult>,
+    ip_bans: Receiver<sqlx::Result<IpBanRows>>,
+}
+
+impl SaveWorker {
+    pub fn spawn(rt: &Runtime, pool: PgPool) -> Self {
+        let (sender, receiver) = async_mpsc::unbounded_channel();
+        let (failure_sender, failures) = mpsc::channel();
+        let (load_sender, loads) = mpsc::channel();
+        let (ip_ban_sender, ip_bans) = mpsc::channel();
+
+        rt.spawn(save_worker(
+            pool,
+            receiver,
+            failure_sender,
+            load_sender,
+            ip_ban_sender,
+        ));
+
+        Self {
+            sender,
+            failures,
+            loads,
+            ip_bans,
+        }
+    }
+
+    pub fn save(&self, job: SaveJob) -> Result<()> {
+        self.sender
+            .send(SaveMessage::Job(job))
+            .map_err(|_| AscendingError::SaveWorkerClosed)
+    }
+
+    /// Reads a character back after its queued writes so a quick relog never sees older
+    /// data. The result is picked up with next_load.
+    pub fn load(&self, request: LoadRequest) -> Result<()> {
+        self.sender
+            .send(SaveMessage::Load(request))
+            .map_err(|_| AscendingError::SaveWorkerClosed)
+    }
+
+    pub fn next_load(&self) -> Option<LoadResult> {
+        self.loads.try_recv().ok()
+    }
+
+    /// Reloads the active ip bans after the queued writes so a ban that was just made
+    /// is never missed. The result is picked up with next_ip_bans.
+    pub fn refresh_ip_bans(&self) -> Result<()> {
+        self.sender
+            .send(SaveMessage::RefreshIpBans)
+            .map_err(|_| AscendingError::SaveWorkerClosed)
+    }
+
+    pub fn next_ip_bans(&self) -> Option<sqlx::Result<IpBanRows>> {
+        self.ip_bans.try_recv().ok()
+    }
+
+    /// Blocks until everything queued so far has been written.
+    pub fn flush(&self) -> Result<()> {
+        let (done, wait) = oneshot::channel();
+
+        self.sender
+            .send(SaveMessage::Flush(done))
+            .map_err(|_| AscendingError::SaveWorkerClosed)?;
+
+        wait.blocking_recv()
+            .map_err(|_| AscendingError::SaveWorkerClosed)
+    }
+}
+
+/// Errors where trying the same write again later could work.
+fn is_transient(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::WorkerCrashed => true,
+        // 08 is the connection exception class, 40001 serialization failure,
+        // 40P01 deadlock and 57P01 the server being shut down by an admin.
+        sqlx::Error::Database(e) => e.code().is_some_and(|code| {
+            code.starts_with("08") || matches!(&*code, "40001" | "40P01" | "57P01")
+        }),
+        _ => false,
+    }
+}
+
+async fn run_job(pool: &PgPool, job: &SaveJob) -> sqlx::Result<()> {
+    let mut delay = SAVE_RETRY_DELAY_MS;
+    let mut attempt = 0;
+
+    loop {
+        match job.execute(pool).await {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < SAVE_RETRY_LIMIT && is_transient(&e) => {
+                warn!("Save of {:?} failed, retrying in {delay}ms: {e}", job.key());
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+async fn save_worker(
+    pool: PgPool,
+    mut receiver: async_mpsc::UnboundedReceiver<SaveMessage>,
+    failures: Sender<SaveFailure>,
+    loads: Sender<LoadResult>,
+    ip_bans: Sender<sqlx::Result<IpBanRows>>,
+) {
+    let mut pending: IndexMap<SaveKey, SaveJob> = IndexMap::default();
+    let mut flushes = Vec::new();
+    let mut refresh_ip_bans = false;
+
+    while let Some(message) = receiver.recv().await {
+        let mut next = Some(message);
+
+        // Take everything already queued so repeated writes to a row collapse into one.
+        while let Some(message) = next {
+            match message {
+                SaveMessage::Job(job) => {
+                    let key = job.key();
+
+                    match (job, pending.get_mut(&key)) {
+                        (SaveJob::Logs { logs }, Some(SaveJob::Logs { logs: queued })) => {
+                            queued.extend(logs);
+                        }
+                        (job, _) => {
+                            // Moved to the back so overlapping writes like Level and Combat stay in order.
+                            pending.shift_remove(&key);
+                            pending.insert(key, job);
+                        }
+                    }
+                }
+                SaveMessage::Flush(done) => flushes.push(done),
+                SaveMessage::RefreshIpBans => refresh_ip_bans = true,
+                SaveMessage::Load(request) => {
+                    // This account's and character's writes have to land first. Whatever was
+                    // queued before them goes too, like the guild a new membership points at.
+                    let last = pending.keys().rposition(|key| {
+                        key.owner()
+                            .is_some_and(|owner| owner == request.uid || owner == request.cid)
+                    });
+
+                    if let Some(last) = last {
+                        for (_, job) in pending.drain(..=last) {
+                            if let Err(error) = run_job(&pool, &job).await {
+                                let _ = failures.send(SaveFailure { job, error });
+                            }
+                        }
+                    }
+
+                    let pool = pool.clone();
+                    let loads = loads.clone();
+
+                    tokio::spawn(async move {
+                        let LoadRequest {
+                            socket_id,
+                            addr,
+                            uid,
+                            cid,
+                        } = request;
+                        let ip = socket_ip(&addr).map(|ip| ip.to_string());
+                        let data = sql_load_player(&pool, uid, cid, ip).await;
+
+                        let _ = loads.send(LoadResult {
+                            socket_id,
+                            addr,
+                            uid,
+                            cid,
+                            data,
+                        });
+                    });
+                }
+            }
+
+            next = receiver.try_recv().ok();
+        }
+
+        for (_, job) in pending.drain(..) {
+            if let Err(error) = run_job(&pool, &job).await {
+                let _ = failures.send(SaveFailure { job, error });
+            }
+        }
+
+        // Loaded before flushes are answered so a flush also waits for the new list.
+        if refresh_ip_bans {
+            refresh_ip_bans = false;
+            let _ = ip_bans.send(sql_load_ip_bans(&pool).await);
+        }
+
+        for done in flushes.drain(..) {
+            let _ = done.send(());
+        }
+    }
+}
+
+/// Logs any writes the worker gave up on. Called from the game loop.
+pub fn process_save_failures(storage: &Storage) {
+    while let Ok(failure) = storage.saves.failures.try_recv() {
+        error!(
+            "Failed to save {:?}, the data will be lost: {}",
+            failure.job, failure.error
+        );
+    }
+}
@@ -1,9 +1,11 @@
 use crate::{
-    containers::{DeathType, Entity, GlobalKey, UserAccess, World},
+    containers::{DeathType, Entity, GlobalKey, StatusEffects, UserAccess, World},
     gametypes::*,
     items::*,
     socket::*,
+    sql::get_time_left,
 };
+use time::Instant;
 
 pub fn move_packet(
     entity: GlobalKey,
@@ -99,6 +101,7 @@ pub fn player_spawn_packet(
             .write(p_data.equipment.clone())? //85
             .write(p_data.general.pk)?
             .write(p_data.general.pvpon)?
+            .write(p_data.guild.tag())?
             .write(did_spawn)?;
 
         Ok(buffer)
@@ -188,3 +191,30 @@ pub fn attack_packet(entity: GlobalKey) -> Result<MByteBuffer> {
 
     Ok(buffer)
 }
+
+pub fn cast_packet(entity: GlobalKey, skill: u16, pos: Position) -> Result<MByteBuffer> {
+    let mut buffer = MByteBuffer::new()?;
+    buffer.write(entity)?.write(skill)?.write(pos)?;
+
+    Ok(buffer)
+}
+
+/// Sends every active effect so the client can replace what it has for the entity.
+pub fn status_effects_packet(
+    entity: GlobalKey,
+    effects: &StatusEffects,
+    tick: Instant,
+) -> Result<MByteBuffer> {
+    let mut buffer = MByteBuffer::new()?;
+    buffer.write(entity)?.write(effects.0.len() as u32)?;
+
+    for effect in &effects.0 {
+        buffer
+            .write(effect.kind)?
+            .write(effect.power)?
+            .write(effect.stacks)?
+            .write(get_time_left(effect.expires, tick))?;
+    }
+
+    Ok(buffer)
+}
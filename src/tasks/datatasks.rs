@@ -25,6 +25,8 @@ pub enum DataTaskToken {
     PlayerSpawn(MapPosition),
     PlayerLevel(MapPosition),
     Vitals(MapPosition),
+    Cast(MapPosition),
+    StatusEffects(MapPosition),
     MapChat(MapPosition),
     ItemLoad(MapPosition),
     EntityUnload(MapPosition),
@@ -89,6 +91,8 @@ impl DataTaskToken {
             Death(_) => ServerPackets::Death,
             Attack(_) => ServerPackets::Attack,
             Vitals(_) => ServerPackets::Vitals,
+            Cast(_) => ServerPackets::Cast,
+            StatusEffects(_) => ServerPackets::StatusEffects,
             EntityUnload(_) => ServerPackets::EntityUnload,
             NpcSpawn(_) | NpcSpawnToEntity(_) => ServerPackets::NpcData,
             PlayerSpawn(_) | PlayerSpawnToEntity(_) => ServerPackets::PlayerSpawn,
@@ -104,11 +108,21 @@ impl DataTaskToken {
         use DataTaskToken::*;
         match self {
             GlobalChat => send_to_all(world, storage, buf),
-            Move(mappos) | Warp(mappos) | Death(mappos) | Dir(mappos) | EntityUnload(mappos)
-            | Attack(mappos) | NpcSpawn(mappos) | PlayerSpawn(mappos) | MapChat(mappos)
-            | ItemLoad(mappos) | Vitals(mappos) | PlayerLevel(mappos) | Damage(mappos) => {
-                send_to_maps(world, storage, *mappos, buf, None)
-            }
+            Move(mappos)
+            | Warp(mappos)
+            | Death(mappos)
+            | Dir(mappos)
+            | EntityUnload(mappos)
+            | Attack(mappos)
+            | NpcSpawn(mappos)
+            | PlayerSpawn(mappos)
+            | MapChat(mappos)
+            | ItemLoad(mappos)
+            | Vitals(mappos)
+            | PlayerLevel(mappos)
+            | Damage(mappos)
+            | Cast(mappos)
+            | StatusEffects(mappos) => send_to_maps(world, storage, *mappos, buf, None),
             PlayerSpawnToEntity(socket_id)
             | NpcSpawnToEntity(socket_id)
             | ItemLoadToEntity(socket_id) => send_to(storage, *socket_id, buf),